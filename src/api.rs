@@ -0,0 +1,287 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+//! The compile pipeline as a library: load a [`Context`], scan its trees into a
+//! [`Workspace`], parse them into [`UnresolvedSections`], link those into a
+//! [`CompileState`], then render single sections or write the whole site.
+//!
+//! ```no_run
+//! use kodama::{BuildMode, CompileOutputs, Context};
+//!
+//! let context = Context::load("Kodama.toml", BuildMode::Publish)?;
+//! let workspace = context.scan()?;
+//! let shallows = context.parse(&workspace)?;
+//! let state = context.compile(&shallows)?;
+//! for section in state.compiled().values() {
+//!     let html = context.render(section, &state)?;
+//!     # let _ = html;
+//! }
+//! context.write(&shallows, &state, CompileOutputs::default())?;
+//! # Ok::<(), eyre::Report>(())
+//! ```
+//!
+//! # Concurrency
+//!
+//! The compiler reads its configuration from one process-wide environment.
+//! Each [`Context`] call installs its own for the length of the call, so calls
+//! are serialized across the whole process: two contexts never compile at the
+//! same time, and a call on one thread waits while another thread is inside a
+//! call. Use separate processes to build several sites in parallel.
+
+use std::sync::Arc;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use eyre::{eyre, WrapErr};
+
+use crate::{
+    cli::build,
+    compiler::{self, writer::Writer},
+    environment::{self, Environment},
+    vfs::{DiskFs, FileSystem},
+};
+
+pub use crate::{
+    compiler::{
+        section::{Section, UnresolvedSection},
+        state::CompileState,
+        CompileOutputs, UnresolvedSections, Workspace,
+    },
+    config::{overrides::ConfigOverrides, parse_config, Config},
+    environment::BuildMode,
+    slug::Slug,
+};
+
+/// A project root, its configuration, a build mode and the file system the
+/// project lives in.
+///
+/// Every call installs this context as the process-wide environment for its
+/// duration, so several contexts can be used in one process, but their calls
+/// are serialized; see [Concurrency](self#concurrency). A context may be used
+/// from inside a call of another on the same thread, and the outer context is
+/// restored when it returns.
+///
+/// The build options of [`Context::write`] are reset by every write. The
+/// in-memory session behind [`BuildMode::Serve`] writes is shared by all
+/// contexts of the process.
+pub struct Context {
+    environment: Arc<Environment>,
+    fs: Arc<dyn FileSystem>,
+}
+
+impl Context {
+    /// Read a configuration file. Like the CLI, a missing file is searched for
+    /// in parent directories, and its directory becomes the project root.
+    pub fn load(config_path: impl Into<Utf8PathBuf>, mode: BuildMode) -> eyre::Result<Context> {
+        let config_path = config_path.into();
+        let environment = environment::load_environment(config_path.clone(), mode)
+            .wrap_err_with(|| eyre!("failed to load config `{}`", config_path))?;
+        Ok(Context::from_environment(environment))
+    }
+
+    /// Like [`Context::load`], with a profile and key overrides merged over
+    /// the configuration file.
+    pub fn load_with_overrides(
+        config_path: impl Into<Utf8PathBuf>,
+        mode: BuildMode,
+        overrides: &ConfigOverrides,
+    ) -> eyre::Result<Context> {
+        let config_path = config_path.into();
+        let environment = environment::load_environment_with(config_path.clone(), mode, overrides)
+            .wrap_err_with(|| eyre!("failed to load config `{}`", config_path))?;
+        Ok(Context::from_environment(environment))
+    }
+
+    /// A context for a configuration built in code, rooted at `root`.
+    pub fn new(root: impl Into<Utf8PathBuf>, config: Config, mode: BuildMode) -> Context {
+        Context::with_file_system(root, config, mode, Arc::new(DiskFs))
+    }
+
+    /// Like [`Context::new`], but sources are read and outputs written through
+    /// `fs`, e.g. a [`crate::vfs::MemoryFs`]. Typst sources still need the disk.
+    pub fn with_file_system(
+        root: impl Into<Utf8PathBuf>,
+        config: Config,
+        mode: BuildMode,
+        fs: Arc<dyn FileSystem>,
+    ) -> Context {
+        let root = root.into();
+        let environment = Environment {
+            config_file: root.join(crate::config::DEFAULT_CONFIG_PATH),
+            root,
+            config,
+            build_mode: mode,
+        };
+        Context {
+            environment: Arc::new(environment),
+            fs,
+        }
+    }
+
+    fn from_environment(environment: Environment) -> Context {
+        Context {
+            environment: Arc::new(environment),
+            fs: Arc::new(DiskFs),
+        }
+    }
+
+    pub fn root(&self) -> &Utf8Path {
+        &self.environment.root
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.environment.config
+    }
+
+    pub fn build_mode(&self) -> BuildMode {
+        self.environment.build_mode
+    }
+
+    pub fn file_system(&self) -> &Arc<dyn FileSystem> {
+        &self.fs
+    }
+
+    /// Find the section sources in the trees directory and its mounts.
+    pub fn scan(&self) -> eyre::Result<Workspace> {
+        self.run(|| {
+            let trees_dir = environment::trees_dir();
+            compiler::all_trees_source(&trees_dir)
+                .wrap_err_with(|| eyre!("failed to scan trees dir `{}`", trees_dir))
+        })
+    }
+
+    /// Parse every source through the entry cache and run the configured plugins.
+    pub fn parse(&self, workspace: &Workspace) -> eyre::Result<UnresolvedSections> {
+        self.run(|| {
+            environment::ensure_cache_version()?;
+            compiler::parse_workspace(workspace)
+        })
+    }
+
+    /// Resolve embeds and links into compiled sections with their parents,
+    /// references and backlinks.
+    pub fn compile(&self, shallows: &UnresolvedSections) -> eyre::Result<CompileState> {
+        self.run(|| compiler::state::compile_all(shallows))
+    }
+
+    /// The full HTML page of a compiled section.
+    pub fn render(&self, section: &Section, state: &CompileState) -> eyre::Result<String> {
+        self.run(|| Writer::html_doc(section, state).map(|(html, _)| html))
+    }
+
+    /// Write the site like `kodama build` from `shallows` and the `state`
+    /// compiled from them: every page, the runtime files, the assets and the
+    /// selected JSON artifacts. Nothing is parsed or compiled again.
+    pub fn write(
+        &self,
+        shallows: &UnresolvedSections,
+        state: &CompileState,
+        outputs: CompileOutputs,
+    ) -> eyre::Result<()> {
+        self.run(|| build::write_compiled(self.build_mode(), shallows, state, outputs))
+    }
+
+    fn run<R>(&self, f: impl FnOnce() -> R) -> R {
+        environment::with_scoped_environment(&self.environment, &self.fs, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{entry::MetaData, vfs::MemoryFs};
+
+    #[test]
+    fn test_context_runs_the_pipeline() {
+        let root = crate::test_io::case_dir("api");
+        let trees = root.join("trees");
+        fs::create_dir_all(trees.as_std_path()).unwrap();
+        fs::write(root.join("Kodama.toml").as_std_path(), "").unwrap();
+        fs::write(
+            trees.join("index.md").as_std_path(),
+            "---\ntitle: Home\n---\n\n[Note](/note.md)\n",
+        )
+        .unwrap();
+        fs::write(
+            trees.join("note.md").as_std_path(),
+            "---\ntitle: Note\n---\n\nHello.\n",
+        )
+        .unwrap();
+
+        let context = Context::load(root.join("Kodama.toml"), BuildMode::Publish).unwrap();
+        assert_eq!(context.root(), root);
+
+        let workspace = context.scan().unwrap();
+        assert_eq!(workspace.slug_exts.len(), 2);
+        let shallows = context.parse(&workspace).unwrap();
+        let state = context.compile(&shallows).unwrap();
+        let note = &state.compiled()[&Slug::new("note")];
+        assert_eq!(note.metadata.title().map(String::as_str), Some("Note"));
+        assert!(state.callback().0[&Slug::new("note")]
+            .backlinks
+            .contains(&Slug::new("index")));
+
+        let html = context.render(note, &state).unwrap();
+        assert!(html.contains("<title>Note</title>"), "{html}");
+
+        context.write(&shallows, &state, CompileOutputs::default()).unwrap();
+        let output = root.join("publish");
+        assert!(output.join("index.html").exists());
+        assert!(output.join("note.html").exists());
+        assert!(output.join("kodama.graph.json").exists());
+
+        let _ = fs::remove_dir_all(root.as_std_path());
+    }
+
+    #[test]
+    fn test_context_calls_nest_on_one_thread() {
+        let outer = Context::new("outer", Config::default(), BuildMode::Publish);
+        let inner = Context::new("inner", Config::default(), BuildMode::Check);
+        outer.run(|| {
+            assert_eq!(environment::root_dir(), "outer");
+            inner.run(|| {
+                assert_eq!(environment::root_dir(), "inner");
+                assert!(environment::is_check());
+            });
+            assert_eq!(environment::root_dir(), "outer");
+            assert!(environment::is_publish());
+        });
+    }
+
+    #[test]
+    fn test_context_compiles_a_forest_in_memory() {
+        let root = crate::test_io::case_dir("api-memory");
+        let fs = Arc::new(MemoryFs::new());
+        fs.insert(
+            root.join("trees/index.md"),
+            "---\ntitle: Home\n---\n\n[Draft](/draft.md)\n",
+        );
+        fs.insert(
+            root.join("trees/draft.md"),
+            "---\ntitle: Unsaved buffer\n---\n\nNot on disk.\n",
+        );
+        fs.insert(root.join("assets/logo.svg"), "<svg/>");
+
+        let config = parse_config("[build]\ntimestamps = \"none\"\n").unwrap();
+        let context = Context::with_file_system(&root, config, BuildMode::Publish, fs.clone());
+        let workspace = context.scan().unwrap();
+        let shallows = context.parse(&workspace).unwrap();
+        let state = context.compile(&shallows).unwrap();
+        let draft = &state.compiled()[&Slug::new("draft")];
+        assert_eq!(
+            draft.metadata.title().map(String::as_str),
+            Some("Unsaved buffer")
+        );
+
+        context.write(&shallows, &state, CompileOutputs::default()).unwrap();
+        let output = root.join("publish");
+        let html = fs.read_to_string(&output.join("draft.html")).unwrap();
+        assert!(html.contains("Not on disk."), "{html}");
+        assert!(fs.is_file(&output.join("index.html")));
+        assert!(fs.is_file(&output.join("assets/logo.svg")));
+        assert!(fs.is_dir(&root.join(".cache/entry")));
+        assert!(!root.exists());
+    }
+}
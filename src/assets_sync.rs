@@ -3,11 +3,11 @@
 // Authors: Kokic (@kokic), Spore (@s-cerevisiae)
 
 use std::collections::HashSet;
-use std::fs::{self};
 
 use camino::{Utf8Path, Utf8PathBuf};
 use eyre::eyre;
-use walkdir::WalkDir;
+
+use crate::{environment, vfs};
 
 /// Synchronizes files from source directory to target directory recursively based on modification time.
 /// It copies changed files from source to target and removes stale files in target.
@@ -16,34 +16,27 @@ use walkdir::WalkDir;
 pub fn sync_assets<P: AsRef<Utf8Path>>(source: P, target: P) -> eyre::Result<bool> {
     let source_path = source.as_ref();
     let target_path = target.as_ref();
+    let fs = environment::fs();
 
-    if !source_path.exists() {
-        if target_path.exists() {
-            fs::remove_dir_all(target_path)?;
+    if !fs.exists(source_path) {
+        if fs.exists(target_path) {
+            fs.remove_dir_all(target_path)?;
             return Ok(true);
         }
         return Ok(false);
     }
 
     // Ensure target directory exists
-    if !target_path.exists() {
-        fs::create_dir_all(target_path)?;
-    } else if !target_path.is_dir() {
+    if !fs.exists(target_path) {
+        fs.create_dir_all(target_path)?;
+    } else if !fs.is_dir(target_path) {
         return Err(eyre!("target path is not a directory: {}", target_path));
     }
 
     let mut changed = false;
     let mut source_files: HashSet<Utf8PathBuf> = HashSet::new();
 
-    let walkdir = WalkDir::new(source_path);
-    for source_file_path in walkdir.into_iter().filter_map(|e| {
-        e.ok()
-            .and_then(|e| Utf8PathBuf::from_path_buf(e.into_path()).ok())
-    }) {
-        if !source_file_path.is_file() {
-            continue;
-        }
-
+    for source_file_path in vfs::walk_files(fs.as_ref(), source_path)? {
         let relative_path = source_file_path
             .strip_prefix(source_path)
             .map_err(|_| eyre::eyre!("failed to compute relative path for {}", source_file_path))?;
@@ -51,56 +44,45 @@ pub fn sync_assets<P: AsRef<Utf8Path>>(source: P, target: P) -> eyre::Result<boo
 
         let target_file_path = target_path.join(relative_path);
 
-        if let Some(parent) = target_file_path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-
-        let source_metadata = source_file_path.metadata()?;
-        let source_mtime = source_metadata.modified()?;
-
-        let should_copy = if target_file_path.exists() {
-            let target_metadata = target_file_path.metadata()?;
-            let target_mtime = target_metadata.modified()?;
-            source_mtime > target_mtime || source_metadata.len() != target_metadata.len()
-        } else {
-            true
+        let source_metadata = fs.metadata(&source_file_path)?;
+        let should_copy = match fs.metadata(&target_file_path) {
+            Ok(target_metadata) => {
+                source_metadata.modified > target_metadata.modified
+                    || source_metadata.len != target_metadata.len
+            }
+            Err(_) => true,
         };
 
         if should_copy {
             changed = true;
-            fs::copy(source_file_path, &target_file_path)?;
+            fs.write(&target_file_path, &fs.read(&source_file_path)?)?;
         }
     }
 
-    for target_file_path in WalkDir::new(target_path).into_iter().filter_map(|e| {
-        e.ok()
-            .and_then(|e| Utf8PathBuf::from_path_buf(e.into_path()).ok())
-    }) {
-        if !target_file_path.is_file() {
-            continue;
-        }
-
+    for target_file_path in vfs::walk_files(fs.as_ref(), target_path)? {
         let relative_path = target_file_path
             .strip_prefix(target_path)
             .map_err(|_| eyre::eyre!("failed to compute relative path for {}", target_file_path))?;
         if !source_files.contains(relative_path) {
             changed = true;
-            fs::remove_file(target_file_path)?;
+            fs.remove_file(&target_file_path)?;
         }
     }
 
-    let mut target_dirs: Vec<Utf8PathBuf> = WalkDir::new(target_path)
-        .into_iter()
-        .filter_map(|e| {
-            e.ok()
-                .and_then(|e| Utf8PathBuf::from_path_buf(e.into_path()).ok())
-        })
-        .filter(|p| p.is_dir())
-        .collect();
+    let mut target_dirs = Vec::new();
+    let mut pending = vec![target_path.to_owned()];
+    while let Some(dir) = pending.pop() {
+        for path in fs.read_dir(&dir)? {
+            if fs.is_dir(&path) {
+                pending.push(path.clone());
+                target_dirs.push(path);
+            }
+        }
+    }
     target_dirs.sort_by_key(|p| std::cmp::Reverse(p.components().count()));
     for dir in target_dirs {
-        if dir != target_path && dir.read_dir_utf8()?.next().is_none() {
-            let _ = fs::remove_dir(&dir);
+        if fs.read_dir(&dir)?.is_empty() {
+            let _ = fs.remove_dir_all(&dir);
         }
     }
 
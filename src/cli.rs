@@ -3,10 +3,144 @@
 // Authors: Kokic (@kokic)
 
 pub mod build;
+pub mod cache;
 pub mod check;
+pub mod clean;
+pub mod config;
+pub mod graph;
 pub mod init;
 pub mod new;
 pub mod output;
+pub mod query;
 pub mod serve;
 pub mod snip;
 pub mod upgrade;
+
+use clap::{
+    builder::{
+        styling::{AnsiColor, Effects},
+        Styles,
+    },
+    Parser,
+};
+
+use self::{
+    build::BuildCommand,
+    cache::CacheCommand,
+    check::CheckCommand,
+    clean::CleanCommand,
+    config::{ConfigCommand, ConfigOverrideArgs},
+    graph::GraphCommand,
+    init::InitCommand,
+    new::{NewCommand, NewCommandCli},
+    query::QueryCommand,
+    serve::ServeCommand,
+    snip::SnipCommand,
+    upgrade::UpgradeCommand,
+};
+
+#[rustfmt::skip]
+const AFTER_HELP: &str = color_print::cstr!("\
+<s><u>Resources:</></>
+  <s>Tutorial:</>   https://kodama-community.github.io/docs/tutorials/
+  <s>Reference:</>  https://kodama-community.github.io/docs/references/
+  <s>Themes:</>     https://github.com/kodama-community/themes
+  <s>Forum:</>      https://discord.gg/mbeF8J6rXX
+");
+
+const STYLES: Styles = Styles::styled()
+    .literal(AnsiColor::Cyan.on_default().effects(Effects::BOLD))
+    .placeholder(AnsiColor::Blue.on_default());
+
+#[derive(Parser)]
+#[command(version, about, long_about = None, after_help = AFTER_HELP, styles=STYLES)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+
+    #[command(flatten)]
+    overrides: ConfigOverrideArgs,
+}
+
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Create a new kodama site / config / post.
+    #[command(visible_alias = "n")]
+    New(NewCommandCli),
+
+    /// Create a new kodama site in an existing directory.
+    #[command(visible_alias = "i")]
+    Init(InitCommand),
+
+    /// Compile current workspace dir to HTMLs.
+    ///
+    /// Emits "kodama.json" and "kodama.graph.json" by default (override with output flags).
+    #[command(visible_alias = "b")]
+    Build(BuildCommand),
+
+    /// Validate sections and graph without generating build artifacts.
+    #[command(visible_alias = "c")]
+    Check(CheckCommand),
+
+    /// Serve a forest at http://localhost:<port>, and rebuilds it on changes.
+    ///
+    /// Does not emit "kodama.json" / "kodama.graph.json" by default.
+    ///
+    /// Server by default depends on the miniserve program in the user's environment.
+    /// Also see the configuration file (e.g., "Kodama.toml").
+    #[command(visible_alias = "s")]
+    Serve(ServeCommand),
+
+    /// List sections by taxon, metadata and graph relations.
+    #[command(visible_alias = "q")]
+    Query(QueryCommand),
+
+    /// Export the section graph.
+    #[command(visible_alias = "g")]
+    Graph(GraphCommand),
+
+    /// Generate snippet files for VSCode, Neovim or Helix.
+    #[command()]
+    Snip(SnipCommand),
+
+    /// Upgrade config & Typst library files.
+    #[command(visible_alias = "u")]
+    Upgrade(UpgradeCommand),
+
+    /// Remove caches and/or build outputs.
+    #[command()]
+    Clean(CleanCommand),
+
+    /// Inspect and prune the ".cache" directory.
+    #[command()]
+    Cache(CacheCommand),
+
+    /// Show the configuration after profiles and overrides.
+    #[command()]
+    Config(ConfigCommand),
+}
+
+/// Parse the command-line arguments and run the selected command.
+pub(crate) fn run() -> eyre::Result<()> {
+    let cli = Cli::parse();
+    config::install_overrides(&cli.overrides)?;
+    match &cli.command {
+        Command::New(NewCommandCli { command }) => match command {
+            NewCommand::Site(command) => new::new_site(command)?,
+            NewCommand::Post(command) => new::new_section(command)?,
+            NewCommand::Config(command) => new::new_config(command)?,
+        },
+        Command::Init(command) => init::init(command)?,
+        Command::Serve(command) => serve::serve(command)?,
+        Command::Build(command) => build::build(command)?,
+        Command::Check(command) => check::check(command)?,
+        Command::Query(command) => query::query(command)?,
+        Command::Graph(command) => graph::graph(command)?,
+        Command::Snip(command) => snip::snip(command)?,
+        Command::Upgrade(command) => upgrade::upgrade(command)?,
+        Command::Clean(command) => clean::clean(command)?,
+        Command::Cache(command) => cache::cache(command)?,
+        Command::Config(command) => config::config(command)?,
+    };
+    Ok(())
+}
@@ -3,8 +3,9 @@
 // Authors: Kokic (@kokic), Spore (@s-cerevisiae)
 
 use std::{
+    collections::HashMap,
     sync::{
-        atomic::{AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
         Mutex, OnceLock,
     },
     time::{SystemTime, UNIX_EPOCH},
@@ -16,10 +17,12 @@ use eyre::{eyre, WrapErr};
 use crate::{
     assets_sync, atomic_text,
     cli::output::OutputControlArgs,
-    compiler::{self, all_trees_source, DirtySet},
+    compiler::{self, all_trees_source, section::HTMLContent, DirtySet},
     config,
     environment::{self, output_path, BuildMode},
     html_flake,
+    ordered_map::OrderedMap,
+    slug::Slug,
 };
 
 #[derive(clap::Args)]
@@ -44,9 +47,10 @@ pub struct BuildCommand {
     output: OutputControlArgs,
 }
 
-static VERBOSE: OnceLock<bool> = OnceLock::new();
-static VERBOSE_SKIP: OnceLock<bool> = OnceLock::new();
-static NO_CACHE: OnceLock<bool> = OnceLock::new();
+// Set by every build, so a build never sees the options of an earlier one.
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+static VERBOSE_SKIP: AtomicBool = AtomicBool::new(false);
+static NO_CACHE: AtomicBool = AtomicBool::new(false);
 static ATOMIC_WRITE_SEQUENCE: AtomicU64 = AtomicU64::new(0);
 static SERVE_SESSION: OnceLock<Mutex<Option<compiler::ServeCompileSession>>> = OnceLock::new();
 
@@ -58,16 +62,22 @@ pub struct BuildOptions {
     pub outputs: compiler::CompileOutputs,
 }
 
-pub fn verbose() -> &'static bool {
-    VERBOSE.get().unwrap_or(&false)
+pub fn verbose() -> bool {
+    VERBOSE.load(Ordering::Relaxed)
 }
 
-pub fn verbose_skip() -> &'static bool {
-    VERBOSE_SKIP.get().unwrap_or(&false)
+pub fn verbose_skip() -> bool {
+    VERBOSE_SKIP.load(Ordering::Relaxed)
 }
 
-pub fn no_cache_enabled() -> &'static bool {
-    NO_CACHE.get().unwrap_or(&false)
+pub fn no_cache_enabled() -> bool {
+    NO_CACHE.load(Ordering::Relaxed)
+}
+
+fn set_build_options(options: &BuildOptions) {
+    VERBOSE.store(options.verbose, Ordering::Relaxed);
+    VERBOSE_SKIP.store(options.verbose_skip, Ordering::Relaxed);
+    NO_CACHE.store(options.no_cache, Ordering::Relaxed);
 }
 
 /// This function invoked the [`environment::init_environment`] function to initialize the environment
@@ -95,10 +105,17 @@ pub fn build_with_dirty(
     dirty_paths: Option<&DirtySet>,
 ) -> eyre::Result<()> {
     environment::init_environment(config.into(), mode)?;
+    build_current(mode, options, dirty_paths)
+}
+
+/// Build the forest of the current environment, which must already be set up.
+pub(crate) fn build_current(
+    mode: BuildMode,
+    options: BuildOptions,
+    dirty_paths: Option<&DirtySet>,
+) -> eyre::Result<()> {
     environment::ensure_cache_version()?;
-    _ = VERBOSE.set(options.verbose);
-    _ = VERBOSE_SKIP.set(options.verbose_skip);
-    _ = NO_CACHE.set(options.no_cache);
+    set_build_options(&options);
 
     export_static_files().wrap_err("failed to export static files")?;
 
@@ -123,12 +140,39 @@ pub fn build_with_dirty(
     Ok(())
 }
 
+/// Write the site of the current environment from sections the caller has
+/// already parsed and compiled, like [`build_current`] does after compiling.
+pub(crate) fn write_compiled(
+    mode: BuildMode,
+    shallows: &compiler::UnresolvedSections,
+    state: &compiler::state::CompileState,
+    outputs: compiler::CompileOutputs,
+) -> eyre::Result<()> {
+    set_build_options(&BuildOptions {
+        verbose: false,
+        verbose_skip: false,
+        no_cache: false,
+        outputs,
+    });
+
+    export_static_files().wrap_err("failed to export static files")?;
+
+    let trees_dir = environment::trees_dir();
+    let workspace = all_trees_source(&trees_dir)?;
+    compiler::sync_typst_svg_assets(trees_dir.as_path(), None)?;
+    compiler::write_compiled(&workspace, shallows, state, outputs)
+        .wrap_err("failed to write compiled sections")?;
+
+    sync_assets_dir()?;
+    write_reload_marker(mode)?;
+
+    Ok(())
+}
+
 pub fn serve_rewrite_from_memory(config: &str, options: BuildOptions) -> eyre::Result<()> {
     environment::init_environment(config.into(), BuildMode::Serve)?;
     environment::ensure_cache_version()?;
-    _ = VERBOSE.set(options.verbose);
-    _ = VERBOSE_SKIP.set(options.verbose_skip);
-    _ = NO_CACHE.set(options.no_cache);
+    set_build_options(&options);
 
     export_static_files().wrap_err("failed to export static files")?;
 
@@ -203,6 +247,17 @@ fn rewrite_serve_with_session(outputs: compiler::CompileOutputs) -> eyre::Result
     })
 }
 
+/// Section indexes of the serve session, once it has compiled.
+pub(crate) fn serve_section_indexes(
+) -> eyre::Result<Option<HashMap<Slug, OrderedMap<String, HTMLContent>>>> {
+    with_serve_session(|slot| {
+        Ok(slot
+            .as_ref()
+            .filter(|session| session.is_initialized())
+            .map(|session| session.indexes().clone()))
+    })
+}
+
 fn clear_serve_session() {
     let _ = with_serve_session(|slot| {
         *slot = None;
@@ -0,0 +1,62 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+use eyre::{eyre, WrapErr};
+
+use crate::{
+    cli::clean::{disk_usage, format_bytes},
+    compiler, config,
+    environment::{self, BuildMode},
+};
+
+#[derive(clap::Args)]
+pub struct CacheCommand {
+    #[command(subcommand)]
+    pub command: CacheSubcommand,
+}
+
+#[derive(clap::Subcommand)]
+pub enum CacheSubcommand {
+    /// Remove cache entries and generated artifacts not referenced by the current sources.
+    Gc(CacheGcCommand),
+}
+
+#[derive(clap::Args)]
+pub struct CacheGcCommand {
+    /// Path to the configuration file (e.g., "Kodama.toml").
+    #[arg(short, long, default_value_t = config::DEFAULT_CONFIG_PATH.into())]
+    config: String,
+}
+
+pub fn cache(command: &CacheCommand) -> eyre::Result<()> {
+    match &command.command {
+        CacheSubcommand::Gc(command) => gc(command),
+    }
+}
+
+/// This function invokes the [`environment::init_environment`] function to initialize the environment
+fn gc(command: &CacheGcCommand) -> eyre::Result<()> {
+    environment::init_environment(command.config.clone().into(), BuildMode::Publish)?;
+
+    let trees_dir = environment::trees_dir();
+    let workspace = compiler::all_trees_source(trees_dir.as_path())
+        .wrap_err_with(|| eyre!("failed to scan trees dir `{}`", trees_dir))?;
+    let report =
+        compiler::collect_cache_garbage(&workspace).wrap_err("failed to collect cache garbage")?;
+
+    println!(
+        "Removed {} file(s): {} entry, {} hash, {} math, {} artifact.",
+        report.removed_files(),
+        report.entry_files,
+        report.hash_files,
+        report.math_files,
+        report.artifact_files
+    );
+    println!(
+        "Reclaimed {}; cache now uses {}.",
+        format_bytes(report.reclaimed_bytes),
+        format_bytes(disk_usage(environment::get_cache_dir().as_path()))
+    );
+    Ok(())
+}
@@ -2,7 +2,10 @@
 // Released under the GPL-3.0 license as described in the file LICENSE.
 // Authors: Kokic (@kokic), Spore (@s-cerevisiae)
 
-use std::collections::{HashMap, HashSet};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+};
 
 use eyre::{bail, eyre, WrapErr};
 
@@ -14,11 +17,17 @@ use crate::{
     config,
     environment::{self, BuildMode},
     path_utils,
-    process::embed_markdown::{include_error_detected, reset_include_error_flag},
+    process::embed_markdown::{
+        include_error_detected, include_warning_detected, reset_include_error_flag,
+        reset_include_warning_flag,
+    },
+    process::link_action::{link_action_error_detected, reset_link_action_error_flag},
     process::typst_image::{reset_typst_image_error_flag, typst_image_error_detected},
     slug::{self, Ext, Slug},
 };
 
+mod schema;
+
 #[derive(clap::Args)]
 pub struct CheckCommand {
     /// Path to the configuration file (e.g., "Kodama.toml").
@@ -66,13 +75,16 @@ impl Diagnostic {
 }
 
 pub fn check(command: &CheckCommand) -> eyre::Result<()> {
+    let mut diagnostics = config_diagnostics(&command.config)?;
+    if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+        return report(&diagnostics, command.strict);
+    }
     environment::init_environment(command.config.clone().into(), BuildMode::Check)?;
 
     let trees_dir = environment::trees_dir();
     let workspace = compiler::all_trees_source(trees_dir.as_path())
         .wrap_err_with(|| eyre!("failed to scan trees dir `{}`", trees_dir))?;
 
-    let mut diagnostics = Vec::new();
     if workspace.slug_exts.is_empty() {
         diagnostics.push(Diagnostic::hint(format!(
             "No sections found under `{}`.",
@@ -87,7 +99,17 @@ pub fn check(command: &CheckCommand) -> eyre::Result<()> {
 
     reset_typst_image_error_flag();
     reset_include_error_flag();
+    reset_include_warning_flag();
+    reset_link_action_error_flag();
     let shallows = parse_shallows_no_cache(&workspace, &mut diagnostics);
+    let shallows = match compiler::run_plugins(&shallows) {
+        Ok(Cow::Owned(processed)) => processed,
+        Ok(Cow::Borrowed(_)) => shallows,
+        Err(err) => {
+            diagnostics.push(Diagnostic::error(format!("{err:#}")));
+            shallows
+        }
+    };
     if typst_image_error_detected() {
         diagnostics.push(Diagnostic::error(
             "Typst render errors were detected while elaborating markdown content.",
@@ -98,13 +120,50 @@ pub fn check(command: &CheckCommand) -> eyre::Result<()> {
             "Include file read errors were detected while elaborating markdown content.",
         ));
     }
+    if include_warning_detected() {
+        diagnostics.push(Diagnostic::warning(
+            "Include line ranges or regions that do not match their files were detected.",
+        ));
+    }
+    if link_action_error_detected() {
+        diagnostics.push(Diagnostic::error(
+            "Link action commands failed while elaborating markdown content.",
+        ));
+    }
     collect_dangling_local_links(&shallows, &mut diagnostics);
     let has_parse_errors = diagnostics.iter().any(|d| d.severity == Severity::Error);
     if !has_parse_errors {
         validate_compile_graph(&shallows, &mut diagnostics);
     }
+    for message in schema::check_schema(&environment::schema(), &shallows) {
+        diagnostics.push(Diagnostic::error(message));
+    }
+
+    report(&diagnostics, command.strict)
+}
+
+/// Unknown keys and invalid values in the configuration file and its overrides.
+fn config_diagnostics(config_path: &str) -> eyre::Result<Vec<Diagnostic>> {
+    let config_file = config::find_config(config_path.into())?;
+    let source = std::fs::read_to_string(&config_file)
+        .wrap_err_with(|| eyre!("failed to read config file `{}`", config_file))?;
+    let overrides = environment::config_overrides();
+    let diagnostics = config::validate::validate_config(&source)
+        .into_iter()
+        .chain(config::override_diagnostics(&source, &overrides)?)
+        .map(|d| {
+            let message = format!("{}: {}", d.location(&config_file), d.message);
+            match d.severity {
+                config::validate::ConfigSeverity::Error => Diagnostic::error(message),
+                config::validate::ConfigSeverity::Warning => Diagnostic::warning(message),
+            }
+        })
+        .collect();
+    Ok(diagnostics)
+}
 
-    for diagnostic in &diagnostics {
+fn report(diagnostics: &[Diagnostic], strict: bool) -> eyre::Result<()> {
+    for diagnostic in diagnostics {
         print_diagnostic(diagnostic);
     }
 
@@ -121,7 +180,7 @@ pub fn check(command: &CheckCommand) -> eyre::Result<()> {
         .filter(|d| d.severity == Severity::Hint)
         .count();
 
-    let strict_note = if command.strict { " (strict mode)" } else { "" };
+    let strict_note = if strict { " (strict mode)" } else { "" };
     println!(
         "Check result: {} error(s), {} warning(s), {} hint(s){}.",
         errors, warnings, hints, strict_note
@@ -130,7 +189,7 @@ pub fn check(command: &CheckCommand) -> eyre::Result<()> {
     if errors > 0 {
         bail!("check failed with {} error(s)", errors);
     }
-    if command.strict && warnings > 0 {
+    if strict && warnings > 0 {
         bail!("check failed in strict mode with {} warning(s)", warnings);
     }
     Ok(())
@@ -147,9 +206,14 @@ fn parse_shallows_no_cache(
         .map(|(&slug, &ext)| (slug, ext))
         .collect();
     entries.sort_by_key(|(slug, _)| slug.as_str());
+    let mut times = compiler::SourceTimes::new();
 
     for (slug, ext) in entries {
-        match compiler::parse_source_sections(slug, ext) {
+        let parsed = compiler::parse_source_sections(slug, ext).and_then(|mut sections| {
+            times.insert(slug, ext, &mut sections)?;
+            Ok(sections)
+        });
+        match parsed {
             Ok(sections) => {
                 for (section_slug, section) in sections {
                     if shallows.insert(section_slug, section).is_some() {
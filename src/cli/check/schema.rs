@@ -0,0 +1,309 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+use std::collections::HashMap;
+
+use indexmap::IndexMap;
+
+use crate::{
+    compiler::section::UnresolvedSection,
+    config::schema::{FieldType, Schema},
+    entry::{HTMLMetaData, MetaData, MetaValue, KEY_SOURCE_POS, KEY_SOURCE_SLUG, KEY_TAXON},
+    environment, footer_sort,
+    slug::Slug,
+};
+
+/// Check every section against `[schema]`, returning messages that start with
+/// the source position, e.g. `notes/paper.md:3: ...`.
+pub(super) fn check_schema(
+    schema: &Schema,
+    shallows: &HashMap<Slug, UnresolvedSection>,
+) -> Vec<String> {
+    if schema.is_empty() {
+        return vec![];
+    }
+    let mut slugs: Vec<Slug> = shallows.keys().copied().collect();
+    slugs.sort();
+
+    let mut sources = SourceFiles::default();
+    let mut messages = Vec::new();
+    for slug in slugs {
+        let metadata = &shallows[&slug].metadata;
+        let is_section = |target: Slug| shallows.contains_key(&target);
+        for violation in section_violations(schema, slug, metadata, &is_section) {
+            let location = sources.locate(slug, metadata, violation.key.as_deref());
+            messages.push(format!("{}: {}", location, violation.message));
+        }
+    }
+    messages
+}
+
+struct Violation {
+    /// The key whose line is reported; the section start when `None`.
+    key: Option<String>,
+    message: String,
+}
+
+fn section_violations(
+    schema: &Schema,
+    slug: Slug,
+    metadata: &HTMLMetaData,
+    is_section: &dyn Fn(Slug) -> bool,
+) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let taxon = metadata.data_taxon().map(|taxon| taxon.to_lowercase());
+
+    if let Some(taxon) = &taxon {
+        let allowed = &schema.allowed_taxa;
+        if !allowed.is_empty() && !allowed.iter().any(|a| a.to_lowercase() == *taxon) {
+            violations.push(Violation {
+                key: Some(KEY_TAXON.to_string()),
+                message: format!(
+                    "taxon `{}` of `{}` is not one of `{}`.",
+                    taxon,
+                    slug,
+                    allowed.join("`, `")
+                ),
+            });
+        }
+    }
+
+    let rule = taxon.as_ref().and_then(|taxon| {
+        schema
+            .taxon
+            .iter()
+            .find(|(name, _)| name.to_lowercase() == *taxon)
+            .map(|(_, rule)| rule)
+    });
+    let mut types: IndexMap<&String, &FieldType> = schema.types.iter().collect();
+
+    if let (Some(taxon), Some(rule)) = (&taxon, rule) {
+        types.extend(rule.types.iter());
+        for key in &rule.required {
+            if metadata
+                .get(key)
+                .is_none_or(|value| value.to_text().trim().is_empty())
+            {
+                violations.push(Violation {
+                    key: None,
+                    message: format!(
+                        "`{}` ({}) is missing required metadata `{}`.",
+                        slug, taxon, key
+                    ),
+                });
+            }
+        }
+        if let Some(optional) = &rule.optional {
+            for key in metadata.etc_keys() {
+                if !rule.required.contains(&key)
+                    && !optional.contains(&key)
+                    && !types.contains_key(&key)
+                {
+                    violations.push(Violation {
+                        message: format!(
+                            "`{}` ({}) has unexpected metadata `{}`.",
+                            slug, taxon, key
+                        ),
+                        key: Some(key),
+                    });
+                }
+            }
+        }
+    }
+
+    for (key, field_type) in types {
+        let Some(value) = metadata.get(key) else {
+            continue;
+        };
+        if let Err(found) = check_type(&value.to_meta_value(), field_type, is_section) {
+            violations.push(Violation {
+                key: Some(key.to_string()),
+                message: format!(
+                    "`{}` in `{}` should be {}, found {}.",
+                    key, slug, field_type, found
+                ),
+            });
+        }
+    }
+    violations
+}
+
+/// `Err` describes the offending value.
+fn check_type(
+    value: &MetaValue,
+    field_type: &FieldType,
+    is_section: &dyn Fn(Slug) -> bool,
+) -> Result<(), String> {
+    let text = match (value, field_type) {
+        (MetaValue::List(_), FieldType::List | FieldType::Text) => return Ok(()),
+        (MetaValue::List(items), _) => {
+            return items
+                .iter()
+                .try_for_each(|item| check_type(item, field_type, is_section))
+        }
+        (MetaValue::Map(_), FieldType::Text) => return Ok(()),
+        (MetaValue::Map(_), _) => return Err("a table".to_string()),
+        (MetaValue::Text(text), _) => text.trim(),
+    };
+    let valid = match field_type {
+        FieldType::Text => true,
+        FieldType::List => false,
+        FieldType::Bool => matches!(text, "true" | "false"),
+        FieldType::Date => footer_sort::parse_date(text).is_some(),
+        FieldType::Slug => is_section(Slug::new(text)),
+        FieldType::Enum(values) => values.iter().any(|value| value == text),
+    };
+    match valid {
+        true => Ok(()),
+        false => Err(format!("`{}`", text)),
+    }
+}
+
+/// Source files read for reporting positions, by trees-relative path.
+#[derive(Default)]
+struct SourceFiles {
+    files: HashMap<String, Option<String>>,
+}
+
+impl SourceFiles {
+    /// `path:line` of `key` in the source of the section, or of the section start.
+    fn locate(&mut self, slug: Slug, metadata: &HTMLMetaData, key: Option<&str>) -> String {
+        let source_slug = metadata
+            .get_str(KEY_SOURCE_SLUG)
+            .map_or_else(|| slug.to_string(), String::clone);
+        let ext = metadata.ext().map_or("md", String::as_str);
+        let relative = format!("{}.{}", source_slug, ext);
+        // Subtrees record `line:column` of their opening tag.
+        let start = metadata
+            .get_str(KEY_SOURCE_POS)
+            .and_then(|pos| pos.split(':').next()?.parse().ok())
+            .unwrap_or(1);
+
+        let content = self.files.entry(relative.clone()).or_insert_with(|| {
+            environment::fs()
+                .read_to_string(&environment::input_path(&relative))
+                .ok()
+        });
+        let line = key
+            .zip(content.as_deref())
+            .and_then(|(key, content)| key_line(content, start, key))
+            .unwrap_or(start);
+        format!("{}:{}", relative, line)
+    }
+}
+
+/// The first line from `start` that sets `key`, as `key:`, `key =` or `"key":`.
+fn key_line(content: &str, start: usize, key: &str) -> Option<usize> {
+    let sets_key = |line: &str| {
+        let line = line.trim_start();
+        let line = line.strip_prefix('"').unwrap_or(line);
+        let Some(rest) = line.strip_prefix(key) else {
+            return false;
+        };
+        let rest = rest.strip_prefix('"').unwrap_or(rest).trim_start();
+        rest.starts_with(':') || rest.starts_with('=')
+    };
+    content
+        .lines()
+        .enumerate()
+        .skip(start.saturating_sub(1))
+        .find(|(_, line)| sets_key(line))
+        .map(|(index, _)| index + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{
+        compiler,
+        environment::{self, BuildMode},
+        slug::Ext,
+    };
+
+    #[test]
+    fn test_check_schema_reports_violations_with_positions() {
+        let root = crate::test_io::case_dir("check-schema");
+        let trees = root.join("trees");
+        fs::create_dir_all(trees.as_std_path()).unwrap();
+        let config_path = root.join("Kodama.toml");
+        fs::write(
+            config_path.as_std_path(),
+            r#"
+[schema]
+allowed-taxa = ["note", "reference"]
+
+[schema.types]
+date = "date"
+parent = "slug"
+
+[schema.taxon.reference]
+required = ["author", "year"]
+optional = ["date"]
+types = { status = { enum = ["draft", "final"] } }
+"#,
+        )
+        .unwrap();
+        fs::write(
+            trees.join("index.md").as_std_path(),
+            "---\ntitle: Index\ntaxon: note\ndate: 2025-01-31\n---\n\nBody\n",
+        )
+        .unwrap();
+        fs::write(
+            trees.join("paper.md").as_std_path(),
+            "---\ntitle: Paper\ntaxon: Reference\nauthor: A\ndate: someday\nparent: missing\nstatus: done\nvenue: X\n---\n\nBody\n",
+        )
+        .unwrap();
+        fs::write(
+            trees.join("thought.md").as_std_path(),
+            "---\ntitle: Thought\ntaxon: remark\n---\n\nBody\n",
+        )
+        .unwrap();
+
+        environment::with_test_environment(root.clone(), BuildMode::Check, || {
+            environment::init_environment(config_path.clone(), BuildMode::Check).unwrap();
+            let mut shallows = HashMap::new();
+            for slug in ["index", "paper", "thought"] {
+                let sections = compiler::parse_source_sections(Slug::new(slug), Ext::Markdown);
+                shallows.extend(sections.unwrap());
+            }
+
+            let messages = check_schema(&environment::schema(), &shallows);
+            assert_eq!(
+                messages,
+                [
+                    "paper.md:1: `paper` (reference) is missing required metadata `year`.",
+                    "paper.md:8: `paper` (reference) has unexpected metadata `venue`.",
+                    "paper.md:5: `date` in `paper` should be a date, found `someday`.",
+                    "paper.md:6: `parent` in `paper` should be a section slug, found `missing`.",
+                    "paper.md:7: `status` in `paper` should be one of `draft`, `final`, found `done`.",
+                    "thought.md:3: taxon `remark` of `thought` is not one of `note`, `reference`.",
+                ]
+            );
+        });
+
+        let _ = fs::remove_dir_all(root.as_std_path());
+    }
+
+    #[test]
+    fn test_check_type_checks_each_list_item() {
+        let list = MetaValue::List(vec![
+            MetaValue::Text("2025-01-01".to_string()),
+            MetaValue::Text("soon".to_string()),
+        ]);
+        let no_sections = |_: Slug| false;
+        assert_eq!(
+            check_type(&list, &FieldType::Date, &no_sections),
+            Err("`soon`".to_string())
+        );
+        assert!(check_type(&list, &FieldType::List, &no_sections).is_ok());
+        assert!(check_type(
+            &MetaValue::Text("a".to_string()),
+            &FieldType::List,
+            &no_sections
+        )
+        .is_err());
+    }
+}
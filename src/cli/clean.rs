@@ -0,0 +1,227 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+use std::io::ErrorKind;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use eyre::{eyre, WrapErr};
+use walkdir::WalkDir;
+
+use crate::{
+    config,
+    environment::{self, BuildMode},
+};
+
+#[derive(clap::Args)]
+pub struct CleanCommand {
+    /// Path to the configuration file (e.g., "Kodama.toml").
+    #[arg(short, long, default_value_t = config::DEFAULT_CONFIG_PATH.into())]
+    config: String,
+
+    /// Remove the hash and entry caches (default).
+    #[arg(long, default_value_t = false)]
+    cache: bool,
+
+    /// Remove the build and serve output directories.
+    #[arg(long, default_value_t = false)]
+    output: bool,
+
+    /// Remove the whole cache directory and all output directories.
+    #[arg(long, default_value_t = false, conflicts_with_all = ["cache", "output"])]
+    all: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct CleanTargets {
+    cache: bool,
+    output: bool,
+    all: bool,
+}
+
+impl CleanCommand {
+    fn targets(&self) -> CleanTargets {
+        CleanTargets {
+            cache: self.cache || self.all || !self.output,
+            output: self.output || self.all,
+            all: self.all,
+        }
+    }
+}
+
+/// This function invokes the [`environment::init_environment`] function to initialize the environment
+pub fn clean(command: &CleanCommand) -> eyre::Result<()> {
+    environment::init_environment(command.config.clone().into(), BuildMode::Publish)?;
+
+    let paths = clean_paths(command.targets())?;
+    let mut reclaimed = 0;
+    for path in &paths {
+        let size = remove_path(path)?;
+        if size > 0 {
+            println!("Removed \"{}\" ({})", path, format_bytes(size));
+        }
+        reclaimed += size;
+    }
+    println!("Reclaimed {}.", format_bytes(reclaimed));
+    Ok(())
+}
+
+fn clean_paths(targets: CleanTargets) -> eyre::Result<Vec<Utf8PathBuf>> {
+    let cache_dir = environment::get_cache_dir();
+    let mut paths = Vec::new();
+
+    if targets.all {
+        paths.push(cache_dir.clone());
+    } else if targets.cache {
+        paths.push(environment::hash_dir());
+        paths.push(environment::entry_dir());
+        paths.push(environment::math_dir());
+    }
+
+    if targets.output {
+        for output_dir in environment::output_dirs() {
+            environment::ensure_removable_output(output_dir.as_path())?;
+            // Already covered when the whole cache directory is removed.
+            if targets.all && output_dir.starts_with(&cache_dir) {
+                continue;
+            }
+            paths.push(output_dir);
+        }
+    }
+
+    Ok(paths)
+}
+
+fn remove_path(path: &Utf8Path) -> eyre::Result<u64> {
+    let size = disk_usage(path);
+    let fs = environment::fs();
+    let result = match fs.is_dir(path) {
+        true => fs.remove_dir_all(path),
+        false => fs.remove_file(path),
+    };
+    match result {
+        Ok(()) => Ok(size),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(0),
+        Err(err) => Err(err).wrap_err_with(|| eyre!("failed to remove \"{}\"", path)),
+    }
+}
+
+/// Total size in bytes of all files under `path`.
+pub(super) fn disk_usage(path: &Utf8Path) -> u64 {
+    WalkDir::new(path)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| entry.metadata().ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+pub(super) fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn command(cache: bool, output: bool, all: bool) -> CleanCommand {
+        CleanCommand {
+            config: config::DEFAULT_CONFIG_PATH.to_string(),
+            cache,
+            output,
+            all,
+        }
+    }
+
+    #[test]
+    fn test_clean_targets_default_to_cache() {
+        let targets = command(false, false, false).targets();
+        assert!(targets.cache);
+        assert!(!targets.output);
+        assert!(!targets.all);
+
+        let targets = command(false, true, false).targets();
+        assert!(!targets.cache);
+        assert!(targets.output);
+
+        let targets = command(false, false, true).targets();
+        assert!(targets.cache && targets.output && targets.all);
+    }
+
+    #[test]
+    fn test_format_bytes_uses_binary_units() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024), "3.0 MiB");
+    }
+
+    #[test]
+    fn test_clean_removes_cache_and_outputs() {
+        let root = crate::test_io::case_dir("clean-command");
+        fs::create_dir_all(root.join("trees").as_std_path()).unwrap();
+        let config_path = root.join("Kodama.toml");
+        fs::write(config_path.as_std_path(), "").unwrap();
+        for file in [
+            ".cache/hash/a.md.hash",
+            ".cache/entry/a.md.entry",
+            ".cache/math/0123.html",
+            ".cache/publish/a.html",
+            "publish/a.html",
+        ] {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap().as_std_path()).unwrap();
+            fs::write(path.as_std_path(), "x").unwrap();
+        }
+
+        environment::with_test_environment(root.clone(), BuildMode::Publish, || {
+            let mut cmd = command(false, false, false);
+            cmd.config = config_path.to_string();
+            clean(&cmd).unwrap();
+            assert!(!root.join(".cache/hash").exists());
+            assert!(!root.join(".cache/entry").exists());
+            assert!(!root.join(".cache/math").exists());
+            assert!(root.join(".cache/publish/a.html").exists());
+            assert!(root.join("publish/a.html").exists());
+
+            let mut cmd = command(false, true, false);
+            cmd.config = config_path.to_string();
+            clean(&cmd).unwrap();
+            assert!(!root.join(".cache/publish").exists());
+            assert!(!root.join("publish").exists());
+            assert!(root.join("trees").exists());
+        });
+
+        let _ = fs::remove_dir_all(root.as_std_path());
+    }
+
+    #[test]
+    fn test_clean_refuses_output_that_contains_sources() {
+        let root = crate::test_io::case_dir("clean-protected-output");
+        fs::create_dir_all(root.join("trees").as_std_path()).unwrap();
+        let config_path = root.join("Kodama.toml");
+        fs::write(config_path.as_std_path(), "[build]\noutput = \"./\"\n").unwrap();
+
+        environment::with_test_environment(root.clone(), BuildMode::Publish, || {
+            let mut cmd = command(false, true, false);
+            cmd.config = config_path.to_string();
+            assert!(clean(&cmd).is_err());
+            assert!(root.join("trees").exists());
+        });
+
+        let _ = fs::remove_dir_all(root.as_std_path());
+    }
+}
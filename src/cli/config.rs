@@ -0,0 +1,82 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+use eyre::{eyre, WrapErr};
+
+use crate::{
+    config::{self, overrides::ConfigOverrides},
+    environment::{self, BuildMode},
+};
+
+// Config overrides accepted by every command. Not a doc comment: clap would
+// take it as the about text of `kodama --help`.
+#[derive(clap::Args)]
+pub struct ConfigOverrideArgs {
+    /// Merge the `[profile.<NAME>]` table over the config. Defaults to `KODAMA_PROFILE`.
+    #[arg(long, value_name = "NAME", global = true)]
+    pub profile: Option<String>,
+
+    /// Override a config key, e.g. `--set build.pretty-urls=true`. Repeatable.
+    #[arg(long = "set", value_name = "KEY=VALUE", global = true)]
+    pub set: Vec<String>,
+}
+
+/// Collect the overrides from the arguments and `KODAMA_*` environment
+/// variables, and install them for every config loaded afterwards.
+pub fn install_overrides(args: &ConfigOverrideArgs) -> eyre::Result<()> {
+    let overrides = ConfigOverrides::from_env_and_args(args.profile.clone(), &args.set)?;
+    environment::set_config_overrides(overrides);
+    Ok(())
+}
+
+#[derive(clap::Args)]
+pub struct ConfigCommand {
+    #[command(subcommand)]
+    pub command: ConfigSubcommand,
+}
+
+#[derive(clap::Subcommand)]
+pub enum ConfigSubcommand {
+    /// Print the configuration with profile and overrides applied.
+    Show(ConfigShowCommand),
+}
+
+#[derive(clap::Args)]
+pub struct ConfigShowCommand {
+    /// Path to the configuration file (e.g., "Kodama.toml").
+    #[arg(short, long, default_value_t = config::DEFAULT_CONFIG_PATH.into())]
+    config: String,
+}
+
+pub fn config(command: &ConfigCommand) -> eyre::Result<()> {
+    match &command.command {
+        ConfigSubcommand::Show(command) => show(command),
+    }
+}
+
+fn show(command: &ConfigShowCommand) -> eyre::Result<()> {
+    let overrides = environment::config_overrides();
+    let environment = environment::load_environment_with(
+        command.config.clone().into(),
+        BuildMode::Publish,
+        &overrides,
+    )?;
+    let resolved = toml::to_string(&environment.config)
+        .wrap_err_with(|| eyre!("failed to serialize config `{}`", environment.config_file))?;
+    print!("{}", show_header(&environment.config_file, &overrides));
+    print!("{}", resolved);
+    Ok(())
+}
+
+fn show_header(config_file: &camino::Utf8Path, overrides: &ConfigOverrides) -> String {
+    let mut header = format!("# Config: {}\n", config_file);
+    if let Some(profile) = &overrides.profile {
+        header.push_str(&format!("# Profile: {}\n", profile));
+    }
+    for (key, value) in &overrides.values {
+        header.push_str(&format!("# Override: {} = {}\n", key, value));
+    }
+    header.push('\n');
+    header
+}
@@ -0,0 +1,91 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+use camino::Utf8PathBuf;
+use eyre::{eyre, WrapErr};
+
+use crate::{
+    compiler::{
+        self,
+        graph_export::{export_graph, GraphFilter, GraphFormat},
+    },
+    config,
+    environment::{self, BuildMode},
+    slug::Slug,
+};
+
+#[derive(clap::Args)]
+pub struct GraphCommand {
+    #[command(subcommand)]
+    pub command: GraphSubcommand,
+}
+
+#[derive(clap::Subcommand)]
+pub enum GraphSubcommand {
+    /// Export the section graph for Graphviz, Gephi or Mermaid.
+    Export(GraphExportCommand),
+}
+
+#[derive(clap::Args)]
+pub struct GraphExportCommand {
+    /// Output format.
+    #[arg(short, long, value_enum, default_value_t = GraphFormat::Dot)]
+    format: GraphFormat,
+
+    /// Only this section and the sections below it.
+    #[arg(long, value_name = "SLUG")]
+    root: Option<String>,
+
+    /// Only sections at most this many levels below the root, or below `index`.
+    #[arg(long)]
+    depth: Option<usize>,
+
+    /// Write to this file instead of standard output.
+    #[arg(short, long)]
+    output: Option<Utf8PathBuf>,
+
+    /// Path to the configuration file (e.g., "Kodama.toml").
+    #[arg(short, long, default_value_t = config::DEFAULT_CONFIG_PATH.into())]
+    config: String,
+}
+
+pub fn graph(command: &GraphCommand) -> eyre::Result<()> {
+    match &command.command {
+        GraphSubcommand::Export(command) => export(command),
+    }
+}
+
+/// This function invokes the [`environment::init_environment`] function to initialize the environment
+fn export(command: &GraphExportCommand) -> eyre::Result<()> {
+    // Check mode reads the caches without writing them or any output.
+    environment::init_environment(command.config.clone().into(), BuildMode::Check)?;
+    environment::ensure_cache_version()?;
+
+    let trees_dir = environment::trees_dir();
+    let workspace = compiler::all_trees_source(trees_dir.as_path())
+        .wrap_err_with(|| eyre!("failed to scan trees dir `{}`", trees_dir))?;
+    let (shallows, state) =
+        compiler::compile_workspace(&workspace).wrap_err("failed to compile sections")?;
+
+    let root = command.root.as_deref().map(Slug::new);
+    if let Some(root) = root.filter(|root| !shallows.contains_key(root)) {
+        return Err(eyre!("section `{}` does not exist", root));
+    }
+    let filter = GraphFilter {
+        root,
+        depth: command.depth,
+    };
+    let payload = export_graph(&shallows, &state, command.format, &filter);
+
+    match &command.output {
+        Some(path) => {
+            environment::create_parent_dirs(path);
+            std::fs::write(path, payload)
+                .wrap_err_with(|| eyre!("failed to write graph to `{}`", path))?;
+            println!("Exported graph to: {}", path);
+        }
+        None => print!("{}", payload),
+    }
+    Ok(())
+}
@@ -7,6 +7,7 @@ use clap::Parser;
 use eyre::Context;
 
 use crate::{
+    compiler,
     config::{self, kodama},
     environment, path_utils,
 };
@@ -138,12 +139,12 @@ pub const DEFAULT_TEMPLATE_CONTENT_TYPST: &str = r#"
 #show: kodama
 
 #metadata((
-  "title": "<FILE_NAME>",
+  "title": "{{title}}",
 ))
 "#;
 pub const DEFAULT_TEMPLATE_CONTENT_MARKDOWN: &str = r#"
 ---
-title: <FILE_NAME>
+title: {{title}}
 ---
 "#;
 pub const DEFAULT_GITIGNORE: &str = r#"# Generated by Kodama
@@ -153,23 +154,31 @@ pub const DEFAULT_GITIGNORE: &str = r#"# Generated by Kodama
 
 #[derive(clap::Args)]
 pub struct NewPostCommand {
-    /// Path to the new section.
-    #[arg(required = true)]
-    pub path: Utf8PathBuf,
+    /// Path to the new section. With `--prefix`, the directory to create it in.
+    #[arg(required_unless_present = "prefix")]
+    pub path: Option<Utf8PathBuf>,
 
     /// Source extension to append when `path` has no extension.
+    /// Defaults to the extension of the template, then `.typst`.
     #[arg(
         short = 'f',
         long,
-        default_value = DEFAULT_SECTION_EXT_TYPST,
         value_parser = [DEFAULT_SECTION_EXT_TYPST, DEFAULT_SECTION_EXT_MARKDOWN]
     )]
-    pub format: String,
+    pub format: Option<String>,
 
-    /// Path to the template file to use for the new section.
+    /// Name of a template in `templates/`, or path to a template file.
     #[arg(short, long, default_value_t = DEFAULT_TEMPLATE.to_string())]
     pub template: String,
 
+    /// Name the section `<prefix>-XXXX` with the next free base-36 ID.
+    #[arg(short, long)]
+    pub prefix: Option<String>,
+
+    /// Value of the `{{title}}` placeholder. Defaults to the file stem.
+    #[arg(long)]
+    pub title: Option<String>,
+
     /// Path to the configuration file (e.g., "Kodama.toml").
     #[arg(short, long, default_value_t = config::DEFAULT_CONFIG_PATH.into())]
     pub config: String,
@@ -177,12 +186,12 @@ pub struct NewPostCommand {
 
 /// This function invoked the [`config::init_environment`] function to initialize the environment]
 pub fn new_section(command: &NewPostCommand) -> eyre::Result<()> {
-    new_section_inner(
-        &command.path,
-        &command.format,
-        &command.template,
-        Utf8Path::new(&command.config),
-    )
+    environment::init_environment(
+        Utf8PathBuf::from(&command.config),
+        environment::BuildMode::Publish,
+    )?;
+    create_section(command)?;
+    Ok(())
 }
 
 /// This function invoked the [`config::init_environment`] function to initialize the environment]
@@ -192,20 +201,58 @@ fn new_section_inner(
     template: &str,
     config: &Utf8Path,
 ) -> eyre::Result<()> {
-    environment::init_environment(config.to_owned(), environment::BuildMode::Publish)?;
+    new_section(&NewPostCommand {
+        path: Some(path.to_owned()),
+        format: Some(extension.to_string()),
+        template: template.to_string(),
+        prefix: None,
+        title: None,
+        config: config.to_string(),
+    })
+}
 
-    let (section_relative_path, section_ext) = normalize_new_section_path(path, extension)?;
-    let section_relative_path = strip_new_post_tree_prefix(
-        section_relative_path.as_path(),
-        &environment::trees_dir_without_root(),
-    );
-    let default_not_exists = template == DEFAULT_TEMPLATE && !std::fs::exists(template)?;
+/// Create the section described by `command`, returning its path.
+fn create_section(command: &NewPostCommand) -> eyre::Result<Utf8PathBuf> {
+    let trees_dir_without_root = environment::trees_dir_without_root();
+    let requested_ext = match (&command.prefix, &command.path) {
+        (None, Some(path)) if path.extension().is_some() => path.extension(),
+        _ => None,
+    }
+    .map(NewSectionExt::from_path)
+    .or_else(|| command.format.as_deref().map(NewSectionExt::from_cli))
+    .transpose()?;
+
+    let template = resolve_template(&command.template, requested_ext)?;
+    let extension = match requested_ext
+        .or(template.ext)
+        .unwrap_or(NewSectionExt::Typst)
+    {
+        NewSectionExt::Typst => DEFAULT_SECTION_EXT_TYPST,
+        NewSectionExt::Markdown => DEFAULT_SECTION_EXT_MARKDOWN,
+    };
 
-    let content = if default_not_exists {
-        default_template_content(section_ext).to_string()
-    } else {
-        std::fs::read_to_string(template)
-            .map_err(|e| eyre::eyre!("failed to read template file: {}", e))?
+    let (section_relative_path, _) = match &command.prefix {
+        Some(prefix) => {
+            let workspace = compiler::all_trees_source(&environment::trees_dir())?;
+            let stems = workspace
+                .slug_exts
+                .keys()
+                .filter_map(|slug| Utf8Path::new(slug.as_str()).file_name());
+            let id = next_prefixed_id(prefix, stems);
+            let dir = command.path.clone().unwrap_or_default();
+            let path = strip_new_post_tree_prefix(&dir.join(id), &trees_dir_without_root);
+            normalize_new_section_path(&path, extension)?
+        }
+        None => {
+            let path = command.path.as_deref().ok_or_else(|| {
+                eyre::eyre!("a section path is required unless `--prefix` is given")
+            })?;
+            let (path, ext) = normalize_new_section_path(path, extension)?;
+            (
+                strip_new_post_tree_prefix(&path, &trees_dir_without_root),
+                ext,
+            )
+        }
     };
 
     let filestem = section_relative_path.file_stem().ok_or_else(|| {
@@ -214,7 +261,16 @@ fn new_section_inner(
             section_relative_path
         )
     })?;
-    let content = content.replace("<FILE_NAME>", filestem);
+    let date = compiler::format_system_time(std::time::SystemTime::now());
+    let vars = TemplateVars {
+        slug: path_utils::pretty_path(&section_relative_path.with_extension("")),
+        title: command
+            .title
+            .clone()
+            .unwrap_or_else(|| filestem.to_string()),
+        date: date[..10].to_string(),
+    };
+    let content = render_template(&template.content, &vars);
 
     let section_path = environment::trees_dir().join(&section_relative_path);
 
@@ -235,7 +291,146 @@ fn new_section_inner(
         .map_err(|e| eyre::eyre!("failed to create section file: {}", e))?;
     println!("Created new section at: {}", section_path);
 
-    Ok(())
+    Ok(section_path)
+}
+
+/// Directory of named templates, relative to the project root.
+pub const TEMPLATES_DIR: &str = "templates";
+
+/// Template used by `--template` when `templates/` has one of this name.
+const DEFAULT_TEMPLATE_NAME: &str = "default";
+
+struct Template {
+    content: String,
+    /// The source extension of the template file, if it has one.
+    ext: Option<NewSectionExt>,
+}
+
+/// Find `name` as a file path, then as `templates/<name>.{typst,md}`, preferring
+/// the requested extension. The default name falls back to the built-in template.
+fn resolve_template(name: &str, requested_ext: Option<NewSectionExt>) -> eyre::Result<Template> {
+    let read = |path: &Utf8Path| {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| eyre::eyre!("failed to read template file `{}`: {}", path, e))?;
+        let ext = path
+            .extension()
+            .and_then(|ext| NewSectionExt::from_path(ext).ok());
+        eyre::Ok(Template { content, ext })
+    };
+
+    let path = Utf8Path::new(name);
+    if path.is_file() {
+        return read(path);
+    }
+
+    let is_default = name == DEFAULT_TEMPLATE;
+    let templates_dir = environment::root_dir().join(TEMPLATES_DIR);
+    let stem = if is_default {
+        DEFAULT_TEMPLATE_NAME
+    } else {
+        name
+    };
+    let mut exts = vec![NewSectionExt::Typst, NewSectionExt::Markdown];
+    if let Some(requested_ext) = requested_ext {
+        exts.retain(|ext| *ext != requested_ext);
+        exts.insert(0, requested_ext);
+    }
+    let candidates: Vec<Utf8PathBuf> = exts
+        .into_iter()
+        .map(|ext| templates_dir.join(format!("{}.{}", stem, ext.extension_without_dot())))
+        .chain([templates_dir.join(stem)])
+        .collect();
+    if let Some(candidate) = candidates.iter().find(|candidate| candidate.is_file()) {
+        return read(candidate);
+    }
+
+    if is_default {
+        let ext = requested_ext.unwrap_or(NewSectionExt::Typst);
+        return Ok(Template {
+            content: default_template_content(ext).to_string(),
+            ext: Some(ext),
+        });
+    }
+    Err(eyre::eyre!(
+        "template `{}` not found, expected a file or one of `{}`",
+        name,
+        candidates
+            .iter()
+            .map(|candidate| candidate.as_str())
+            .collect::<Vec<_>>()
+            .join("`, `")
+    ))
+}
+
+struct TemplateVars {
+    slug: String,
+    title: String,
+    date: String,
+}
+
+/// Replace `{{slug}}`, `{{title}}` and `{{date}}`, also written with inner spaces.
+/// The older `<FILE_NAME>` placeholder is replaced by the title.
+fn render_template(content: &str, vars: &TemplateVars) -> String {
+    let mut output = String::with_capacity(content.len());
+    let mut rest = content;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start..].find("}}") else {
+            break;
+        };
+        let value = match rest[start + 2..start + len].trim() {
+            "slug" => vars.slug.as_str(),
+            "title" => vars.title.as_str(),
+            "date" => vars.date.as_str(),
+            _ => &rest[start..start + len + 2],
+        };
+        output.push_str(&rest[..start]);
+        output.push_str(value);
+        rest = &rest[start + len + 2..];
+    }
+    output.push_str(rest);
+    output.replace("<FILE_NAME>", &vars.title)
+}
+
+/// Minimum digits of an allocated ID, as in `alg-000A`; `alg-ZZZZ` is
+/// followed by `alg-10000`.
+const ID_WIDTH: usize = 4;
+
+/// `<prefix>-XXXX` with the base-36 number after the largest one among `stems`.
+/// An empty prefix gives bare IDs such as `0AFE`. Only digits and uppercase
+/// letters, as generated, count as IDs, so a stem like `alg-main` does not.
+/// Bare IDs also need a digit, so stems like `TODO` or `NOTE` do not count.
+fn next_prefixed_id<'a>(prefix: &str, stems: impl Iterator<Item = &'a str>) -> String {
+    let head = match prefix {
+        "" => String::new(),
+        prefix => format!("{}-", prefix),
+    };
+    let next = stems
+        .filter_map(|stem| stem.strip_prefix(head.as_str()))
+        .filter(|digits| {
+            digits.len() >= ID_WIDTH
+                && digits
+                    .bytes()
+                    .all(|b| b.is_ascii_digit() || b.is_ascii_uppercase())
+                && (!head.is_empty() || digits.bytes().any(|b| b.is_ascii_digit()))
+        })
+        .filter_map(|digits| u64::from_str_radix(digits, 36).ok())
+        .max()
+        .map_or(1, |max| max + 1);
+    format!("{}{:0>width$}", head, to_base36(next), width = ID_WIDTH)
+}
+
+fn to_base36(mut n: u64) -> String {
+    const DIGITS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+    let mut digits = Vec::new();
+    loop {
+        digits.push(DIGITS[(n % 36) as usize]);
+        n /= 36;
+        if n == 0 {
+            break;
+        }
+    }
+    digits.reverse();
+    String::from_utf8(digits).unwrap()
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -368,6 +563,93 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_next_prefixed_id_follows_largest_base36_id() {
+        let stems = [
+            "alg-0009",
+            "alg-000A",
+            "alg-draft",
+            "alg-main",
+            "top-00ZZ",
+            "big-ZZZZ",
+            "big-10000",
+        ];
+        assert_eq!(next_prefixed_id("alg", stems.into_iter()), "alg-000B");
+        assert_eq!(next_prefixed_id("top", stems.into_iter()), "top-0100");
+        assert_eq!(next_prefixed_id("new", stems.into_iter()), "new-0001");
+        assert_eq!(next_prefixed_id("big", stems.into_iter()), "big-10001");
+        assert_eq!(
+            next_prefixed_id("big", ["big-ZZZZ"].into_iter()),
+            "big-10000"
+        );
+        assert_eq!(
+            next_prefixed_id("", ["0AFE", "index", "test", "TODO", "NOTE"].into_iter()),
+            "0AFF"
+        );
+    }
+
+    #[test]
+    fn test_render_template_replaces_known_placeholders() {
+        let vars = TemplateVars {
+            slug: "alg/alg-0001".to_string(),
+            title: "Lemma".to_string(),
+            date: "2025-03-01".to_string(),
+        };
+        assert_eq!(
+            render_template(
+                "{{title}} ({{ slug }}, {{date}}) {{other}} <FILE_NAME> {{",
+                &vars
+            ),
+            "Lemma (alg/alg-0001, 2025-03-01) {{other}} Lemma {{"
+        );
+    }
+
+    #[test]
+    fn test_create_section_uses_named_template_and_prefix() {
+        let root = crate::test_io::case_dir("new-post-template");
+        let trees = root.join("trees");
+        let templates = root.join(TEMPLATES_DIR);
+        std::fs::create_dir_all(trees.join("alg").as_std_path()).unwrap();
+        std::fs::create_dir_all(templates.as_std_path()).unwrap();
+        let config_path = root.join("Kodama.toml");
+        std::fs::write(config_path.as_std_path(), "").unwrap();
+        std::fs::write(
+            templates.join("lemma.md").as_std_path(),
+            "---\ntitle: {{title}}\ntaxon: lemma\nslug-note: {{slug}}\n---\n",
+        )
+        .unwrap();
+        std::fs::write(trees.join("alg/alg-0009.md").as_std_path(), "").unwrap();
+        std::fs::write(trees.join("alg-000A.typst").as_std_path(), "").unwrap();
+
+        environment::with_test_environment(root.clone(), environment::BuildMode::Publish, || {
+            environment::init_environment(config_path.clone(), environment::BuildMode::Publish)
+                .unwrap();
+            let command = NewPostCommand {
+                path: Some(Utf8PathBuf::from("trees/alg")),
+                format: None,
+                template: "lemma".to_string(),
+                prefix: Some("alg".to_string()),
+                title: Some("Zorn".to_string()),
+                config: config_path.to_string(),
+            };
+            let created = create_section(&command).unwrap();
+            assert_eq!(created, trees.join("alg/alg-000B.md"));
+            assert_eq!(
+                std::fs::read_to_string(created.as_std_path()).unwrap(),
+                "---\ntitle: Zorn\ntaxon: lemma\nslug-note: alg/alg-000B\n---\n"
+            );
+
+            let missing = NewPostCommand {
+                template: "theorem".to_string(),
+                ..command
+            };
+            let err = create_section(&missing).unwrap_err().to_string();
+            assert!(err.starts_with("template `theorem` not found"), "{err}");
+        });
+
+        let _ = std::fs::remove_dir_all(root.as_std_path());
+    }
+
     #[test]
     fn test_strip_new_post_tree_prefix_strips_leading_tree_directory() {
         let stripped = strip_new_post_tree_prefix(Utf8Path::new("trees/notes/a.md"), "trees");
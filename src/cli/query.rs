@@ -0,0 +1,417 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+use std::collections::BTreeSet;
+
+use eyre::{eyre, WrapErr};
+
+use crate::{
+    compiler::{self, state::CompileState, UnresolvedSections},
+    config,
+    entry::{MetaData, MetaValue, KEY_TAXON},
+    environment::{self, BuildMode},
+    ordered_map::OrderedMap,
+    slug::Slug,
+};
+
+#[derive(clap::Args)]
+pub struct QueryCommand {
+    /// Only sections of this taxon, ignoring case. Repeat to allow several.
+    #[arg(short, long)]
+    taxon: Vec<String>,
+
+    /// Metadata predicate: `key`, `!key`, `key=value`, `key!=value` or `key~text`.
+    /// Repeat to require all of them.
+    #[arg(short, long = "where", value_name = "PREDICATE", value_parser = Predicate::parse)]
+    predicates: Vec<Predicate>,
+
+    /// Sections that link to this slug.
+    #[arg(long, value_name = "SLUG", conflicts_with_all = ["references", "ancestors", "descendants"])]
+    backlinks: Option<String>,
+
+    /// Sections this slug refers to.
+    #[arg(long, value_name = "SLUG", conflicts_with_all = ["ancestors", "descendants"])]
+    references: Option<String>,
+
+    /// Parents of this slug, nearest first.
+    #[arg(long, value_name = "SLUG", conflicts_with = "descendants")]
+    ancestors: Option<String>,
+
+    /// Sections below this slug in the parent tree.
+    #[arg(long, value_name = "SLUG")]
+    descendants: Option<String>,
+
+    /// Output format.
+    #[arg(short, long, value_enum, default_value_t = QueryFormat::Table)]
+    format: QueryFormat,
+
+    /// Path to the configuration file (e.g., "Kodama.toml").
+    #[arg(short, long, default_value_t = config::DEFAULT_CONFIG_PATH.into())]
+    config: String,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+enum QueryFormat {
+    /// Slug, taxon and title columns.
+    Table,
+    /// An array of `{ "slug", "metadata" }` objects.
+    Json,
+    /// One slug per line.
+    Slugs,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Predicate {
+    /// The key is set to a non-empty value.
+    Present(String),
+    Absent(String),
+    /// The value, or any item of a list, equals the text.
+    Equals(String, String),
+    NotEquals(String, String),
+    /// The value contains the text, ignoring case.
+    Contains(String, String),
+}
+
+impl Predicate {
+    fn parse(text: &str) -> Result<Self, String> {
+        let split = |op: &str| {
+            text.split_once(op)
+                .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        };
+        let predicate = if let Some((key, value)) = split("!=") {
+            Predicate::NotEquals(key, value)
+        } else if let Some((key, value)) = split("=") {
+            Predicate::Equals(key, value)
+        } else if let Some((key, value)) = split("~") {
+            Predicate::Contains(key, value.to_lowercase())
+        } else if let Some(key) = text.trim().strip_prefix('!') {
+            Predicate::Absent(key.trim().to_string())
+        } else {
+            Predicate::Present(text.trim().to_string())
+        };
+        match predicate.key().is_empty() {
+            true => Err(format!("missing metadata key in `{}`", text)),
+            false => Ok(predicate),
+        }
+    }
+
+    fn key(&self) -> &str {
+        match self {
+            Predicate::Present(key)
+            | Predicate::Absent(key)
+            | Predicate::Equals(key, _)
+            | Predicate::NotEquals(key, _)
+            | Predicate::Contains(key, _) => key,
+        }
+    }
+
+    fn matches(&self, value: Option<&MetaValue>) -> bool {
+        let value = value.filter(|value| !value.to_text().trim().is_empty());
+        let equals = |expected: &str| {
+            value.is_some_and(|value| match value {
+                MetaValue::List(items) => {
+                    items.iter().any(|item| item.to_text().trim() == expected)
+                }
+                value => value.to_text().trim() == expected,
+            })
+        };
+        match self {
+            Predicate::Present(_) => value.is_some(),
+            Predicate::Absent(_) => value.is_none(),
+            Predicate::Equals(_, expected) => equals(expected),
+            Predicate::NotEquals(_, expected) => !equals(expected),
+            Predicate::Contains(_, text) => {
+                value.is_some_and(|value| value.to_text().to_lowercase().contains(text))
+            }
+        }
+    }
+
+    /// Taxa are compared like `--taxon`: by the taxon without its display
+    /// suffix, ignoring case.
+    fn matches_taxon(&self, taxon: Option<&str>) -> bool {
+        let predicate = match self {
+            Predicate::Equals(key, value) => Predicate::Equals(key.clone(), value.to_lowercase()),
+            Predicate::NotEquals(key, value) => {
+                Predicate::NotEquals(key.clone(), value.to_lowercase())
+            }
+            predicate => predicate.clone(),
+        };
+        let taxon = taxon.map(|taxon| MetaValue::Text(taxon.to_lowercase()));
+        predicate.matches(taxon.as_ref())
+    }
+}
+
+/// This function invokes the [`environment::init_environment`] function to initialize the environment
+pub fn query(command: &QueryCommand) -> eyre::Result<()> {
+    // Check mode reads the caches without writing them or any output.
+    environment::init_environment(command.config.clone().into(), BuildMode::Check)?;
+    environment::ensure_cache_version()?;
+
+    let trees_dir = environment::trees_dir();
+    let workspace = compiler::all_trees_source(trees_dir.as_path())
+        .wrap_err_with(|| eyre!("failed to scan trees dir `{}`", trees_dir))?;
+    let (shallows, state) =
+        compiler::compile_workspace(&workspace).wrap_err("failed to compile sections")?;
+
+    let slugs = select(command, &shallows, &state)?;
+    let rows: Vec<(Slug, OrderedMap<String, MetaValue>)> = slugs
+        .into_iter()
+        .map(|slug| {
+            let metadata = shallows[&slug]
+                .metadata
+                .0
+                .iter()
+                .map(|(key, value)| (key.clone(), value.to_meta_value()))
+                .collect();
+            (slug, metadata)
+        })
+        .collect();
+
+    match command.format {
+        QueryFormat::Slugs => rows.iter().for_each(|(slug, _)| println!("{}", slug)),
+        QueryFormat::Json => {
+            let json: Vec<_> = rows
+                .iter()
+                .map(|(slug, metadata)| serde_json::json!({ "slug": slug, "metadata": metadata }))
+                .collect();
+            let serialized = serde_json::to_string_pretty(&json)
+                .wrap_err("failed to serialize query results to JSON")?;
+            println!("{}", serialized);
+        }
+        QueryFormat::Table => print!("{}", table(&rows)),
+    }
+    Ok(())
+}
+
+/// The visible sections that satisfy the relation and filters of `command`,
+/// sorted by slug except for ancestors.
+fn select(
+    command: &QueryCommand,
+    shallows: &UnresolvedSections,
+    state: &CompileState,
+) -> eyre::Result<Vec<Slug>> {
+    let known = |slug: &String| {
+        let slug = Slug::new(slug);
+        match shallows.contains_key(&slug) {
+            true => Ok(slug),
+            false => Err(eyre!("section `{}` does not exist", slug)),
+        }
+    };
+
+    let mut slugs: Vec<Slug> = if let Some(target) = &command.backlinks {
+        let callback = state.callback().0.get(&known(target)?);
+        sorted(
+            callback
+                .into_iter()
+                .flat_map(|value| value.backlinks.iter().copied()),
+        )
+    } else if let Some(source) = &command.references {
+        let section = state.compiled().get(&known(source)?);
+        sorted(
+            section
+                .into_iter()
+                .flat_map(|section| section.references.iter().copied()),
+        )
+    } else if let Some(slug) = &command.ancestors {
+        ancestors(state, known(slug)?)
+    } else if let Some(slug) = &command.descendants {
+        sorted(state.descendants(known(slug)?).into_keys())
+    } else {
+        sorted(shallows.keys().copied())
+    };
+
+    let taxa: Vec<String> = command.taxon.iter().map(|t| t.to_lowercase()).collect();
+    slugs.retain(|slug| {
+        let Some(section) = shallows.get(slug) else {
+            return false;
+        };
+        if compiler::is_internal_anonymous_subtree(section) {
+            return false;
+        }
+        let metadata = &section.metadata;
+        let taxon_matches = taxa.is_empty()
+            || metadata
+                .data_taxon()
+                .is_some_and(|taxon| taxa.contains(&taxon.to_lowercase()));
+        taxon_matches
+            && command.predicates.iter().all(|predicate| {
+                if predicate.key() == KEY_TAXON {
+                    return predicate.matches_taxon(metadata.data_taxon().map(String::as_str));
+                }
+                let value = metadata.get(predicate.key()).map(|v| v.to_meta_value());
+                predicate.matches(value.as_ref())
+            })
+    });
+    Ok(slugs)
+}
+
+fn sorted(slugs: impl Iterator<Item = Slug>) -> Vec<Slug> {
+    slugs.collect::<BTreeSet<_>>().into_iter().collect()
+}
+
+fn ancestors(state: &CompileState, slug: Slug) -> Vec<Slug> {
+    let mut ancestors = Vec::new();
+    let mut current = slug;
+    while let Some(parent) = state.parent_of(current) {
+        if parent == slug || ancestors.contains(&parent) {
+            break;
+        }
+        ancestors.push(parent);
+        current = parent;
+    }
+    ancestors
+}
+
+/// Columns padded to their widest cell, without trailing spaces.
+fn table(rows: &[(Slug, OrderedMap<String, MetaValue>)]) -> String {
+    let text = |metadata: &OrderedMap<String, MetaValue>, key: &str| {
+        metadata
+            .get(key)
+            .map(MetaValue::to_text)
+            .unwrap_or_default()
+    };
+    let cells: Vec<[String; 3]> = rows
+        .iter()
+        .map(|(slug, metadata)| {
+            [
+                slug.to_string(),
+                text(metadata, crate::entry::KEY_DATA_TAXON),
+                text(metadata, crate::entry::KEY_PAGE_TITLE),
+            ]
+        })
+        .collect();
+    let width = |column: usize| {
+        cells
+            .iter()
+            .map(|row| row[column].chars().count())
+            .max()
+            .unwrap_or(0)
+    };
+    let (slug_width, taxon_width) = (width(0), width(1));
+
+    let mut output = String::new();
+    for [slug, taxon, title] in cells {
+        let line = format!("{:slug_width$}  {:taxon_width$}  {}", slug, taxon, title);
+        output.push_str(line.trim_end());
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use clap::Parser;
+
+    use super::*;
+
+    #[derive(Parser)]
+    struct TestCli {
+        #[command(flatten)]
+        query: QueryCommand,
+    }
+
+    fn command(args: &[&str]) -> QueryCommand {
+        let args = std::iter::once("query").chain(args.iter().copied());
+        TestCli::try_parse_from(args).unwrap().query
+    }
+
+    #[test]
+    fn test_predicate_parse() {
+        assert_eq!(
+            Predicate::parse("status != draft"),
+            Ok(Predicate::NotEquals("status".into(), "draft".into()))
+        );
+        assert_eq!(
+            Predicate::parse("tags=algebra"),
+            Ok(Predicate::Equals("tags".into(), "algebra".into()))
+        );
+        assert_eq!(
+            Predicate::parse("title~Zorn"),
+            Ok(Predicate::Contains("title".into(), "zorn".into()))
+        );
+        assert_eq!(
+            Predicate::parse("!proof"),
+            Ok(Predicate::Absent("proof".into()))
+        );
+        assert_eq!(
+            Predicate::parse("date"),
+            Ok(Predicate::Present("date".into()))
+        );
+        assert!(Predicate::parse("=x").is_err());
+    }
+
+    #[test]
+    fn test_select_filters_and_walks_the_graph() {
+        let root = crate::test_io::case_dir("query");
+        let trees = root.join("trees");
+        fs::create_dir_all(trees.as_std_path()).unwrap();
+        let config_path = root.join("Kodama.toml");
+        fs::write(config_path.as_std_path(), "").unwrap();
+        let files = [
+            ("index.md", "---\ntitle: Index\n---\n\n[](chapter#:embed)\n"),
+            (
+                "chapter.md",
+                "---\ntitle: Chapter\n---\n\n[](thm-a#:embed)\n[](thm-b#:embed)\n",
+            ),
+            (
+                "thm-a.md",
+                "---\ntitle: Zorn\ntaxon: theorem\nproof: proof-a\ntags: [algebra, sets]\n---\n\nSee [B](thm-b).\n",
+            ),
+            (
+                "thm-b.md",
+                "---\ntitle: Other\ntaxon: Theorem\nasref: true\n---\n\nBody\n",
+            ),
+        ];
+        for (name, content) in files {
+            fs::write(trees.join(name).as_std_path(), content).unwrap();
+        }
+
+        environment::with_test_environment(root.clone(), BuildMode::Check, || {
+            environment::init_environment(config_path.clone(), BuildMode::Check).unwrap();
+            let workspace = compiler::all_trees_source(&environment::trees_dir()).unwrap();
+            let (shallows, state) = compiler::compile_workspace(&workspace).unwrap();
+            let run = |args: &[&str]| {
+                let slugs = select(&command(args), &shallows, &state).unwrap();
+                slugs.iter().map(Slug::to_string).collect::<Vec<_>>()
+            };
+
+            assert_eq!(run(&["--taxon", "theorem", "--where", "!proof"]), ["thm-b"]);
+            assert_eq!(run(&["--where", "tags=sets"]), ["thm-a"]);
+            assert_eq!(run(&["--where", "title~ZO", "--where", "proof"]), ["thm-a"]);
+            assert_eq!(run(&["--backlinks", "thm-b"]), ["thm-a"]);
+            assert_eq!(run(&["--references", "thm-a"]), ["thm-b"]);
+            assert_eq!(run(&["--ancestors", "thm-a"]), ["chapter", "index"]);
+            assert_eq!(
+                run(&["--descendants", "index"]),
+                ["chapter", "thm-a", "thm-b"]
+            );
+            assert_eq!(run(&["--where", "taxon=Theorem"]), ["thm-a", "thm-b"]);
+            assert_eq!(
+                run(&["--descendants", "chapter", "--where", "taxon!=theorem"]),
+                Vec::<String>::new()
+            );
+            assert!(select(&command(&["--ancestors", "nope"]), &shallows, &state).is_err());
+
+            let rows: Vec<_> = ["index", "thm-a"]
+                .map(|slug| {
+                    let metadata = shallows[&Slug::new(slug)]
+                        .metadata
+                        .0
+                        .iter()
+                        .map(|(key, value)| (key.clone(), value.to_meta_value()))
+                        .collect();
+                    (Slug::new(slug), metadata)
+                })
+                .into();
+            assert_eq!(
+                table(&rows),
+                "index           Index\nthm-a  Theorem  Zorn\n"
+            );
+        });
+
+        let _ = fs::remove_dir_all(root.as_std_path());
+    }
+}
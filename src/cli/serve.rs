@@ -7,7 +7,8 @@ use std::{io::Write, sync::OnceLock};
 use crate::{
     cli::build::{build_with_dirty, serve_rewrite_from_memory, BuildOptions},
     cli::output::OutputControlArgs,
-    compiler::{CompileOutputs, DirtySet},
+    cli::snip::{self, SnippetFormat},
+    compiler::{self, CompileOutputs, DirtySet},
     config,
     environment::{self, BuildMode},
 };
@@ -18,7 +19,7 @@ mod watch;
 use process::spawn_serve_process;
 use watch::{
     analyze_watch_changes, compose_watched_paths, format_watch_change_stats,
-    should_restart_for_config_change, watch_paths,
+    should_restart_for_config_change, watch_paths, ExternalDependencies, WatchedTree,
 };
 
 #[derive(clap::Args)]
@@ -43,6 +44,10 @@ pub struct ServeCommand {
     #[arg(short, long, default_value_t = false)]
     watch_stats: bool,
 
+    /// Keep section snippets in this format up to date, like `kodama snip --section`.
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    snippets: Option<SnippetFormat>,
+
     #[command(flatten)]
     output: OutputControlArgs,
 }
@@ -79,6 +84,11 @@ pub fn serve(command: &ServeCommand) -> eyre::Result<()> {
             build_options,
             dirty_paths,
         )?;
+        if let Some(format) = command.snippets {
+            if let Err(err) = snip::sync_serve_section_snippets(format) {
+                color_print::ceprintln!("<y>[watch] Warning: {:?}</>", err);
+            }
+        }
         Ok(())
     };
 
@@ -104,23 +114,57 @@ pub fn serve(command: &ServeCommand) -> eyre::Result<()> {
     let assets_dir_canonical = assets_dir
         .canonicalize_utf8()
         .unwrap_or_else(|_| assets_dir.clone());
-    let trees_dir_canonical = trees_dir
-        .canonicalize_utf8()
-        .unwrap_or_else(|_| trees_dir.clone());
-    let watched_paths = compose_watched_paths(
+    let mounts = crate::environment::tree_mounts();
+    let mut watched_paths = compose_watched_paths(
         root_dir.as_path(),
         trees_dir.clone(),
         assets_dir.clone(),
         config_file.clone(),
         crate::environment::theme_paths(),
     );
-    watch_paths(&watched_paths, assets_dir.as_path(), |changed_paths| {
+    watched_paths.extend(mounts.iter().skip(1).map(|mount| mount.dir.clone()));
+    // Files outside the trees directory read by `#:include` and friends, as
+    // recorded by the last build.
+    let external_dependencies = || {
+        ExternalDependencies::from_recorded(
+            compiler::recorded_dependencies(),
+            trees_dir.as_path(),
+            &watched_paths,
+        )
+    };
+    let mut dependencies = external_dependencies();
+    let ignore_files: Vec<_> = mounts
+        .iter()
+        .map(|mount| {
+            let canonical = mount
+                .dir
+                .canonicalize_utf8()
+                .unwrap_or_else(|_| mount.dir.clone());
+            (
+                mount.dir.join(compiler::IGNORE_FILE_NAME),
+                canonical.join(compiler::IGNORE_FILE_NAME),
+            )
+        })
+        .collect();
+    let initial_dependencies = dependencies.paths().cloned().collect();
+    let rebuild = |changed_paths: &[camino::Utf8PathBuf]| {
+        // Reload on every batch so that edits to `.kodamaignore` apply immediately.
+        let trees: Vec<WatchedTree> = mounts
+            .iter()
+            .map(|mount| {
+                let exclude = compiler::ExcludeRules::load(&mount.dir).unwrap_or_else(|err| {
+                    color_print::ceprintln!("<y>[watch] Warning: {:?}</>", err);
+                    compiler::ExcludeRules::default()
+                });
+                WatchedTree::new(mount.clone(), exclude)
+            })
+            .collect();
         let analysis = analyze_watch_changes(
             changed_paths,
-            trees_dir.as_path(),
-            trees_dir_canonical.as_path(),
+            &trees,
             assets_dir.as_path(),
             assets_dir_canonical.as_path(),
+            &dependencies,
         );
         if watch_stats {
             color_print::ceprintln!("<dim>{}</>", format_watch_change_stats(analysis.stats));
@@ -141,6 +185,13 @@ pub fn serve(command: &ServeCommand) -> eyre::Result<()> {
             let _ = serve.kill();
             let _ = serve.wait();
             serve = spawn_serve_process()?;
+        } else if changed_paths.iter().any(|path| {
+            ignore_files.iter().any(|(file, canonical)| {
+                should_restart_for_config_change(path.as_path(), file, canonical)
+            })
+        }) {
+            // Exclude patterns decide which sources exist; rescan everything.
+            serve_build(None)?;
         } else if !analysis.stats.has_effective_changes() {
             color_print::ceprintln!(
                 "<dim>[watch] Skip rebuild: no effective changes after filtering.</>"
@@ -157,8 +208,16 @@ pub fn serve(command: &ServeCommand) -> eyre::Result<()> {
             // Serve mode uses watcher-driven dirty set to avoid full hash scans on every rebuild.
             serve_build(Some(&analysis.dirty_paths))?;
         }
-        Ok(())
-    })?;
+        // Rebuilds may reference new files outside the trees directory.
+        dependencies = external_dependencies();
+        Ok(dependencies.paths().cloned().collect())
+    };
+    watch_paths(
+        &watched_paths,
+        initial_dependencies,
+        assets_dir.as_path(),
+        rebuild,
+    )?;
 
     // After watching process is done, kill the miniserve process.
     let _ = serve.kill();
@@ -178,6 +237,7 @@ mod tests {
             verbose_skip: false,
             disable_reload: false,
             watch_stats: false,
+            snippets: None,
             output: OutputControlArgs::default(),
         };
         let outputs = compile_outputs(&command);
@@ -193,6 +253,7 @@ mod tests {
             verbose_skip: false,
             disable_reload: false,
             watch_stats: false,
+            snippets: None,
             output: OutputControlArgs {
                 indexes: true,
                 no_indexes: false,
@@ -213,6 +274,7 @@ mod tests {
             verbose_skip: false,
             disable_reload: false,
             watch_stats: false,
+            snippets: None,
             output: OutputControlArgs {
                 indexes: false,
                 no_indexes: true,
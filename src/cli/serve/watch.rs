@@ -8,6 +8,7 @@ mod strategy;
 
 pub(super) use analysis::{
     analyze_watch_changes, format_watch_change_stats, should_restart_for_config_change,
+    ExternalDependencies, WatchedTree,
 };
 pub(super) use runtime::watch_paths;
 pub(super) use strategy::compose_watched_paths;
@@ -2,9 +2,15 @@
 // Released under the GPL-3.0 license as described in the file LICENSE.
 // Authors: Kokic (@kokic)
 
+use std::collections::HashMap;
+
 use camino::{Utf8Path, Utf8PathBuf};
 
-use crate::{compiler::DirtySet, path_utils};
+use crate::{
+    compiler::{DirtySet, ExcludeRules},
+    environment::TreeMount,
+    path_utils,
+};
 
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
 pub(in crate::cli::serve) struct WatchChangeStats {
@@ -15,6 +21,7 @@ pub(in crate::cli::serve) struct WatchChangeStats {
     pub global_paths: usize,
     pub ignored_temp_paths: usize,
     pub ignored_directory_paths: usize,
+    pub ignored_excluded_paths: usize,
 }
 
 impl WatchChangeStats {
@@ -32,6 +39,72 @@ pub(in crate::cli::serve) struct WatchChangeAnalysis {
     pub stats: WatchChangeStats,
 }
 
+/// A watched source directory: the main trees directory or a mount.
+#[derive(Debug)]
+pub(in crate::cli::serve) struct WatchedTree {
+    pub mount: TreeMount,
+    pub dir_canonical: Utf8PathBuf,
+    pub exclude: ExcludeRules,
+}
+
+impl WatchedTree {
+    pub fn new(mount: TreeMount, exclude: ExcludeRules) -> Self {
+        let dir_canonical = canonicalize_or_self(&mount.dir);
+        Self {
+            mount,
+            dir_canonical,
+            exclude,
+        }
+    }
+
+    /// Map a changed path to its trees-relative path, if it lies in this tree.
+    fn relative_path(&self, path: &Utf8Path) -> Option<Utf8PathBuf> {
+        let inner = relative_tree_path(path, &self.mount.dir, &self.dir_canonical)?;
+        Some(self.mount.virtual_path(&inner))
+    }
+}
+
+/// Recorded source dependencies that live outside the trees directory (e.g. an
+/// `#:include` of a file in the project root), mapped to their `../`-prefixed
+/// trees-relative dependency key.
+#[derive(Debug, Default)]
+pub(in crate::cli::serve) struct ExternalDependencies {
+    keys: HashMap<Utf8PathBuf, Utf8PathBuf>,
+}
+
+impl ExternalDependencies {
+    /// Keep the recorded dependencies that point outside `trees_dir` and exist on
+    /// disk, except `excluded` paths that are already watched for global changes.
+    pub fn from_recorded<I>(recorded: I, trees_dir: &Utf8Path, excluded: &[Utf8PathBuf]) -> Self
+    where
+        I: IntoIterator<Item = Utf8PathBuf>,
+    {
+        let excluded: Vec<Utf8PathBuf> = excluded
+            .iter()
+            .map(|path| canonicalize_or_self(path))
+            .collect();
+        let keys = recorded
+            .into_iter()
+            .filter(|key| key.starts_with(".."))
+            .filter_map(|key| {
+                let path = trees_dir.join(&key).canonicalize_utf8().ok()?;
+                (path.is_file() && !excluded.contains(&path)).then_some((path, key))
+            })
+            .collect();
+        Self { keys }
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &Utf8PathBuf> {
+        self.keys.keys()
+    }
+
+    fn key_for(&self, path: &Utf8Path) -> Option<&Utf8PathBuf> {
+        self.keys
+            .get(path)
+            .or_else(|| self.keys.get(&canonicalize_or_self(path)))
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 enum NoisePathKind {
     Temp,
@@ -117,10 +190,10 @@ fn classify_noise_path(path: &Utf8Path) -> Option<NoisePathKind> {
 
 pub(in crate::cli::serve) fn analyze_watch_changes(
     changed_paths: &[Utf8PathBuf],
-    trees_dir: &Utf8Path,
-    trees_dir_canonical: &Utf8Path,
+    trees: &[WatchedTree],
     assets_dir: &Utf8Path,
     assets_dir_canonical: &Utf8Path,
+    external_dependencies: &ExternalDependencies,
 ) -> WatchChangeAnalysis {
     let mut dirty_paths = DirtySet::new();
     let mut stats = WatchChangeStats::default();
@@ -135,7 +208,17 @@ pub(in crate::cli::serve) fn analyze_watch_changes(
             continue;
         }
 
-        if let Some(relative) = relative_tree_path(path.as_path(), trees_dir, trees_dir_canonical) {
+        // With nested trees, the innermost one owns the path.
+        let located = trees
+            .iter()
+            .filter_map(|tree| Some((tree, tree.relative_path(path.as_path())?)))
+            .min_by_key(|(tree, relative)| relative.as_str().len() - tree.mount.prefix.len());
+        if let Some((tree, relative)) = located {
+            let inner = tree.mount.inner_path(&relative).unwrap_or(&relative);
+            if tree.exclude.is_excluded(inner, false) {
+                stats.ignored_excluded_paths += 1;
+                continue;
+            }
             if is_source_extension(relative.extension()) {
                 stats.tree_source_paths += 1;
             } else {
@@ -150,6 +233,12 @@ pub(in crate::cli::serve) fn analyze_watch_changes(
             continue;
         }
 
+        if let Some(key) = external_dependencies.key_for(path.as_path()) {
+            stats.tree_dependency_paths += 1;
+            dirty_paths.insert(key.clone());
+            continue;
+        }
+
         stats.global_paths += 1;
     }
 
@@ -158,14 +247,15 @@ pub(in crate::cli::serve) fn analyze_watch_changes(
 
 pub(in crate::cli::serve) fn format_watch_change_stats(stats: WatchChangeStats) -> String {
     format!(
-        "[watch] Stats: total={}, tree_source={}, tree_dependency={}, assets={}, global={}, ignored_temp={}, ignored_dir={}",
+        "[watch] Stats: total={}, tree_source={}, tree_dependency={}, assets={}, global={}, ignored_temp={}, ignored_dir={}, ignored_excluded={}",
         stats.total_paths,
         stats.tree_source_paths,
         stats.tree_dependency_paths,
         stats.asset_paths,
         stats.global_paths,
         stats.ignored_temp_paths,
-        stats.ignored_directory_paths
+        stats.ignored_directory_paths,
+        stats.ignored_excluded_paths
     )
 }
 
@@ -179,21 +269,24 @@ mod tests {
         crate::test_io::case_dir(&format!("serve-{name}"))
     }
 
+    fn main_tree(trees: &Utf8Path, exclude: ExcludeRules) -> WatchedTree {
+        WatchedTree::new(TreeMount::main(trees.to_owned()), exclude)
+    }
+
     #[test]
     fn test_analyze_watch_changes_collects_tree_relative_files() {
         let root = Utf8PathBuf::from("site");
         let trees = root.join("trees");
-        let trees_canonical = trees.clone();
         let assets = root.join("assets");
         let assets_canonical = assets.clone();
         let changed = vec![trees.join("a.md"), root.join("import-style.html")];
 
         let analysis = analyze_watch_changes(
             &changed,
-            trees.as_path(),
-            trees_canonical.as_path(),
+            &[main_tree(&trees, ExcludeRules::default())],
             assets.as_path(),
             assets_canonical.as_path(),
+            &ExternalDependencies::default(),
         );
         assert!(analysis.dirty_paths.contains(&Utf8PathBuf::from("a.md")));
         assert!(!analysis
@@ -212,13 +305,12 @@ mod tests {
         fs::write(&file, "x").unwrap();
         let changed = vec![trees.join("sub/../a.typst")];
 
-        let trees_canonical = trees.canonicalize_utf8().unwrap();
         let analysis = analyze_watch_changes(
             &changed,
-            trees.as_path(),
-            trees_canonical.as_path(),
+            &[main_tree(&trees, ExcludeRules::default())],
             assets.as_path(),
             assets.as_path(),
+            &ExternalDependencies::default(),
         );
         assert!(analysis.dirty_paths.contains(&Utf8PathBuf::from("a.typst")));
 
@@ -239,13 +331,12 @@ mod tests {
             dir.clone(),
         ];
 
-        let trees_canonical = trees.canonicalize_utf8().unwrap();
         let analysis = analyze_watch_changes(
             &changed,
-            trees.as_path(),
-            trees_canonical.as_path(),
+            &[main_tree(&trees, ExcludeRules::default())],
             assets.as_path(),
             assets.as_path(),
+            &ExternalDependencies::default(),
         );
         assert!(analysis
             .dirty_paths
@@ -278,14 +369,13 @@ mod tests {
             themes.join("theme.html"),
         ];
 
-        let trees_canonical = trees.canonicalize_utf8().unwrap();
         let assets_canonical = assets.canonicalize_utf8().unwrap();
         let analysis = analyze_watch_changes(
             &changed,
-            trees.as_path(),
-            trees_canonical.as_path(),
+            &[main_tree(&trees, ExcludeRules::default())],
             assets.as_path(),
             assets_canonical.as_path(),
+            &ExternalDependencies::default(),
         );
 
         assert!(analysis
@@ -304,6 +394,7 @@ mod tests {
                 global_paths: 1,
                 ignored_temp_paths: 1,
                 ignored_directory_paths: 1,
+                ignored_excluded_paths: 0,
             }
         );
         assert!(analysis.stats.has_effective_changes());
@@ -311,6 +402,110 @@ mod tests {
         let _ = fs::remove_dir_all(root);
     }
 
+    #[test]
+    fn test_analyze_watch_changes_maps_mounted_paths_to_prefixed_keys() {
+        let root = Utf8PathBuf::from("site");
+        let trees = root.join("trees");
+        let assets = root.join("assets");
+        let vendor = root.join("vendor/foundations");
+        let mounted = WatchedTree::new(
+            TreeMount {
+                dir: vendor.clone(),
+                prefix: "found".to_string(),
+                edit: None,
+            },
+            ExcludeRules::new(["drafts/"]).unwrap(),
+        );
+        let changed = vec![
+            trees.join("a.md"),
+            vendor.join("sets.md"),
+            vendor.join("drafts/wip.md"),
+        ];
+
+        let analysis = analyze_watch_changes(
+            &changed,
+            &[main_tree(&trees, ExcludeRules::default()), mounted],
+            assets.as_path(),
+            assets.as_path(),
+            &ExternalDependencies::default(),
+        );
+        assert_eq!(
+            analysis.dirty_paths,
+            DirtySet::from([
+                Utf8PathBuf::from("a.md"),
+                Utf8PathBuf::from("found/sets.md")
+            ])
+        );
+        assert_eq!(analysis.stats.ignored_excluded_paths, 1);
+    }
+
+    #[test]
+    fn test_analyze_watch_changes_skips_excluded_tree_paths() {
+        let root = Utf8PathBuf::from("site");
+        let trees = root.join("trees");
+        let assets = root.join("assets");
+        let changed = vec![
+            trees.join("a.md"),
+            trees.join("scratch/idea.md"),
+            trees.join("b.draft.md"),
+        ];
+        let exclude = ExcludeRules::new(["scratch/", "*.draft.md"]).unwrap();
+
+        let analysis = analyze_watch_changes(
+            &changed,
+            &[main_tree(&trees, exclude)],
+            assets.as_path(),
+            assets.as_path(),
+            &ExternalDependencies::default(),
+        );
+        assert_eq!(
+            analysis.dirty_paths,
+            DirtySet::from([Utf8PathBuf::from("a.md")])
+        );
+        assert_eq!(analysis.stats.tree_source_paths, 1);
+        assert_eq!(analysis.stats.ignored_excluded_paths, 2);
+    }
+
+    #[test]
+    fn test_analyze_watch_changes_maps_external_dependencies_to_tree_keys() {
+        let root = case_dir("external-deps");
+        let trees = root.join("trees");
+        let assets = root.join("assets");
+        fs::create_dir_all(trees.as_std_path()).unwrap();
+        fs::create_dir_all(root.join("src").as_std_path()).unwrap();
+        fs::write(root.join("src/lib.rs"), "fn main() {}").unwrap();
+        fs::write(root.join("import-meta.html"), "").unwrap();
+
+        let external = ExternalDependencies::from_recorded(
+            [
+                Utf8PathBuf::from("../src/lib.rs"),
+                Utf8PathBuf::from("../import-meta.html"),
+                Utf8PathBuf::from("../missing.txt"),
+                Utf8PathBuf::from("notes/inner.md"),
+            ],
+            trees.as_path(),
+            &[root.join("import-meta.html")],
+        );
+        assert_eq!(external.paths().count(), 1);
+
+        let changed = vec![root.join("src/lib.rs"), root.join("import-meta.html")];
+        let analysis = analyze_watch_changes(
+            &changed,
+            &[main_tree(&trees, ExcludeRules::default())],
+            assets.as_path(),
+            assets.as_path(),
+            &external,
+        );
+        assert_eq!(
+            analysis.dirty_paths,
+            DirtySet::from([Utf8PathBuf::from("../src/lib.rs")])
+        );
+        assert_eq!(analysis.stats.tree_dependency_paths, 1);
+        assert_eq!(analysis.stats.global_paths, 1);
+
+        let _ = fs::remove_dir_all(root);
+    }
+
     #[test]
     fn test_format_watch_change_stats_contains_all_counters() {
         let line = format_watch_change_stats(WatchChangeStats {
@@ -321,10 +516,11 @@ mod tests {
             global_paths: 1,
             ignored_temp_paths: 1,
             ignored_directory_paths: 1,
+            ignored_excluded_paths: 1,
         });
         assert_eq!(
             line,
-            "[watch] Stats: total=7, tree_source=2, tree_dependency=1, assets=1, global=1, ignored_temp=1, ignored_dir=1"
+            "[watch] Stats: total=7, tree_source=2, tree_dependency=1, assets=1, global=1, ignored_temp=1, ignored_dir=1, ignored_excluded=1"
         );
     }
 
@@ -2,7 +2,7 @@
 // Released under the GPL-3.0 license as described in the file LICENSE.
 // Authors: Kokic (@kokic)
 
-use std::{io::Write, sync::mpsc::RecvTimeoutError, time::Instant};
+use std::{collections::BTreeSet, io::Write, sync::mpsc::RecvTimeoutError, time::Instant};
 
 use camino::{Utf8Path, Utf8PathBuf};
 use notify::{Config, RecommendedWatcher, Watcher};
@@ -12,17 +12,23 @@ use super::strategy::{
     WatchBatcher, WatchChangeFoldState, WatchStrategy,
 };
 
+/// Watch `watched_paths` and the files in `dependencies`, and call `action` with
+/// each batch of changes. `action` returns the dependency files to watch from
+/// then on, so files referenced by a rebuild are watched without a restart.
+///
 /// from: https://github.com/notify-rs/notify/blob/main/examples/monitor_raw.rs#L18
 pub(in crate::cli::serve) fn watch_paths<P: AsRef<Utf8Path>, F>(
     watched_paths: &[P],
+    dependencies: BTreeSet<Utf8PathBuf>,
     assets_dir: &Utf8Path,
     mut action: F,
 ) -> eyre::Result<()>
 where
-    F: FnMut(&[Utf8PathBuf]) -> eyre::Result<()>,
+    F: FnMut(&[Utf8PathBuf]) -> eyre::Result<BTreeSet<Utf8PathBuf>>,
 {
     watch_paths_with_strategy(
         watched_paths,
+        dependencies,
         assets_dir,
         default_watch_strategy(),
         &mut action,
@@ -31,12 +37,13 @@ where
 
 fn watch_paths_with_strategy<P: AsRef<Utf8Path>, F>(
     watched_paths: &[P],
+    mut dependencies: BTreeSet<Utf8PathBuf>,
     assets_dir: &Utf8Path,
     strategy: WatchStrategy,
     action: &mut F,
 ) -> eyre::Result<()>
 where
-    F: FnMut(&[Utf8PathBuf]) -> eyre::Result<()>,
+    F: FnMut(&[Utf8PathBuf]) -> eyre::Result<BTreeSet<Utf8PathBuf>>,
 {
     let (tx, rx) = std::sync::mpsc::channel();
     let mut batcher = WatchBatcher::new(strategy.debounce);
@@ -75,6 +82,10 @@ where
         watcher.watch(watched_path.as_std_path(), mode)?;
         print!("\"{}\"  ", display_watch_path(watched_path));
     }
+    for dependency in &dependencies {
+        watch_dependency(&mut watcher, dependency);
+        print!("\"{}\"  ", display_watch_path(dependency));
+    }
     println!("\n\nPress Ctrl+C to stop watching.\n");
 
     loop {
@@ -109,7 +120,11 @@ where
                 let Some(changed_paths) = batcher.take_ready(Instant::now()) else {
                     continue;
                 };
-                process_batch(&changed_paths, &mut fold_state, strategy, action)?;
+                if let Some(next) =
+                    process_batch(&changed_paths, &mut fold_state, strategy, action)?
+                {
+                    update_dependencies(&mut watcher, &mut dependencies, next);
+                }
             }
             Err(RecvTimeoutError::Disconnected) => break,
         }
@@ -118,24 +133,59 @@ where
     Ok(())
 }
 
+/// The dependencies to watch after the batch, or `None` when the rebuild
+/// failed and the current ones stay.
 fn process_batch<F>(
     changed_paths: &[Utf8PathBuf],
     fold_state: &mut WatchChangeFoldState,
     strategy: WatchStrategy,
     action: &mut F,
-) -> eyre::Result<()>
+) -> eyre::Result<Option<BTreeSet<Utf8PathBuf>>>
 where
-    F: FnMut(&[Utf8PathBuf]) -> eyre::Result<()>,
+    F: FnMut(&[Utf8PathBuf]) -> eyre::Result<BTreeSet<Utf8PathBuf>>,
 {
     for line in (strategy.format_change_lines)(fold_state, changed_paths) {
         println!("{line}");
     }
     std::io::stdout().flush()?;
-    if let Err(err) = action(changed_paths) {
-        // A warning color should be used here, as rebuild failures during user editing are acceptable.
-        color_print::ceprintln!("<y>[watch] Rebuild failed: {}</>", err);
+    match action(changed_paths) {
+        Ok(dependencies) => Ok(Some(dependencies)),
+        Err(err) => {
+            // A warning color should be used here, as rebuild failures during user editing are acceptable.
+            color_print::ceprintln!("<y>[watch] Rebuild failed: {}</>", err);
+            Ok(None)
+        }
     }
-    Ok(())
+}
+
+fn watch_dependency(watcher: &mut RecommendedWatcher, path: &Utf8Path) {
+    if let Err(err) = watcher.watch(path.as_std_path(), watch_mode_for_path(path)) {
+        color_print::ceprintln!(
+            "<y>[watch] Warning: cannot watch \"{}\": {}</>",
+            display_watch_path(path),
+            err
+        );
+    }
+}
+
+/// Watch the dependencies that are new in `next` and stop watching the ones
+/// that are gone.
+fn update_dependencies(
+    watcher: &mut RecommendedWatcher,
+    dependencies: &mut BTreeSet<Utf8PathBuf>,
+    next: BTreeSet<Utf8PathBuf>,
+) {
+    for removed in dependencies.difference(&next) {
+        let _ = watcher.unwatch(removed.as_std_path());
+    }
+    for added in next.difference(dependencies) {
+        watch_dependency(watcher, added);
+        color_print::ceprintln!(
+            "<dim>[watch] Watching \"{}\".</>",
+            display_watch_path(added)
+        );
+    }
+    *dependencies = next;
 }
 
 fn collect_event_paths(event: notify::Event, strategy: WatchStrategy) -> Option<Vec<Utf8PathBuf>> {
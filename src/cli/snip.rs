@@ -4,10 +4,12 @@
 
 use std::collections::HashMap;
 
+use camino::Utf8PathBuf;
 use eyre::{eyre, WrapErr};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    atomic_text, cli,
     compiler::{self, section::HTMLContent},
     config, entry, environment,
     ordered_map::OrderedMap,
@@ -20,68 +22,119 @@ pub struct SnipCommand {
     #[arg(short, long, default_value_t = config::DEFAULT_CONFIG_PATH.into())]
     config: String,
 
-    /// Generate `.vscode/katex.code-snippets`.
+    /// Snippet format, which decides where the files are written.
+    #[arg(short, long, value_enum, default_value_t = SnippetFormat::Vscode)]
+    format: SnippetFormat,
+
+    /// Generate KaTeX snippets, e.g. `.vscode/katex.code-snippets`.
     #[arg(short, long)]
     katex: bool,
 
-    /// Generate `.vscode/section.code-snippets`.
+    /// Generate section link snippets, e.g. `.vscode/section.code-snippets`.
     #[arg(short, long)]
     section: bool,
 
-    /// Generate `.vscode/inline-section.code-snippets`.
+    /// Generate a snippet for each subtree tag, e.g. `.vscode/inline-section.code-snippets`.
     #[arg(short, long)]
     inline_section: bool,
 }
 
-#[derive(Serialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum SnippetFormat {
+    /// `.vscode/<name>.code-snippets`.
+    Vscode,
+    /// `.luasnip/markdown/<name>.lua`, for the LuaSnip Lua loader.
+    Luasnip,
+    /// `.ultisnips/markdown/<name>.snippets`.
+    Ultisnips,
+    /// `.helix/snippets/<name>.toml`, for snippet language servers.
+    Helix,
+}
+
+impl SnippetFormat {
+    fn path(self, name: &str) -> Utf8PathBuf {
+        let root = environment::root_dir();
+        match self {
+            SnippetFormat::Vscode => root.join(".vscode").join(format!("{name}.code-snippets")),
+            SnippetFormat::Luasnip => root.join(".luasnip/markdown").join(format!("{name}.lua")),
+            SnippetFormat::Ultisnips => root
+                .join(".ultisnips/markdown")
+                .join(format!("{name}.snippets")),
+            SnippetFormat::Helix => root.join(".helix/snippets").join(format!("{name}.toml")),
+        }
+    }
+}
+
+/// A snippet in VSCode form. Bodies use the TextMate syntax shared by VSCode,
+/// LuaSnip and language servers.
+#[derive(Serialize, Deserialize)]
 struct Snippet {
-    scope: &'static str,
+    scope: String,
     prefix: String,
-    body: [String; 1],
+    body: Vec<String>,
+    #[serde(default)]
     description: String,
 }
 
 impl Snippet {
-    fn new(scope: &'static str, prefix: String, body: [String; 1], description: String) -> Self {
+    fn md(prefix: String, body: String, description: String) -> Self {
         Self {
-            scope,
+            scope: "markdown".to_string(),
             prefix,
-            body,
+            body: vec![body],
             description,
         }
     }
 
-    fn md(prefix: String, body: [String; 1], description: String) -> Self {
-        Self::new("markdown", prefix, body, description)
+    fn body(&self) -> String {
+        self.body.join("\n")
     }
 }
 
+type Snippets = OrderedMap<String, Snippet>;
+
 /// This function invokes the [`environment::init_environment`] function to initialize the environment
 pub fn snip(command: &SnipCommand) -> eyre::Result<()> {
     let config_path = &command.config;
     environment::init_environment(config_path.into(), environment::BuildMode::Serve)?;
+    let format = command.format;
 
     if command.section {
-        section_snippets()?;
+        let indexes = refresh_section_indexes()?;
+        write_snippets(format, "section", &section_snippets(&indexes), None)?;
     }
 
     if command.katex {
-        katex_snippets()?;
+        // Generated by: https://github.com/kodama-community/vscode-katex-snippets
+        let json = include_str!("../include/katex-snippets.json");
+        write_bundled_snippets(format, "katex", json)?;
     }
 
     if command.inline_section {
-        inline_section_snippets()?;
+        let json = include_str!("../include/inline-section-snippets.json");
+        write_bundled_snippets(format, "inline-section", json)?;
     }
 
     Ok(())
 }
 
-fn section_snippets() -> eyre::Result<()> {
-    let indexes = refresh_section_indexes()?;
+/// Rewrite the section snippets from the sections known to `kodama serve`.
+/// Unchanged snippets are not written again.
+pub fn sync_serve_section_snippets(format: SnippetFormat) -> eyre::Result<()> {
+    let Some(indexes) = cli::build::serve_section_indexes()? else {
+        return Ok(());
+    };
+    write_snippets(format, "section", &section_snippets(&indexes), None)
+}
+
+fn section_snippets(indexes: &HashMap<Slug, OrderedMap<String, HTMLContent>>) -> Snippets {
+    let mut slugs: Vec<&Slug> = indexes.keys().collect();
+    slugs.sort();
 
-    let snippets: HashMap<&str, Snippet> = indexes
-        .iter()
-        .filter_map(|(slug, metadata)| {
+    slugs
+        .into_iter()
+        .filter_map(|slug| {
+            let metadata = &indexes[slug];
             let slug_str = slug.as_str();
             let title = metadata.get(entry::KEY_TITLE)?.remove_all_tags();
             let prefix = format!("{title} [{slug_str}]");
@@ -91,28 +144,16 @@ fn section_snippets() -> eyre::Result<()> {
 
             // We assume that the markdown editor used by the user
             // supports link label names that contain spaces
-            let body = [format!("[{title}]: {url}")];
+            let body = escape_textmate(&format!("[{title}]: {url}"));
             let description = metadata
                 .get(entry::KEY_TAXON)
                 .and_then(HTMLContent::as_str)
                 .unwrap_or("")
                 .to_string();
 
-            Some((slug_str, Snippet::md(prefix.to_string(), body, description)))
+            Some((slug_str.to_string(), Snippet::md(prefix, body, description)))
         })
-        .collect();
-
-    let snippets_path = environment::root_dir()
-        .join(".vscode")
-        .join("section.code-snippets");
-    environment::create_parent_dirs(&snippets_path);
-
-    let serialized = serde_json::to_string_pretty(&snippets)
-        .wrap_err_with(|| eyre!("failed to serialize snippets to JSON"))?;
-    std::fs::write(&snippets_path, serialized)
-        .wrap_err_with(|| eyre!("failed to write snippets to `{}`", snippets_path))?;
-
-    Ok(())
+        .collect()
 }
 
 fn refresh_section_indexes() -> eyre::Result<HashMap<Slug, OrderedMap<String, HTMLContent>>> {
@@ -124,29 +165,227 @@ fn refresh_section_indexes() -> eyre::Result<HashMap<Slug, OrderedMap<String, HT
         .wrap_err("failed to refresh in-memory section indexes")
 }
 
-fn katex_snippets() -> eyre::Result<()> {
-    let snippets_path = environment::root_dir()
-        .join(".vscode")
-        .join("katex.code-snippets");
-    environment::create_parent_dirs(&snippets_path);
+/// Bundled snippets are VSCode files, which are written as they are.
+fn write_bundled_snippets(format: SnippetFormat, name: &str, json: &str) -> eyre::Result<()> {
+    let snippets: Snippets = serde_json::from_str(json)
+        .wrap_err_with(|| eyre!("failed to parse bundled `{}` snippets", name))?;
+    write_snippets(format, name, &snippets, Some(json))
+}
 
-    // Generated by: https://github.com/kodama-community/vscode-katex-snippets
-    let json = include_str!("../include/katex-snippets.json");
-    std::fs::write(&snippets_path, json)
-        .wrap_err_with(|| eyre!("failed to write snippets to `{}`", snippets_path))?;
+fn write_snippets(
+    format: SnippetFormat,
+    name: &str,
+    snippets: &Snippets,
+    vscode_json: Option<&str>,
+) -> eyre::Result<()> {
+    let content = match (format, vscode_json) {
+        (SnippetFormat::Vscode, Some(json)) => json.to_string(),
+        (SnippetFormat::Vscode, None) => serde_json::to_string_pretty(snippets)
+            .wrap_err_with(|| eyre!("failed to serialize snippets to JSON"))?,
+        (SnippetFormat::Luasnip, _) => luasnip(snippets),
+        (SnippetFormat::Ultisnips, _) => ultisnips(snippets),
+        (SnippetFormat::Helix, _) => helix(snippets)?,
+    };
 
-    Ok(())
+    let snippets_path = format.path(name);
+    atomic_text::sync_text_output(&snippets_path, &content, "snippets")
+        .wrap_err_with(|| eyre!("failed to write snippets to `{}`", snippets_path))
 }
 
-fn inline_section_snippets() -> eyre::Result<()> {
-    let snippets_path = environment::root_dir()
-        .join(".vscode")
-        .join("inline-section.code-snippets");
-    environment::create_parent_dirs(&snippets_path);
+/// Escape literal text for a TextMate snippet body.
+fn escape_textmate(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '$' | '}') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
 
-    let json = include_str!("../include/inline-section-snippets.json");
-    std::fs::write(&snippets_path, json)
-        .wrap_err_with(|| eyre!("failed to write snippets to `{}`", snippets_path))?;
+/// A Lua file for `require("luasnip.loaders.from_lua")`, whose snippet
+/// environment provides `parse`.
+fn luasnip(snippets: &Snippets) -> String {
+    let mut lua = String::from("-- Generated by `kodama snip`.\nreturn {\n");
+    for snippet in snippets.values() {
+        lua.push_str(&format!(
+            "  parse({{ trig = {}, desc = {} }}, {}),\n",
+            lua_string(&snippet.prefix),
+            lua_string(&snippet.description),
+            lua_string(&snippet.body()),
+        ));
+    }
+    lua.push_str("}\n");
+    lua
+}
 
-    Ok(())
+fn lua_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn ultisnips(snippets: &Snippets) -> String {
+    let mut file = String::from("# Generated by `kodama snip`.\n");
+    for snippet in snippets.values() {
+        file.push_str("\nsnippet ");
+        file.push_str(&ultisnips_trigger(&snippet.prefix));
+        if !snippet.description.is_empty() {
+            file.push_str(&format!(" \"{}\"", snippet.description.replace('"', "'")));
+        }
+        file.push('\n');
+        file.push_str(&ultisnips_body(&snippet.body()));
+        file.push_str("\nendsnippet\n");
+    }
+    file
+}
+
+/// Triggers with spaces are enclosed in a character they do not contain.
+fn ultisnips_trigger(prefix: &str) -> String {
+    if !prefix.contains(char::is_whitespace) {
+        return prefix.to_string();
+    }
+    match ['"', '!', '|', '#', '%', '~', '^']
+        .into_iter()
+        .find(|delimiter| !prefix.contains(*delimiter))
+    {
+        Some(delimiter) => format!("{delimiter}{prefix}{delimiter}"),
+        None => prefix.split_whitespace().collect::<Vec<_>>().join("_"),
+    }
+}
+
+/// UltiSnips also escapes `{` and backticks, which TextMate bodies keep literal.
+fn ultisnips_body(body: &str) -> String {
+    let mut converted = String::with_capacity(body.len());
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.peek() {
+                Some(&next @ ('\\' | '$' | '}')) => {
+                    chars.next();
+                    converted.push('\\');
+                    converted.push(next);
+                }
+                _ => converted.push_str("\\\\"),
+            },
+            '`' => converted.push_str("\\`"),
+            c => converted.push(c),
+        }
+    }
+    converted
+}
+
+#[derive(Serialize)]
+struct HelixSnippets<'a> {
+    snippets: Vec<HelixSnippet<'a>>,
+}
+
+#[derive(Serialize)]
+struct HelixSnippet<'a> {
+    prefix: &'a str,
+    scope: [&'static str; 1],
+    body: String,
+    description: &'a str,
+}
+
+/// The TOML read by `simple-completion-language-server`. Every snippet is
+/// scoped to Markdown, where KaTeX is written in Kodama.
+fn helix(snippets: &Snippets) -> eyre::Result<String> {
+    let snippets = HelixSnippets {
+        snippets: snippets
+            .values()
+            .map(|snippet| HelixSnippet {
+                prefix: &snippet.prefix,
+                scope: ["markdown"],
+                body: snippet.body(),
+                description: &snippet.description,
+            })
+            .collect(),
+    };
+    toml::to_string(&snippets).wrap_err("failed to serialize snippets to TOML")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippets() -> Snippets {
+        let mut snippets = Snippets::new();
+        snippets.insert(
+            "groups".to_string(),
+            Snippet::md(
+                "Groups [groups]".to_string(),
+                escape_textmate("[Groups $G$]: /trees/groups.md"),
+                "Definition".to_string(),
+            ),
+        );
+        snippets.insert(
+            "set".to_string(),
+            Snippet {
+                scope: "latex".to_string(),
+                prefix: "\\set".to_string(),
+                body: vec!["\\{$1\\}`".to_string()],
+                description: String::new(),
+            },
+        );
+        snippets
+    }
+
+    #[test]
+    fn test_snippet_formats_keep_textmate_bodies() {
+        let snippets = snippets();
+        assert_eq!(
+            snippets["groups"].body(),
+            "[Groups \\$G\\$]: /trees/groups.md"
+        );
+
+        let lua = luasnip(&snippets);
+        assert!(
+            lua.contains(r#"parse({ trig = "Groups [groups]", desc = "Definition" }, "[Groups \\$G\\$]: /trees/groups.md"),"#),
+            "{lua}"
+        );
+        assert!(lua.contains(r#"parse({ trig = "\\set", desc = "" }, "\\{$1\\}`"),"#));
+
+        let ultisnips = ultisnips(&snippets);
+        assert!(ultisnips.contains(
+            "snippet \"Groups [groups]\" \"Definition\"\n[Groups \\$G\\$]: /trees/groups.md\nendsnippet\n"
+        ));
+        assert!(ultisnips.contains("snippet \\set\n\\\\{$1\\}\\`\nendsnippet\n"));
+
+        let helix = helix(&snippets).unwrap();
+        let parsed: toml::Value = toml::from_str(&helix).unwrap();
+        let first = &parsed["snippets"][0];
+        assert_eq!(first["prefix"].as_str(), Some("Groups [groups]"));
+        assert_eq!(first["scope"][0].as_str(), Some("markdown"));
+        assert_eq!(parsed["snippets"][1]["body"].as_str(), Some("\\{$1\\}`"));
+    }
+
+    #[test]
+    fn test_ultisnips_trigger_picks_an_unused_delimiter() {
+        assert_eq!(ultisnips_trigger("\\alpha"), "\\alpha");
+        assert_eq!(ultisnips_trigger("A \"B\" [b]"), "!A \"B\" [b]!");
+    }
+
+    #[test]
+    fn test_bundled_snippets_parse() {
+        for json in [
+            include_str!("../include/katex-snippets.json"),
+            include_str!("../include/inline-section-snippets.json"),
+        ] {
+            let snippets: Snippets = serde_json::from_str(json).unwrap();
+            assert!(!snippets.is_empty());
+        }
+    }
 }
@@ -2,16 +2,47 @@
 // Released under the GPL-3.0 license as described in the file LICENSE.
 // Authors: Kokic (@kokic), Spore (@s-cerevisiae)
 
-use camino::Utf8PathBuf;
+mod diff;
+
+use camino::{Utf8Path, Utf8PathBuf};
 use eyre::{eyre, WrapErr};
 
-use crate::config;
+use crate::{
+    compiler::ExcludeRules,
+    config,
+    vfs::{self, DiskFs, FileSystem},
+};
+
+/// A rewrite of source files introduced by a release, applied once by
+/// `kodama upgrade` to projects whose `content-version` is below `version`.
+///
+/// `migrate` returns `None` when a file needs no change. It must be
+/// idempotent: an interrupted upgrade leaves the version unchanged and
+/// applies the migration again on the next run.
+pub(crate) struct ContentMigration {
+    pub version: u32,
+    /// Release that introduced the migration, e.g. `0.6.0`.
+    pub release: &'static str,
+    pub description: &'static str,
+    /// Extensions of the source files to rewrite, e.g. `["md"]`.
+    pub extensions: &'static [&'static str],
+    pub migrate: fn(&str) -> Option<String>,
+}
+
+/// Content migrations in version order, starting at 1. The last version is
+/// [`config::kodama::CONTENT_VERSION`].
+const CONTENT_MIGRATIONS: &[ContentMigration] = &[];
 
 #[derive(clap::Args)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct UpgradeCommand {
     /// Optional subcommand. If omitted, behaves like `upgrade all`.
     #[command(subcommand)]
     pub command: Option<UpgradeSubcommand>,
+
+    /// Preview the changes of `upgrade all` as diffs without writing any file.
+    #[arg(long)]
+    pub dry_run: bool,
 }
 
 #[derive(clap::Subcommand)]
@@ -24,6 +55,10 @@ pub enum UpgradeSubcommand {
     #[command(visible_alias = "c")]
     Config(UpgradeConfigCommand),
 
+    /// Apply pending content migrations to the sources only.
+    #[command(visible_alias = "m")]
+    Content(UpgradeContentCommand),
+
     /// Sync trees/_lib/kodama.typ only.
     #[command(name = "typst-lib", visible_alias = "t")]
     TypstLib(UpgradeTypstLibCommand),
@@ -39,6 +74,10 @@ pub struct UpgradeAllCommand {
     /// Defaults to overwriting the source file.
     #[arg(short, long)]
     pub output: Option<String>,
+
+    /// Preview the changes as diffs without writing any file.
+    #[arg(long)]
+    pub dry_run: bool,
 }
 
 #[derive(clap::Args)]
@@ -51,6 +90,21 @@ pub struct UpgradeConfigCommand {
     /// Defaults to overwriting the source file.
     #[arg(short, long)]
     pub output: Option<String>,
+
+    /// Preview the changes as a diff without writing the config.
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(clap::Args)]
+pub struct UpgradeContentCommand {
+    /// Path to the source configuration file (e.g., "Kodama.toml").
+    #[arg(short, long, default_value_t = config::DEFAULT_CONFIG_PATH.into())]
+    pub config: String,
+
+    /// Preview the changes as diffs without writing any file.
+    #[arg(long)]
+    pub dry_run: bool,
 }
 
 #[derive(clap::Args)]
@@ -64,51 +118,224 @@ pub fn upgrade(command: &UpgradeCommand) -> eyre::Result<()> {
     match &command.command {
         Some(UpgradeSubcommand::All(args)) => run_upgrade_all(args),
         Some(UpgradeSubcommand::Config(args)) => run_upgrade_config(args),
+        Some(UpgradeSubcommand::Content(args)) => run_upgrade_content(args),
         Some(UpgradeSubcommand::TypstLib(args)) => run_upgrade_typst_lib(args),
         None => run_upgrade_all(&UpgradeAllCommand {
             config: config::DEFAULT_CONFIG_PATH.to_string(),
             output: None,
+            dry_run: command.dry_run,
         }),
     }
 }
 
 fn run_upgrade_all(command: &UpgradeAllCommand) -> eyre::Result<()> {
-    let upgraded = upgrade_config_file(&command.config, command.output.as_deref())?;
-    print_config_upgrade_message(
-        upgraded.source_path.as_path(),
-        upgraded.output_path.as_path(),
-    );
+    let source_path = resolve_config_path(&command.config)?;
+    let config = read_config(&source_path)?;
+    ensure_output_records_migrations(
+        &source_path,
+        command.output.as_deref(),
+        &config,
+        CONTENT_MIGRATIONS,
+    )?;
+    let content_version =
+        migrate_content(&source_path, &config, CONTENT_MIGRATIONS, command.dry_run)?;
+    let upgraded = upgrade_config_file(
+        &command.config,
+        command.output.as_deref(),
+        Some(content_version),
+        command.dry_run,
+    )?;
+    print_config_upgrade_message(&upgraded, command.dry_run);
+    if command.dry_run {
+        let typ_path = trees_lib_kodama_typ_path(upgraded.output_path.as_path(), &upgraded.config);
+        println!("Would sync Typst library: {}", typ_path);
+        return Ok(());
+    }
     let typ_path = sync_kodama_typ(upgraded.output_path.as_path(), &upgraded.config)?;
     println!("Synced Typst library: {}", typ_path);
     Ok(())
 }
 
 fn run_upgrade_config(command: &UpgradeConfigCommand) -> eyre::Result<()> {
-    let upgraded = upgrade_config_file(&command.config, command.output.as_deref())?;
-    print_config_upgrade_message(
-        upgraded.source_path.as_path(),
-        upgraded.output_path.as_path(),
-    );
+    let upgraded = upgrade_config_file(
+        &command.config,
+        command.output.as_deref(),
+        None,
+        command.dry_run,
+    )?;
+    print_config_upgrade_message(&upgraded, command.dry_run);
     Ok(())
 }
 
-fn run_upgrade_typst_lib(command: &UpgradeTypstLibCommand) -> eyre::Result<()> {
+fn run_upgrade_content(command: &UpgradeContentCommand) -> eyre::Result<()> {
     let source_path = resolve_config_path(&command.config)?;
-    let source = std::fs::read_to_string(&source_path)
+    let config = read_config(&source_path)?;
+    let content_version =
+        migrate_content(&source_path, &config, CONTENT_MIGRATIONS, command.dry_run)?;
+    if content_version == config.kodama.content_version {
+        return Ok(());
+    }
+    let upgraded = upgrade_config_file(
+        &command.config,
+        None,
+        Some(content_version),
+        command.dry_run,
+    )?;
+    print_config_upgrade_message(&upgraded, command.dry_run);
+    Ok(())
+}
+
+/// Migrations newer than the project's `content-version`.
+fn pending_migrations<'a>(
+    config: &config::Config,
+    migrations: &'a [ContentMigration],
+) -> Vec<&'a ContentMigration> {
+    let current = config.kodama.content_version;
+    migrations.iter().filter(|m| m.version > current).collect()
+}
+
+/// Content migrations rewrite the sources of the config that was read, so
+/// their version must be recorded in that config rather than in `--output`.
+fn ensure_output_records_migrations(
+    source_path: &Utf8Path,
+    output_path: Option<&str>,
+    config: &config::Config,
+    migrations: &[ContentMigration],
+) -> eyre::Result<()> {
+    let Some(output_path) = output_path else {
+        return Ok(());
+    };
+    let output_path = Utf8Path::new(output_path);
+    let same_file = match (
+        source_path.canonicalize_utf8(),
+        output_path.canonicalize_utf8(),
+    ) {
+        (Ok(source), Ok(output)) => source == output,
+        _ => source_path == output_path,
+    };
+    if same_file || pending_migrations(config, migrations).is_empty() {
+        return Ok(());
+    }
+    Err(eyre!(
+        "content migrations are pending for \"{}\", which `--output` would leave unrecorded; \
+         run `kodama upgrade content` first or drop `--output`",
+        source_path
+    ))
+}
+
+/// Apply the migrations newer than the project's `content-version` to its
+/// sources, printing a diff for every rewritten file, and return the content
+/// version reached. With `dry_run`, nothing is written.
+fn migrate_content(
+    config_path: &Utf8Path,
+    config: &config::Config,
+    migrations: &[ContentMigration],
+    dry_run: bool,
+) -> eyre::Result<u32> {
+    let pending = pending_migrations(config, migrations);
+    let Some(target) = pending.last().map(|m| m.version) else {
+        return Ok(config.kodama.content_version);
+    };
+    for migration in &pending {
+        println!(
+            "Content migration {} ({}): {}",
+            migration.version, migration.release, migration.description
+        );
+    }
+
+    let root = config_root(config_path);
+    let mut changed = 0;
+    for path in content_files(&root, config)? {
+        let extension = path.extension().unwrap_or_default();
+        let applicable: Vec<_> = pending
+            .iter()
+            .filter(|m| m.extensions.contains(&extension))
+            .collect();
+        if applicable.is_empty() {
+            continue;
+        }
+        let before = DiskFs
+            .read_to_string(&path)
+            .wrap_err_with(|| eyre!("failed to read source file \"{}\"", path))?;
+        let mut after = before.clone();
+        for migration in applicable {
+            if let Some(migrated) = (migration.migrate)(&after) {
+                after = migrated;
+            }
+        }
+        let label = path.strip_prefix(&root).unwrap_or(&path);
+        let Some(diff) = diff::unified_diff(label.as_str(), &before, &after) else {
+            continue;
+        };
+        diff::print_diff(&diff);
+        changed += 1;
+        if !dry_run {
+            DiskFs
+                .write_atomic(&path, after.as_bytes())
+                .wrap_err_with(|| eyre!("failed to write migrated source file \"{}\"", path))?;
+        }
+    }
+
+    match dry_run {
+        true => println!("Would migrate {changed} file(s) to content version {target}."),
+        false => println!("Migrated {changed} file(s) to content version {target}."),
+    }
+    Ok(target)
+}
+
+/// Source files of the main trees directory and all mounts, skipping hidden
+/// entries and those excluded by `[kodama].exclude` or `.kodamaignore`.
+fn content_files(root: &Utf8Path, config: &config::Config) -> eyre::Result<Vec<Utf8PathBuf>> {
+    let dirs = std::iter::once(config.kodama.trees.as_str())
+        .chain(config.kodama.mounts.iter().map(|mount| mount.path.as_str()));
+    let mut files = Vec::new();
+    for dir in dirs {
+        let dir = root.join(dir);
+        let rules = ExcludeRules::load_with(config.kodama.exclude.clone(), &dir)?;
+        let walked = vfs::walk_files(&DiskFs, &dir)
+            .wrap_err_with(|| eyre!("failed to scan source directory \"{}\"", dir))?;
+        files.extend(walked.into_iter().filter(|path| {
+            path.strip_prefix(&dir).is_ok_and(|relative| {
+                !relative.components().any(|c| c.as_str().starts_with('.'))
+                    && !rules.is_excluded(relative, false)
+            })
+        }));
+    }
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+fn config_root(config_path: &Utf8Path) -> Utf8PathBuf {
+    config_path
+        .parent()
+        .map(|p| p.to_owned())
+        .unwrap_or_else(|| Utf8PathBuf::from("."))
+}
+
+fn read_config(source_path: &Utf8Path) -> eyre::Result<config::Config> {
+    let source = std::fs::read_to_string(source_path)
         .wrap_err_with(|| eyre!("failed to read config file \"{}\"", source_path))?;
-    let config = config::parse_config(&source).wrap_err_with(|| {
-        eyre!(
-            "failed to parse config file \"{}\" while syncing Typst library",
-            source_path
-        )
-    })?;
+    config::parse_config(&source)
+        .wrap_err_with(|| eyre!("failed to parse config file \"{}\"", source_path))
+}
+
+fn run_upgrade_typst_lib(command: &UpgradeTypstLibCommand) -> eyre::Result<()> {
+    let source_path = resolve_config_path(&command.config)?;
+    let config = read_config(&source_path)?;
     let typ_path = sync_kodama_typ(source_path.as_path(), &config)?;
     println!("Synced Typst library: {}", typ_path);
     Ok(())
 }
 
-fn print_config_upgrade_message(source_path: &camino::Utf8Path, output_path: &camino::Utf8Path) {
-    if output_path == source_path {
+fn print_config_upgrade_message(upgraded: &UpgradedConfig, dry_run: bool) {
+    let (source_path, output_path) = (&upgraded.source_path, &upgraded.output_path);
+    if dry_run {
+        if let Some(diff) = &upgraded.diff {
+            diff::print_diff(diff);
+        }
+        println!("Would upgrade config at: {}", output_path);
+    } else if output_path == source_path {
         println!("Upgraded config at: {}", output_path);
     } else {
         println!(
@@ -131,31 +358,55 @@ struct UpgradedConfig {
     source_path: Utf8PathBuf,
     output_path: Utf8PathBuf,
     config: config::Config,
+    /// Diff from the source to the upgraded config, if they differ.
+    diff: Option<String>,
 }
 
+/// Rewrite the config into the current shape, recording `content_version`
+/// when given. With `dry_run`, nothing is written.
 fn upgrade_config_file(
     config_path: &str,
     output_path: Option<&str>,
+    content_version: Option<u32>,
+    dry_run: bool,
 ) -> eyre::Result<UpgradedConfig> {
     let source_path = resolve_config_path(config_path)?;
     let source = std::fs::read_to_string(&source_path)
         .wrap_err_with(|| eyre!("failed to read config file \"{}\"", source_path))?;
-    let (upgraded, upgraded_config) = upgrade_content(&source)?;
+    let (upgraded, upgraded_config) = upgrade_content(&source, content_version)?;
+    for warning in config::validate::validate_config(&source) {
+        color_print::ceprintln!(
+            "<y>Warning: {}:{}: {}</>",
+            source_path,
+            warning.line,
+            warning.message
+        );
+    }
 
     let output_path = output_path
         .map(Utf8PathBuf::from)
         .unwrap_or_else(|| source_path.clone());
-    std::fs::write(&output_path, upgraded)
-        .wrap_err_with(|| eyre!("failed to write upgraded config to \"{}\"", output_path))?;
+    let diff = diff::unified_diff(output_path.as_str(), &source, &upgraded);
+    if !dry_run {
+        std::fs::write(&output_path, upgraded)
+            .wrap_err_with(|| eyre!("failed to write upgraded config to \"{}\"", output_path))?;
+    }
     Ok(UpgradedConfig {
         source_path,
         output_path,
         config: upgraded_config,
+        diff,
     })
 }
 
-fn upgrade_content(source: &str) -> eyre::Result<(String, config::Config)> {
-    let config = config::parse_config(source)?;
+fn upgrade_content(
+    source: &str,
+    content_version: Option<u32>,
+) -> eyre::Result<(String, config::Config)> {
+    let mut config = config::parse_config(source)?;
+    if let Some(content_version) = content_version {
+        config.kodama.content_version = content_version;
+    }
     let mut upgraded =
         toml::to_string(&config).wrap_err("failed to serialize upgraded configuration")?;
     if !upgraded.ends_with('\n') {
@@ -168,11 +419,8 @@ fn trees_lib_kodama_typ_path(
     config_path: &camino::Utf8Path,
     config: &config::Config,
 ) -> Utf8PathBuf {
-    let root = config_path
-        .parent()
-        .map(|p| p.to_owned())
-        .unwrap_or_else(|| Utf8PathBuf::from("."));
-    root.join(&config.kodama.trees)
+    config_root(config_path)
+        .join(&config.kodama.trees)
         .join("_lib")
         .join("kodama.typ")
 }
@@ -208,7 +456,7 @@ base-url = "https://example.com/"
 [build]
 output = "./dist"
 "#;
-        let (upgraded, _) = upgrade_content(source).unwrap();
+        let (upgraded, _) = upgrade_content(source, None).unwrap();
         assert!(upgraded.contains("[kodama]"));
         assert!(upgraded.contains("base-url = \"https://example.com/\""));
         assert!(upgraded.contains("[toc]"));
@@ -221,7 +469,7 @@ output = "./dist"
 
     #[test]
     fn test_upgrade_content_output_is_parseable() {
-        let (upgraded, config) = upgrade_content("").unwrap();
+        let (upgraded, config) = upgrade_content("", None).unwrap();
         let parsed = config::parse_config(&upgraded).unwrap();
         assert_eq!(parsed.kodama.trees, "trees");
         assert_eq!(parsed.build.output, "./publish");
@@ -281,7 +529,9 @@ trees = "content"
             command: Some(UpgradeSubcommand::All(UpgradeAllCommand {
                 config: source_config.to_string(),
                 output: None,
+                dry_run: false,
             })),
+            dry_run: false,
         })
         .unwrap();
 
@@ -295,6 +545,157 @@ trees = "content"
         let _ = std::fs::remove_dir_all(root.as_std_path());
     }
 
+    fn rename_author_key(source: &str) -> Option<String> {
+        source
+            .contains("author:")
+            .then(|| source.replace("author:", "authors:"))
+    }
+
+    fn list_authors(source: &str) -> Option<String> {
+        source
+            .contains("authors: kokic")
+            .then(|| source.replace("authors: kokic", "authors: [kokic]"))
+    }
+
+    const TEST_MIGRATIONS: &[ContentMigration] = &[
+        ContentMigration {
+            version: 1,
+            release: "0.0.1",
+            description: "rename `author` to `authors`",
+            extensions: &["md"],
+            migrate: rename_author_key,
+        },
+        ContentMigration {
+            version: 2,
+            release: "0.0.2",
+            description: "make `authors` a list",
+            extensions: &["md"],
+            migrate: list_authors,
+        },
+    ];
+
+    #[test]
+    fn test_content_migrations_end_at_current_content_version() {
+        for (index, migration) in CONTENT_MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version as usize, index + 1);
+        }
+        assert_eq!(
+            CONTENT_MIGRATIONS.last().map_or(0, |m| m.version),
+            config::kodama::CONTENT_VERSION
+        );
+    }
+
+    #[test]
+    fn test_migrate_content_applies_pending_migrations_in_order() {
+        let root = crate::test_io::case_dir("upgrade-content-migrations");
+        let config_path = root.join("Kodama.toml");
+        let mut cfg = config::Config::default();
+        cfg.kodama.content_version = 0;
+        cfg.kodama.exclude.push("drafts/".to_string());
+        cfg.kodama.mounts.push(config::kodama::Mount {
+            path: "shared".to_string(),
+            prefix: "shared".to_string(),
+            edit: None,
+        });
+        let source = "---\nauthor: kokic\n---\n\nBody.\n";
+        for path in [
+            "trees/a.md",
+            "trees/b.typ",
+            "trees/.drafts/c.md",
+            "shared/d.md",
+            "trees/drafts/e.md",
+            "shared/f.wip.md",
+        ] {
+            std::fs::create_dir_all(root.join(path).parent().unwrap()).unwrap();
+            std::fs::write(root.join(path), source).unwrap();
+        }
+        std::fs::write(root.join("shared/.kodamaignore"), "*.wip.md\n").unwrap();
+        let read = |path: &str| std::fs::read_to_string(root.join(path)).unwrap();
+
+        let version = migrate_content(&config_path, &cfg, TEST_MIGRATIONS, true).unwrap();
+        assert_eq!(version, 2);
+        assert_eq!(read("trees/a.md"), source);
+
+        let version = migrate_content(&config_path, &cfg, TEST_MIGRATIONS, false).unwrap();
+        assert_eq!(version, 2);
+        let migrated = "---\nauthors: [kokic]\n---\n\nBody.\n";
+        assert_eq!(read("trees/a.md"), migrated);
+        assert_eq!(read("shared/d.md"), migrated);
+        assert_eq!(read("trees/b.typ"), source);
+        assert_eq!(read("trees/.drafts/c.md"), source);
+        assert_eq!(read("trees/drafts/e.md"), source);
+        assert_eq!(read("shared/f.wip.md"), source);
+
+        std::fs::write(root.join("trees/a.md"), source).unwrap();
+        cfg.kodama.content_version = 1;
+        migrate_content(&config_path, &cfg, TEST_MIGRATIONS, false).unwrap();
+        assert_eq!(read("trees/a.md"), source);
+
+        cfg.kodama.content_version = 2;
+        assert_eq!(
+            migrate_content(&config_path, &cfg, TEST_MIGRATIONS, false).unwrap(),
+            2
+        );
+
+        let _ = std::fs::remove_dir_all(root.as_std_path());
+    }
+
+    #[test]
+    fn test_upgrade_all_refuses_output_with_pending_migrations() {
+        let source_path = Utf8Path::new("site/Kodama.toml");
+        let mut cfg = config::Config::default();
+        cfg.kodama.content_version = 0;
+        let ensure = |output, cfg: &config::Config| {
+            ensure_output_records_migrations(source_path, output, cfg, TEST_MIGRATIONS)
+        };
+
+        assert!(ensure(None, &cfg).is_ok());
+        assert!(ensure(Some("site/Kodama.toml"), &cfg).is_ok());
+        let err = ensure(Some("Kodama.new.toml"), &cfg).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("content migrations are pending"));
+
+        cfg.kodama.content_version = 2;
+        assert!(ensure(Some("Kodama.new.toml"), &cfg).is_ok());
+    }
+
+    #[test]
+    fn test_upgrade_all_records_content_version_and_dry_run_writes_nothing() {
+        let root = crate::test_io::case_dir("upgrade-content-version");
+        std::fs::create_dir_all(root.as_std_path()).unwrap();
+        let source_config = root.join("Kodama.toml");
+        let source = "[kodama]\ntrees = \"content\"\n";
+        std::fs::write(source_config.as_std_path(), source).unwrap();
+        let upgrade_all = |dry_run| {
+            upgrade(&UpgradeCommand {
+                command: Some(UpgradeSubcommand::All(UpgradeAllCommand {
+                    config: source_config.to_string(),
+                    output: None,
+                    dry_run,
+                })),
+                dry_run: false,
+            })
+            .unwrap()
+        };
+
+        upgrade_all(true);
+        let unchanged = std::fs::read_to_string(source_config.as_std_path()).unwrap();
+        assert_eq!(unchanged, source);
+        assert!(!root.join("content/_lib/kodama.typ").exists());
+
+        upgrade_all(false);
+        let upgraded = std::fs::read_to_string(source_config.as_std_path()).unwrap();
+        let parsed = config::parse_config(&upgraded).unwrap();
+        assert!(upgraded.contains("content-version = "));
+        assert_eq!(
+            parsed.kodama.content_version,
+            config::kodama::CONTENT_VERSION
+        );
+
+        let _ = std::fs::remove_dir_all(root.as_std_path());
+    }
+
     #[test]
     fn test_upgrade_config_subcommand_only_writes_config() {
         let root = crate::test_io::case_dir("upgrade-config-only");
@@ -313,7 +714,9 @@ trees = "content"
             command: Some(UpgradeSubcommand::Config(UpgradeConfigCommand {
                 config: source_config.to_string(),
                 output: None,
+                dry_run: false,
             })),
+            dry_run: false,
         })
         .unwrap();
 
@@ -340,6 +743,7 @@ trees = "content"
             command: Some(UpgradeSubcommand::TypstLib(UpgradeTypstLibCommand {
                 config: source_config.to_string(),
             })),
+            dry_run: false,
         })
         .unwrap();
 
@@ -0,0 +1,178 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+//! Line-based unified diffs for the previews of `kodama upgrade`.
+
+/// Unchanged lines shown around each change.
+const CONTEXT: usize = 3;
+
+/// Largest LCS table computed; beyond it the changed region is shown as one
+/// replaced block.
+const MAX_TABLE_CELLS: usize = 1 << 22;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Unified diff of `before` and `after` labelled with `path`, or `None` when
+/// both are equal.
+pub(super) fn unified_diff(path: &str, before: &str, after: &str) -> Option<String> {
+    if before == after {
+        return None;
+    }
+    let old: Vec<&str> = before.lines().collect();
+    let new: Vec<&str> = after.lines().collect();
+    let ops = diff_lines(&old, &new);
+
+    let mut diff = format!("--- {path}\n+++ {path}\n");
+    let mut positions = Vec::with_capacity(ops.len() + 1);
+    let (mut old_line, mut new_line) = (0, 0);
+    for (op, _) in &ops {
+        positions.push((old_line, new_line));
+        old_line += usize::from(*op != Op::Insert);
+        new_line += usize::from(*op != Op::Delete);
+    }
+    positions.push((old_line, new_line));
+
+    for (start, end) in hunks(&ops) {
+        let (old_start, new_start) = positions[start];
+        let (old_end, new_end) = positions[end];
+        diff.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(old_start, old_end - old_start),
+            hunk_range(new_start, new_end - new_start)
+        ));
+        for (op, line) in &ops[start..end] {
+            let marker = match op {
+                Op::Equal => ' ',
+                Op::Delete => '-',
+                Op::Insert => '+',
+            };
+            diff.push(marker);
+            diff.push_str(line);
+            diff.push('\n');
+        }
+    }
+    Some(diff)
+}
+
+/// Print a diff from [`unified_diff`] with the usual colors.
+pub(super) fn print_diff(diff: &str) {
+    for line in diff.lines() {
+        if line.starts_with("--- ") || line.starts_with("+++ ") {
+            color_print::cprintln!("<s>{}</>", line);
+        } else if line.starts_with("@@") {
+            color_print::cprintln!("<c>{}</>", line);
+        } else if line.starts_with('-') {
+            color_print::cprintln!("<r>{}</>", line);
+        } else if line.starts_with('+') {
+            color_print::cprintln!("<g>{}</>", line);
+        } else {
+            println!("{}", line);
+        }
+    }
+}
+
+fn hunk_range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{start},0"),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{len}", start + 1),
+    }
+}
+
+/// Op ranges of the hunks, each change padded with [`CONTEXT`] lines and
+/// overlapping hunks merged.
+fn hunks(ops: &[(Op, &str)]) -> Vec<(usize, usize)> {
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for (index, _) in ops
+        .iter()
+        .enumerate()
+        .filter(|(_, (op, _))| *op != Op::Equal)
+    {
+        let start = index.saturating_sub(CONTEXT);
+        let end = (index + 1 + CONTEXT).min(ops.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    hunks
+}
+
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(Op, &'a str)> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    let mut ops: Vec<_> = old[..prefix]
+        .iter()
+        .map(|line| (Op::Equal, *line))
+        .collect();
+    let width = b.len() + 1;
+    if (a.len() + 1).saturating_mul(width) > MAX_TABLE_CELLS {
+        ops.extend(a.iter().map(|line| (Op::Delete, *line)));
+        ops.extend(b.iter().map(|line| (Op::Insert, *line)));
+    } else {
+        // `lcs[i * width + j]` is the LCS length of `a[i..]` and `b[j..]`.
+        let mut lcs = vec![0usize; (a.len() + 1) * width];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lcs[i * width + j] = match a[i] == b[j] {
+                    true => lcs[(i + 1) * width + j + 1] + 1,
+                    false => lcs[(i + 1) * width + j].max(lcs[i * width + j + 1]),
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() || j < b.len() {
+            if i < a.len() && j < b.len() && a[i] == b[j] {
+                ops.push((Op::Equal, a[i]));
+                i += 1;
+                j += 1;
+            } else if j == b.len()
+                || (i < a.len() && lcs[(i + 1) * width + j] >= lcs[i * width + j + 1])
+            {
+                ops.push((Op::Delete, a[i]));
+                i += 1;
+            } else {
+                ops.push((Op::Insert, b[j]));
+                j += 1;
+            }
+        }
+    }
+    ops.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .map(|line| (Op::Equal, *line)),
+    );
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff_groups_changes_into_hunks() {
+        let before = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\n";
+        let after = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\n";
+        assert_eq!(
+            unified_diff("trees/x.md", before, after).unwrap(),
+            "--- trees/x.md\n+++ trees/x.md\n\
+             @@ -1,5 +1,5 @@\n a\n-b\n+B\n c\n d\n e\n\
+             @@ -10,3 +10,4 @@\n j\n k\n l\n+m\n"
+        );
+        assert_eq!(unified_diff("x", before, before), None);
+    }
+}
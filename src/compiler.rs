@@ -4,11 +4,19 @@
 
 mod anonymous_slug;
 mod artifacts;
+mod cache_gc;
 pub mod callback;
+mod computed;
 pub mod counter;
 pub mod custom_tag;
+mod defaults;
+mod dependency;
+mod exclude;
+pub mod graph_export;
 mod incremental;
+mod local_graph;
 pub mod parser;
+mod plugin;
 mod rss;
 pub mod section;
 mod serve_session;
@@ -20,11 +28,7 @@ pub mod taxon;
 pub mod typst;
 pub mod writer;
 
-use std::{
-    collections::{HashMap, HashSet},
-    fs::File,
-    io::BufReader,
-};
+use std::collections::{HashMap, HashSet};
 
 use camino::{Utf8Path, Utf8PathBuf};
 use eyre::{eyre, WrapErr};
@@ -49,7 +53,13 @@ use self::{
     stale::cleanup_stale_slug_artifacts,
 };
 
+pub use cache_gc::collect_cache_garbage;
+pub(crate) use computed::{format_system_time, SourceTimes};
+pub use dependency::recorded_dependencies;
+pub(crate) use dependency::{record_dependency, record_math, record_root_dependency};
+pub use exclude::{ExcludeRules, IGNORE_FILE_NAME};
 pub use incremental::expand_dirty_paths;
+pub use plugin::run_plugins;
 pub use serve_session::ServeCompileSession;
 pub use source_scan::{
     all_trees_source, sync_typst_svg_assets, Workspace,
@@ -78,6 +88,12 @@ impl Default for CompileOutputs {
 #[derive(Debug, Serialize, Deserialize)]
 pub(super) struct CachedSourceEntry {
     pub sections: Vec<CachedSection>,
+    /// Files (relative to the trees directory) read while parsing this source.
+    #[serde(default)]
+    pub dependencies: Vec<Utf8PathBuf>,
+    /// Renderings under `.cache/math` used by this source.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub math: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -94,6 +110,7 @@ pub fn compile(
     let stale_slugs = cleanup_stale_slug_artifacts(&workspace)
         .wrap_err("failed to clean stale slug artifacts")?;
     let shallows = collect_shallows(&workspace, dirty_paths)?;
+    let shallows = run_plugins(&shallows)?;
     compile_from_shallows(&workspace, &shallows, dirty_paths, outputs, stale_slugs)
 }
 
@@ -102,9 +119,37 @@ pub fn refresh_indexes(
     dirty_paths: Option<&DirtySet>,
 ) -> eyre::Result<HashMap<Slug, OrderedMap<String, HTMLContent>>> {
     let shallows = collect_shallows(workspace, dirty_paths)?;
+    let shallows = run_plugins(&shallows)?;
     Ok(indexes_from_shallows(&shallows))
 }
 
+/// Parse the workspace through the entry cache and link its sections, without
+/// writing any output.
+pub fn compile_workspace(
+    workspace: &Workspace,
+) -> eyre::Result<(UnresolvedSections, state::CompileState)> {
+    let shallows = parse_workspace(workspace)?;
+    let state = state::compile_all(&shallows)?;
+    Ok((shallows, state))
+}
+
+/// Parse the workspace through the entry cache and run the configured plugins.
+pub fn parse_workspace(workspace: &Workspace) -> eyre::Result<UnresolvedSections> {
+    let shallows = collect_shallows(workspace, None)?;
+    Ok(run_plugins(&shallows)?.into_owned())
+}
+
+/// A local graph deeper than one hop can change when a section several hops
+/// away does, which [`affected_slugs_from_dirty`] doesn't follow, so such
+/// builds rewrite every page.
+fn has_multi_hop_local_graphs(shallows: &UnresolvedSections) -> bool {
+    environment::local_graph_depth() > 1
+        && shallows
+            .values()
+            .any(|section| section.metadata.local_graph_enabled().unwrap_or(false))
+}
+
+/// Link and write `shallows`, which the plugins have already processed.
 pub(super) fn compile_from_shallows(
     workspace: &Workspace,
     shallows: &UnresolvedSections,
@@ -118,13 +163,15 @@ pub(super) fn compile_from_shallows(
         .collect();
     all_slugs.sort();
 
-    let indexes = outputs.indexes.then(|| indexes_from_shallows(shallows));
-
     let state = state::compile_all(shallows)?;
     let slugs_to_write: Vec<Slug> = match dirty_paths {
         Some(dirty_paths) => {
             let dirty_slugs = dirty_source_slugs(workspace, dirty_paths);
-            if !stale_slugs.is_empty() {
+            // Plugins may change sections whose sources did not change.
+            if !stale_slugs.is_empty()
+                || has_multi_hop_local_graphs(shallows)
+                || !environment::plugins().is_empty()
+            {
                 all_slugs.clone()
             } else if dirty_slugs.is_empty() {
                 Vec::new()
@@ -144,9 +191,42 @@ pub(super) fn compile_from_shallows(
 
     Writer::write_needed_slugs(slugs_to_write, &state)
         .wrap_err("failed to write compiled HTML files")?;
+    write_artifacts(shallows, &state, outputs)
+}
 
+/// Write the page of every section in `state`, which was compiled from
+/// `shallows`, and the selected artifacts, without parsing or compiling again.
+/// Outputs of sources missing from `workspace` are removed as in a build.
+pub(crate) fn write_compiled(
+    workspace: &Workspace,
+    shallows: &UnresolvedSections,
+    state: &state::CompileState,
+    outputs: CompileOutputs,
+) -> eyre::Result<()> {
+    cleanup_stale_slug_artifacts(workspace).wrap_err("failed to clean stale slug artifacts")?;
+    let mut slugs: Vec<Slug> = state
+        .compiled()
+        .keys()
+        .filter(|slug| {
+            shallows
+                .get(slug)
+                .is_some_and(|section| !is_internal_anonymous_subtree(section))
+        })
+        .copied()
+        .collect();
+    slugs.sort();
+    Writer::write_needed_slugs(slugs, state).wrap_err("failed to write compiled HTML files")?;
+    write_artifacts(shallows, state, outputs)
+}
+
+fn write_artifacts(
+    shallows: &UnresolvedSections,
+    state: &state::CompileState,
+    outputs: CompileOutputs,
+) -> eyre::Result<()> {
+    let indexes = outputs.indexes.then(|| indexes_from_shallows(shallows));
     let graph_payload = if outputs.graph {
-        let graph = graph_snapshot(&state);
+        let graph = graph_snapshot(state);
         Some(
             serde_json::to_string(&graph)
                 .wrap_err_with(|| eyre!("failed to serialize graph to JSON"))?,
@@ -178,7 +258,7 @@ pub(super) fn compile_from_shallows(
         let feed_path = environment::feed_path(output_dir.as_path());
         let feed_payload = if environment::publish_rss() {
             rss::ensure_publish_rss_base_url_is_absolute()?;
-            Some(rss::feed_xml(&state)?)
+            Some(rss::feed_xml(state)?)
         } else {
             None
         };
@@ -198,7 +278,7 @@ fn indexes_from_shallows(
         .collect()
 }
 
-fn is_internal_anonymous_subtree(section: &UnresolvedSection) -> bool {
+pub(crate) fn is_internal_anonymous_subtree(section: &UnresolvedSection) -> bool {
     section
         .metadata
         .get_str(KEY_INTERNAL_ANON_SUBTREE)
@@ -218,9 +298,11 @@ pub(super) fn collect_shallows_with_sources(
 ) -> eyre::Result<(UnresolvedSections, SourceSectionsIndex)> {
     let mut shallows = HashMap::new();
     let mut source_sections = HashMap::new();
+    let mut times = SourceTimes::new();
 
     for (&source_slug, &ext) in &workspace.slug_exts {
-        let sections = load_shallow_sections(source_slug, ext, dirty_paths)?;
+        let mut sections = load_shallow_sections(source_slug, ext, dirty_paths)?;
+        times.insert(source_slug, ext, &mut sections)?;
         let produced_slugs: Vec<Slug> = sections.iter().map(|(slug, _)| *slug).collect();
 
         for (slug, shallow) in sections {
@@ -243,11 +325,24 @@ pub(crate) fn parse_source_sections(source_slug: Slug, ext: Ext) -> eyre::Result
     let mut sections = match ext {
         Ext::Markdown => parse_markdown_sections(source_slug)
             .wrap_err_with(|| eyre!("failed to parse markdown file `{source_slug}.{ext}`"))?,
-        Ext::Typst => parse_typst_sections(source_slug, environment::typst_root_dir())
-            .wrap_err_with(|| eyre!("failed to parse typst file `{source_slug}.{ext}`"))?,
+        Ext::Typst => {
+            let relative = source_relative_path(source_slug, ext);
+            let (mount, inner) = environment::locate_tree_path(&relative);
+            let root_dir = if mount.is_main() {
+                environment::typst_root_dir()
+            } else {
+                mount.dir
+            };
+            parse_typst_sections(source_slug, root_dir, &inner)
+                .wrap_err_with(|| eyre!("failed to parse typst file `{source_slug}.{ext}`"))?
+        }
     };
 
+    let defaults = defaults::directory_defaults(source_slug, ext)?;
     for (_, section) in &mut sections {
+        // Subtrees get them too, after their own attributes such as the taxon.
+        defaults::apply_defaults(&mut section.metadata, &defaults);
+        computed::insert_computed(section);
         section.metadata.compute_textual_attrs();
     }
     Ok(sections)
@@ -256,6 +351,7 @@ pub(crate) fn parse_source_sections(source_slug: Slug, ext: Ext) -> eyre::Result
 pub(super) fn write_entry_cache(
     entry_path: &Utf8Path,
     sections: &[(Slug, UnresolvedSection)],
+    recorded: dependency::Recorded,
 ) -> eyre::Result<()> {
     let serialized = serde_json::to_string(&CachedSourceEntry {
         sections: sections
@@ -265,20 +361,22 @@ pub(super) fn write_entry_cache(
                 section: section.clone(),
             })
             .collect(),
+        dependencies: recorded.dependencies,
+        math: recorded.math,
     })
     .wrap_err_with(|| eyre!("failed to serialize entry for `{}`", entry_path))?;
-    std::fs::write(entry_path, serialized)
+    environment::fs()
+        .write(entry_path, serialized.as_bytes())
         .wrap_err_with(|| eyre!("failed to write entry to `{}`", entry_path))?;
     Ok(())
 }
 
 fn read_entry_cache(entry_path: &Utf8Path, source_slug: Slug) -> eyre::Result<ParsedSections> {
-    let entry_file = BufReader::new(
-        File::open(entry_path)
-            .wrap_err_with(|| eyre!("failed to open entry file at `{}`", entry_path))?,
-    );
+    let entry = environment::fs()
+        .read(entry_path)
+        .wrap_err_with(|| eyre!("failed to open entry file at `{}`", entry_path))?;
 
-    if let Ok(cached) = serde_json::from_reader::<_, CachedSourceEntry>(entry_file) {
+    if let Ok(cached) = serde_json::from_slice::<CachedSourceEntry>(&entry) {
         return Ok(cached
             .sections
             .into_iter()
@@ -287,11 +385,7 @@ fn read_entry_cache(entry_path: &Utf8Path, source_slug: Slug) -> eyre::Result<Pa
     }
 
     // Backward compatibility: older versions cached a single section value.
-    let entry_file = BufReader::new(
-        File::open(entry_path)
-            .wrap_err_with(|| eyre!("failed to reopen entry file at `{}`", entry_path))?,
-    );
-    let section: UnresolvedSection = serde_json::from_reader(entry_file)
+    let section: UnresolvedSection = serde_json::from_slice(&entry)
         .wrap_err_with(|| eyre!("failed to deserialize entry file at `{}`", entry_path))?;
     Ok(vec![(source_slug, section)])
 }
@@ -304,9 +398,12 @@ fn load_shallow_sections(
     let relative_path = source_relative_path(source_slug, ext);
     let is_modified = is_source_modified(relative_path.as_path(), dirty_paths)
         .wrap_err_with(|| eyre!("failed to verify hash of `{relative_path}`"))?;
+    // With a dirty set, changed defaults files reach sources as recorded dependencies.
+    let is_defaults_modified =
+        dirty_paths.is_none() && defaults::is_defaults_modified(relative_path.as_path())?;
     let entry_path = environment::entry_file_path(&relative_path);
 
-    if !is_modified && entry_path.exists() {
+    if !is_modified && !is_defaults_modified && environment::fs().exists(&entry_path) {
         let mut sections = read_entry_cache(entry_path.as_path(), source_slug)?;
         for (_, section) in &mut sections {
             section.metadata.compute_textual_attrs();
@@ -314,7 +411,9 @@ fn load_shallow_sections(
         return Ok(sections);
     }
 
-    let sections = parse_source_sections(source_slug, ext)?;
-    write_entry_cache(entry_path.as_path(), &sections)?;
+    let (sections, recorded) = dependency::parse_with_dependencies(source_slug, ext)?;
+    if !environment::is_check() {
+        write_entry_cache(entry_path.as_path(), &sections, recorded)?;
+    }
     Ok(sections)
 }
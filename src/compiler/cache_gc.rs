@@ -0,0 +1,365 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+use std::collections::HashSet;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use eyre::{eyre, WrapErr};
+
+use crate::{
+    environment::{self, TreeMount},
+    path_utils,
+    slug::Slug,
+    vfs::{self, FileSystem},
+};
+
+use super::{
+    stale::{read_cached_slugs, remove_file_if_exists, same_ext, source_from_entry_relative_path},
+    Workspace,
+};
+
+/// Summary of a cache garbage collection pass.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheGcReport {
+    pub entry_files: usize,
+    pub hash_files: usize,
+    pub math_files: usize,
+    pub artifact_files: usize,
+    pub reclaimed_bytes: u64,
+}
+
+impl CacheGcReport {
+    pub fn removed_files(&self) -> usize {
+        self.entry_files + self.hash_files + self.math_files + self.artifact_files
+    }
+}
+
+pub(super) struct CacheGcPaths<'a> {
+    pub mounts: &'a [TreeMount],
+    pub entry_dir: &'a Utf8Path,
+    pub hash_dir: &'a Utf8Path,
+    pub math_dir: &'a Utf8Path,
+    pub output_dirs: &'a [Utf8PathBuf],
+    pub assets_dir_name: Option<&'a str>,
+}
+
+/// Remove entry caches, hash records, math renderings and generated Typst
+/// artifacts that are no longer referenced by `workspace`. Only outputs with a
+/// hash record of their own or of their Typst source are removed, so files the
+/// user put in the output directory, such as `404.html`, stay.
+pub fn collect_cache_garbage(workspace: &Workspace) -> eyre::Result<CacheGcReport> {
+    let assets_dir = environment::assets_dir();
+    collect_cache_garbage_with_paths(
+        workspace,
+        CacheGcPaths {
+            mounts: &environment::tree_mounts(),
+            entry_dir: environment::entry_dir().as_path(),
+            hash_dir: environment::hash_dir().as_path(),
+            math_dir: environment::math_dir().as_path(),
+            output_dirs: &environment::output_dirs(),
+            assets_dir_name: assets_dir.file_name(),
+        },
+    )
+}
+
+pub(super) fn collect_cache_garbage_with_paths(
+    workspace: &Workspace,
+    paths: CacheGcPaths<'_>,
+) -> eyre::Result<CacheGcReport> {
+    let mut report = CacheGcReport::default();
+    let mut live_slugs: HashSet<Slug> = workspace.slug_exts.keys().copied().collect();
+    let mut live_math: HashSet<String> = HashSet::new();
+
+    for (entry_path, relative) in walk_files(paths.entry_dir)? {
+        let live_slug = source_from_entry_relative_path(&relative)
+            .filter(|(_, slug, ext)| {
+                workspace
+                    .slug_exts
+                    .get(slug)
+                    .is_some_and(|current| same_ext(*current, *ext))
+            })
+            .map(|(_, slug, _)| slug);
+        if let Some(slug) = live_slug {
+            live_slugs.extend(read_cached_slugs(entry_path.as_path(), slug));
+            live_math.extend(read_cached_math(entry_path.as_path()));
+            continue;
+        }
+        report.reclaimed_bytes += remove_counted(entry_path.as_path())?;
+        report.entry_files += 1;
+    }
+
+    let hash_files = walk_files(paths.hash_dir)?;
+    // Read before stale records go, since they name the outputs to remove.
+    let recorded: HashSet<Utf8PathBuf> = hash_files
+        .iter()
+        .filter_map(|(_, relative)| relative.as_str().strip_suffix(".hash"))
+        .map(Utf8PathBuf::from)
+        .collect();
+
+    for (hash_path, relative) in hash_files {
+        let live = relative
+            .as_str()
+            .strip_suffix(".hash")
+            .is_some_and(|target| {
+                source_exists(paths.mounts, Utf8Path::new(target))
+                    || has_action_source(paths.mounts, Utf8Path::new(target))
+                    || is_live_page(target, &live_slugs)
+            });
+        if live {
+            continue;
+        }
+        report.reclaimed_bytes += remove_counted(hash_path.as_path())?;
+        report.hash_files += 1;
+    }
+
+    for (math_path, relative) in walk_files(paths.math_dir)? {
+        if live_math.contains(relative.as_str()) {
+            continue;
+        }
+        report.reclaimed_bytes += remove_counted(math_path.as_path())?;
+        report.math_files += 1;
+    }
+
+    for output_dir in paths.output_dirs {
+        environment::ensure_removable_output(output_dir)?;
+        for (artifact_path, relative) in walk_files(output_dir)? {
+            if paths
+                .assets_dir_name
+                .is_some_and(|name| relative.starts_with(name))
+            {
+                continue;
+            }
+            if !is_recorded_output(&relative, &recorded)
+                || !is_stale_artifact(&relative, paths.mounts, &live_slugs)
+            {
+                continue;
+            }
+            report.reclaimed_bytes += remove_counted(artifact_path.as_path())?;
+            report.artifact_files += 1;
+        }
+    }
+
+    remove_empty_dirs(paths.entry_dir)?;
+    remove_empty_dirs(paths.hash_dir)?;
+    Ok(report)
+}
+
+#[derive(serde::Deserialize)]
+struct CachedMath {
+    #[serde(default)]
+    math: Vec<String>,
+}
+
+/// Math renderings recorded in an entry cache; none if it is unreadable.
+fn read_cached_math(entry_path: &Utf8Path) -> Vec<String> {
+    environment::fs()
+        .read(entry_path)
+        .ok()
+        .and_then(|entry| serde_json::from_slice::<CachedMath>(&entry).ok())
+        .map(|cached| cached.math)
+        .unwrap_or_default()
+}
+
+fn is_live_page(relative: &str, live_slugs: &HashSet<Slug>) -> bool {
+    relative
+        .strip_suffix(".html")
+        .is_some_and(|slug| live_slugs.contains(&Slug::new(slug)))
+}
+
+/// Whether kodama wrote `relative`: pages and link action outputs are recorded
+/// under their own path, Typst HTML and SVG under their source.
+fn is_recorded_output(relative: &Utf8Path, recorded: &HashSet<Utf8PathBuf>) -> bool {
+    let exts: &[&str] = match relative.extension() {
+        Some("html") => &["typ", "typst"],
+        Some("svg") => &["typ"],
+        _ => &[],
+    };
+    recorded.contains(relative)
+        || exts
+            .iter()
+            .any(|ext| recorded.contains(&relative.with_extension(ext)))
+}
+
+/// Only page HTML and Typst HTML/SVG outputs are considered. Everything else in
+/// the output directory (runtime assets, indexes, feeds) is left untouched.
+fn is_stale_artifact(
+    relative: &Utf8Path,
+    mounts: &[TreeMount],
+    live_slugs: &HashSet<Slug>,
+) -> bool {
+    match relative.extension() {
+        Some("html") => {
+            !is_live_page(relative.as_str(), live_slugs)
+                && !has_typst_source(mounts, relative, &["typ", "typst"])
+        }
+        Some("svg") => {
+            !has_typst_source(mounts, relative, &["typ"]) && !has_action_source(mounts, relative)
+        }
+        _ => false,
+    }
+}
+
+fn source_exists(mounts: &[TreeMount], relative: &Utf8Path) -> bool {
+    environment::locate_in_mounts(mounts, relative)
+        .is_some_and(|(mount, inner)| environment::fs().is_file(&mount.dir.join(inner)))
+}
+
+fn has_typst_source(mounts: &[TreeMount], relative: &Utf8Path, exts: &[&str]) -> bool {
+    exts.iter()
+        .any(|ext| source_exists(mounts, &relative.with_extension(ext)))
+}
+
+/// Link action outputs append their extension to the input, as in `graph.dot.svg`.
+fn has_action_source(mounts: &[TreeMount], relative: &Utf8Path) -> bool {
+    let input = relative.with_extension("");
+    input.extension().is_some() && source_exists(mounts, &input)
+}
+
+fn walk_files(dir: &Utf8Path) -> eyre::Result<Vec<(Utf8PathBuf, Utf8PathBuf)>> {
+    let files = vfs::walk_files(environment::fs().as_ref(), dir)
+        .wrap_err_with(|| eyre!("failed to read directory `{}`", dir))?;
+    Ok(files
+        .into_iter()
+        .map(|path| {
+            let relative = Utf8PathBuf::from(path_utils::pretty_path(
+                path.strip_prefix(dir).unwrap_or(path.as_path()),
+            ));
+            (path, relative)
+        })
+        .collect())
+}
+
+fn remove_counted(path: &Utf8Path) -> eyre::Result<u64> {
+    let size = environment::fs().metadata(path).map(|m| m.len).unwrap_or(0);
+    let removed = remove_file_if_exists(path)?;
+    Ok(if removed { size } else { 0 })
+}
+
+fn remove_empty_dirs(dir: &Utf8Path) -> eyre::Result<()> {
+    let fs = environment::fs();
+    if fs.is_dir(dir) {
+        remove_empty_subdirs(fs.as_ref(), dir)?;
+    }
+    Ok(())
+}
+
+/// Remove the directories below `dir` that hold no files, and report whether
+/// `dir` is left empty.
+fn remove_empty_subdirs(fs: &dyn FileSystem, dir: &Utf8Path) -> eyre::Result<bool> {
+    let mut empty = true;
+    for path in fs
+        .read_dir(dir)
+        .wrap_err_with(|| eyre!("failed to read directory `{}`", dir))?
+    {
+        if fs.is_dir(&path) && remove_empty_subdirs(fs, &path)? {
+            fs.remove_dir_all(&path)
+                .wrap_err_with(|| eyre!("failed to remove directory `{}`", path))?;
+        } else {
+            empty = false;
+        }
+    }
+    Ok(empty)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, fs};
+
+    use super::*;
+    use crate::slug::Ext;
+
+    fn write(path: Utf8PathBuf, content: &str) -> Utf8PathBuf {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_collect_cache_garbage_removes_unreferenced_files() {
+        let base = crate::test_io::case_dir("cache-gc");
+        let trees = base.join("trees");
+        let entry_dir = base.join(".cache/entry");
+        let hash_dir = base.join(".cache/hash");
+        let math_dir = base.join(".cache/math");
+        let output = base.join("publish");
+
+        write(trees.join("keep.md"), "# keep");
+        write(trees.join("figs/plot.typ"), "#let x = 1");
+        write(trees.join("figs/graph.dot"), "digraph {}");
+
+        let keep_entry = write(
+            entry_dir.join("keep.md.entry"),
+            r#"{"sections":[{"slug":"keep","section":{"metadata":{},"content":{"Plain":""}}},{"slug":"keep/sub","section":{"metadata":{},"content":{"Plain":""}}}],"math":["0000000000000001.html"]}"#,
+        );
+        let old_entry = write(entry_dir.join("old/gone.md.entry"), "{}");
+        let odd_entry = write(entry_dir.join("notes.txt.entry"), "{}");
+
+        let keep_hashes = [
+            write(hash_dir.join("keep.md.hash"), "1"),
+            write(hash_dir.join("keep.html.hash"), "1"),
+            write(hash_dir.join("keep/sub.html.hash"), "1"),
+            write(hash_dir.join("figs/plot.typ.hash"), "1"),
+            write(hash_dir.join("figs/graph.dot.svg.hash"), "1"),
+        ];
+        let old_hashes = [
+            write(hash_dir.join("old/gone.md.hash"), "1"),
+            write(hash_dir.join("old/gone.html.hash"), "1"),
+            write(hash_dir.join("figs/removed.typ.hash"), "1"),
+        ];
+
+        let keep_math = write(math_dir.join("0000000000000001.html"), "<svg/>");
+        let old_math = write(math_dir.join("0000000000000002.html"), "<svg/>");
+
+        let keep_outputs = [
+            write(output.join("keep.html"), "<html/>"),
+            write(output.join("keep/sub.html"), "<html/>"),
+            write(output.join("figs/plot.svg"), "<svg/>"),
+            write(output.join("figs/graph.dot.svg"), "<svg/>"),
+            write(output.join("main.css"), "body{}"),
+            write(output.join("assets/logo.svg"), "<svg/>"),
+            write(output.join("404.html"), "<html/>"),
+            write(output.join("figs/drawn.svg"), "<svg/>"),
+        ];
+        let old_outputs = [
+            write(output.join("old/gone.html"), "<html/>"),
+            write(output.join("figs/removed.svg"), "<svg/>"),
+        ];
+
+        let mut slug_exts = HashMap::new();
+        slug_exts.insert(Slug::new("keep"), Ext::Markdown);
+        let workspace = Workspace { slug_exts };
+
+        let report = collect_cache_garbage_with_paths(
+            &workspace,
+            CacheGcPaths {
+                mounts: &[TreeMount::main(trees.clone())],
+                entry_dir: entry_dir.as_path(),
+                hash_dir: hash_dir.as_path(),
+                math_dir: math_dir.as_path(),
+                output_dirs: std::slice::from_ref(&output),
+                assets_dir_name: Some("assets"),
+            },
+        )
+        .unwrap();
+
+        assert_eq!(report.entry_files, 2);
+        assert_eq!(report.hash_files, 3);
+        assert_eq!(report.math_files, 1);
+        assert_eq!(report.artifact_files, 2);
+        assert!(report.reclaimed_bytes > 0);
+
+        assert!(keep_entry.exists());
+        assert!(!old_entry.exists());
+        assert!(!odd_entry.exists());
+        assert!(!entry_dir.join("old").exists());
+        assert!(keep_hashes.iter().all(|p| p.exists()));
+        assert!(old_hashes.iter().all(|p| !p.exists()));
+        assert!(keep_math.exists());
+        assert!(!old_math.exists());
+        assert!(keep_outputs.iter().all(|p| p.exists()));
+        assert!(old_outputs.iter().all(|p| !p.exists()));
+
+        let _ = fs::remove_dir_all(base);
+    }
+}
@@ -0,0 +1,339 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+use std::{collections::HashMap, process::Command, time::SystemTime};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use eyre::{eyre, WrapErr};
+
+use crate::{
+    config::build::Timestamps,
+    entry::{HTMLMetaData, KEY_CREATED, KEY_MODIFIED, KEY_READING_TIME, KEY_WORD_COUNT},
+    environment,
+    process::text_elaborator::{count_words, WordCount},
+    slug::{Ext, Slug},
+};
+
+use super::{
+    incremental::source_relative_path,
+    section::{HTMLContent, UnresolvedSection},
+    ParsedSections,
+};
+
+/// Reading speed for space-separated words, per minute.
+const WORDS_PER_MINUTE: usize = 200;
+
+/// Reading speed for Han and kana characters, per minute.
+const CJK_CHARS_PER_MINUTE: usize = 400;
+
+/// `created` and `modified` of one source file, as UTC timestamps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct FileTimes {
+    pub created: String,
+    pub modified: String,
+}
+
+/// Add `word-count` and `reading-time` from the section's own text. Keys the
+/// section already sets are kept.
+pub(super) fn insert_computed(section: &mut UnresolvedSection) {
+    let count = count_words(&section.content.to_text());
+    let metadata = &mut section.metadata;
+    insert_missing(metadata, KEY_WORD_COUNT, count.total().to_string());
+    insert_missing(metadata, KEY_READING_TIME, reading_time(count).to_string());
+}
+
+fn insert_missing(metadata: &mut HTMLMetaData, key: &str, value: String) {
+    if !metadata.0.contains_key(key) {
+        metadata
+            .0
+            .insert(key.to_string(), HTMLContent::Plain(value));
+    }
+}
+
+/// Whole minutes, rounded up; at least one for any text.
+fn reading_time(count: WordCount) -> usize {
+    // In units of 1/400 minute, so both speeds stay integral.
+    let units = count.words * (CJK_CHARS_PER_MINUTE / WORDS_PER_MINUTE) + count.cjk_chars;
+    units.div_ceil(CJK_CHARS_PER_MINUTE)
+}
+
+/// File times of the sources of one build, according to `[build].timestamps`.
+///
+/// They change without the source text changing, so they are added after the
+/// entry cache is read or written and never stored in it. In auto mode `git`
+/// runs twice per tree directory, on the first source looked up in it.
+pub(crate) struct SourceTimes {
+    mode: Timestamps,
+    git: HashMap<Utf8PathBuf, Option<GitTimes>>,
+}
+
+impl SourceTimes {
+    pub fn new() -> Self {
+        SourceTimes {
+            mode: environment::timestamps(),
+            git: HashMap::new(),
+        }
+    }
+
+    /// Add `created` and `modified` to every section of the source. Keys the
+    /// sections already set are kept.
+    pub fn insert(
+        &mut self,
+        source_slug: Slug,
+        ext: Ext,
+        sections: &mut ParsedSections,
+    ) -> eyre::Result<()> {
+        let Some(times) = self.file_times(&source_relative_path(source_slug, ext))? else {
+            return Ok(());
+        };
+        for (_, section) in sections {
+            insert_missing(&mut section.metadata, KEY_CREATED, times.created.clone());
+            insert_missing(&mut section.metadata, KEY_MODIFIED, times.modified.clone());
+        }
+        Ok(())
+    }
+
+    fn file_times(&mut self, source: &Utf8Path) -> eyre::Result<Option<FileTimes>> {
+        if self.mode == Timestamps::None {
+            return Ok(None);
+        }
+        let (mount, inner) = environment::locate_tree_path(source);
+        let fs_times = fs_times(&mount.dir.join(&inner))?;
+        if self.mode != Timestamps::Auto {
+            return Ok(Some(fs_times));
+        }
+        let git = self
+            .git
+            .entry(mount.dir.clone())
+            .or_insert_with(|| GitTimes::read(&mount.dir));
+        let times = match git.as_ref().and_then(|git| git.commits.get(&inner)) {
+            Some(&(first, last)) => FileTimes {
+                created: format_unix_time(first),
+                modified: match git.as_ref().is_some_and(|git| git.dirty.contains(&inner)) {
+                    true => fs_times.modified,
+                    false => format_unix_time(last),
+                },
+            },
+            None => fs_times,
+        };
+        Ok(Some(times))
+    }
+}
+
+fn fs_times(full_path: &Utf8Path) -> eyre::Result<FileTimes> {
+    let meta = environment::fs()
+        .metadata(full_path)
+        .wrap_err_with(|| eyre!("failed to read metadata of `{}`", full_path))?;
+    let modified = meta.modified;
+    let created = meta.created.unwrap_or(modified);
+    Ok(FileTimes {
+        created: format_system_time(created),
+        modified: format_system_time(modified),
+    })
+}
+
+/// Commit times of the files tracked under one directory, with paths relative
+/// to it.
+#[derive(Debug)]
+struct GitTimes {
+    /// First and last commit time of each file, following renames.
+    commits: HashMap<Utf8PathBuf, (i64, i64)>,
+    /// Files with uncommitted changes, which keep their filesystem
+    /// modification time.
+    dirty: Vec<Utf8PathBuf>,
+}
+
+impl GitTimes {
+    /// `None` outside a repository or when `git` is not installed.
+    fn read(dir: &Utf8Path) -> Option<GitTimes> {
+        let git = |args: &[&str]| {
+            let output = Command::new("git")
+                .arg("-C")
+                .arg(dir)
+                .args(["-c", "core.quotePath=false"])
+                .args(args)
+                .args(["--relative", "--", "."])
+                .output()
+                .ok()?;
+            output
+                .status
+                .success()
+                .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+        };
+
+        let log = git(&["log", "--format=%ct", "--name-status", "-M"])?;
+        let dirty = git(&["diff", "--name-only", "HEAD"]).unwrap_or_default();
+        Some(GitTimes {
+            commits: parse_log(&log),
+            dirty: dirty.lines().map(Utf8PathBuf::from).collect(),
+        })
+    }
+}
+
+/// First and last commit time of each file in the output of
+/// `git log --format=%ct --name-status -M`, newest commit first. The history
+/// of a renamed file, in the commits before the rename, counts towards its
+/// current name.
+fn parse_log(log: &str) -> HashMap<Utf8PathBuf, (i64, i64)> {
+    let mut commits: HashMap<Utf8PathBuf, (i64, i64)> = HashMap::new();
+    let mut renamed: HashMap<&str, Utf8PathBuf> = HashMap::new();
+    let mut pending = Vec::new();
+    let mut time = None;
+    for line in log.lines() {
+        let mut fields = line.split('\t');
+        let status = fields.next().unwrap_or_default();
+        let Some(path) = fields.next() else {
+            if let Ok(secs) = status.trim().parse::<i64>() {
+                renamed.extend(pending.drain(..));
+                time = Some(secs);
+            }
+            continue;
+        };
+        let Some(time) = time else {
+            continue;
+        };
+        let path = match fields.next() {
+            Some(new_path) if status.starts_with('R') => {
+                let current = renamed
+                    .get(new_path)
+                    .cloned()
+                    .unwrap_or_else(|| Utf8PathBuf::from(new_path));
+                pending.push((path, current.clone()));
+                current
+            }
+            Some(new_path) => Utf8PathBuf::from(new_path),
+            None => renamed
+                .get(path)
+                .cloned()
+                .unwrap_or_else(|| Utf8PathBuf::from(path)),
+        };
+        commits
+            .entry(path)
+            .and_modify(|(first, _)| *first = time)
+            .or_insert((time, time));
+    }
+    commits
+}
+
+pub(crate) fn format_system_time(time: SystemTime) -> String {
+    let secs = match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs() as i64,
+        Err(err) => -(err.duration().as_secs() as i64),
+    };
+    format_unix_time(secs)
+}
+
+/// `YYYY-MM-DDTHH:MM:SSZ`, which sorts as text and parses as a date.
+fn format_unix_time(secs: i64) -> String {
+    let days = secs.div_euclid(86_400);
+    let rem = secs.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// The proleptic Gregorian date of a day count since 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{
+        compiler::parse_source_sections,
+        entry::MetaData,
+        environment::BuildMode,
+        slug::{Ext, Slug},
+    };
+
+    #[test]
+    fn test_format_unix_time() {
+        assert_eq!(format_unix_time(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_unix_time(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(format_unix_time(1_735_689_599), "2024-12-31T23:59:59Z");
+        assert_eq!(format_unix_time(-1), "1969-12-31T23:59:59Z");
+    }
+
+    #[test]
+    fn test_parse_log_follows_renames() {
+        let log = "300\n\nM\tnotes/b.md\n\n200\n\nR100\tnotes/a.md\tnotes/b.md\nA\tnotes/a.md\n\n\
+                   100\n\nA\tnotes/a.md\nA\tindex.md\n";
+        let commits = parse_log(log);
+        assert_eq!(commits[Utf8Path::new("notes/b.md")], (100, 300));
+        assert_eq!(commits[Utf8Path::new("notes/a.md")], (200, 200));
+        assert_eq!(commits[Utf8Path::new("index.md")], (100, 100));
+    }
+
+    #[test]
+    fn test_reading_time_rounds_up() {
+        let count = |words, cjk_chars| WordCount { words, cjk_chars };
+        assert_eq!(reading_time(count(0, 0)), 0);
+        assert_eq!(reading_time(count(1, 0)), 1);
+        assert_eq!(reading_time(count(200, 0)), 1);
+        assert_eq!(reading_time(count(201, 0)), 2);
+        assert_eq!(reading_time(count(100, 200)), 1);
+        assert_eq!(reading_time(count(0, 1200)), 3);
+    }
+
+    #[test]
+    fn test_computed_metadata_is_added_unless_set() {
+        let root = crate::test_io::case_dir("computed-metadata");
+        let trees = root.join("trees");
+        fs::create_dir_all(trees.as_std_path()).unwrap();
+        let config_path = root.join("Kodama.toml");
+        fs::write(
+            config_path.as_std_path(),
+            "[build]\ntimestamps = \"mtime\"\n",
+        )
+        .unwrap();
+        fs::write(
+            trees.join("post.md").as_std_path(),
+            "---\ntitle: Post\ncreated: 2020-01-01\n---\n\nSome *plain* words and 中文.\n",
+        )
+        .unwrap();
+
+        environment::with_test_environment(root.clone(), BuildMode::Publish, || {
+            environment::init_environment(config_path.clone(), BuildMode::Publish).unwrap();
+            let mut sections = parse_source_sections(Slug::new("post"), Ext::Markdown).unwrap();
+            let metadata = &sections[0].1.metadata;
+            assert!(metadata.get(KEY_MODIFIED).is_none());
+
+            SourceTimes::new()
+                .insert(Slug::new("post"), Ext::Markdown, &mut sections)
+                .unwrap();
+            let metadata = &sections[0].1.metadata;
+            let get = |key| metadata.get_str(key).map(String::as_str);
+            assert_eq!(get(KEY_WORD_COUNT), Some("6"));
+            assert_eq!(get(KEY_READING_TIME), Some("1"));
+            assert_eq!(get(KEY_CREATED), Some("2020-01-01"));
+            let modified = get(KEY_MODIFIED).unwrap();
+            assert!(
+                crate::footer_sort::parse_date(modified).is_some(),
+                "{modified}"
+            );
+            assert!(modified.ends_with('Z'));
+        });
+
+        let _ = fs::remove_dir_all(root.as_std_path());
+    }
+}
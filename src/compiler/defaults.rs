@@ -0,0 +1,211 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+use std::io::ErrorKind;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use eyre::{eyre, WrapErr};
+
+use crate::{
+    entry::HTMLMetaData,
+    environment,
+    ordered_map::OrderedMap,
+    process::metadata,
+    slug::{Ext, Slug},
+};
+
+use super::{incremental::source_relative_path, record_dependency, section::HTMLContent};
+
+/// Metadata defaults for every section source in a directory and below it.
+pub const DEFAULTS_FILE_NAME: &str = "_defaults.toml";
+
+/// Suffix of the hash record that tracks the defaults of one source.
+const DEFAULTS_HASH_SUFFIX: &str = "defaults";
+
+/// Trees-relative paths of the defaults files that may apply to `source`, from
+/// the mount root down to its own directory. The files need not exist.
+pub(super) fn defaults_paths(source: &Utf8Path) -> Vec<Utf8PathBuf> {
+    let (mount, inner) = environment::locate_tree_path(source);
+    let mut dirs: Vec<&Utf8Path> = inner.ancestors().skip(1).collect();
+    dirs.reverse();
+    dirs.into_iter()
+        .map(|dir| mount.virtual_path(&dir.join(DEFAULTS_FILE_NAME)))
+        .collect()
+}
+
+/// The merged defaults of a source: nearer directories override farther ones.
+///
+/// Every candidate file is recorded as a dependency, so creating one later also
+/// triggers a rebuild during serve.
+pub(super) fn directory_defaults(
+    source_slug: Slug,
+    ext: Ext,
+) -> eyre::Result<OrderedMap<String, HTMLContent>> {
+    let mut defaults = OrderedMap::new();
+    for path in defaults_paths(&source_relative_path(source_slug, ext)) {
+        record_dependency(path.clone());
+        let Some(content) = read_defaults(&path)? else {
+            continue;
+        };
+        let fields = metadata::parse_toml_metadata(&content, source_slug)
+            .wrap_err_with(|| eyre!("failed to parse metadata defaults `{}`", path))?;
+        defaults.extend(fields);
+    }
+    Ok(defaults)
+}
+
+/// Add defaults for the keys the section does not set itself.
+pub(super) fn apply_defaults(
+    metadata: &mut HTMLMetaData,
+    defaults: &OrderedMap<String, HTMLContent>,
+) {
+    for (key, value) in defaults {
+        if !metadata.0.contains_key(key) {
+            metadata.0.insert(key.clone(), value.clone());
+        }
+    }
+}
+
+/// Whether the defaults of `source` changed since it was last parsed, for builds
+/// without a dirty set. Sources without defaults keep no hash record.
+pub(super) fn is_defaults_modified(source: &Utf8Path) -> eyre::Result<bool> {
+    let mut fingerprint = String::new();
+    for path in defaults_paths(source) {
+        if let Some(content) = read_defaults(&path)? {
+            fingerprint.push_str(path.as_str());
+            fingerprint.push('\n');
+            fingerprint.push_str(&content);
+        }
+    }
+
+    let record = Utf8PathBuf::from(format!("{}.{}", source, DEFAULTS_HASH_SUFFIX));
+    let hash_path = environment::hash_file_path(&record);
+    // Check mode only reads the caches.
+    if environment::is_check() {
+        return Ok(match fingerprint.is_empty() {
+            true => environment::fs().exists(&hash_path),
+            false => environment::is_hash_updated(&fingerprint, &hash_path).0,
+        });
+    }
+    if fingerprint.is_empty() {
+        return match environment::fs().remove_file(&hash_path) {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(err).wrap_err_with(|| eyre!("failed to remove `{}`", hash_path)),
+        };
+    }
+    environment::verify_update_hash(&record, &fingerprint)
+        .wrap_err_with(|| eyre!("failed to update defaults hash of `{}`", source))
+}
+
+fn read_defaults(path: &Utf8Path) -> eyre::Result<Option<String>> {
+    let full_path = environment::input_path(path);
+    match environment::fs().read_to_string(&full_path) {
+        Ok(content) => Ok(Some(content)),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err).wrap_err_with(|| eyre!("failed to read `{}`", full_path)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{
+        compiler::parse_source_sections,
+        entry::{MetaData, KEY_TAXON},
+        environment::BuildMode,
+    };
+
+    #[test]
+    fn test_directory_defaults_cascade_and_file_wins() {
+        let root = crate::test_io::case_dir("directory-defaults");
+        let trees = root.join("trees");
+        fs::create_dir_all(trees.join("blog/2025").as_std_path()).unwrap();
+        let config_path = root.join("Kodama.toml");
+        fs::write(config_path.as_std_path(), "").unwrap();
+        fs::write(
+            trees.join(DEFAULTS_FILE_NAME).as_std_path(),
+            "author = \"Site\"\nbacklinks = true\n",
+        )
+        .unwrap();
+        fs::write(
+            trees.join("blog").join(DEFAULTS_FILE_NAME).as_std_path(),
+            "taxon = \"post\"\nbacklinks = false\nfooter-mode = \"link\"\n",
+        )
+        .unwrap();
+        fs::write(
+            trees.join("blog/2025/hello.md").as_std_path(),
+            "---\ntitle: Hello\nfooter-mode: embed\n---\n\nBody\n\n<remark slug=\"aside\">\n\nAside\n\n</remark>\n",
+        )
+        .unwrap();
+
+        environment::with_test_environment(root.clone(), BuildMode::Publish, || {
+            environment::init_environment(config_path.clone(), BuildMode::Publish).unwrap();
+            assert_eq!(
+                defaults_paths(Utf8Path::new("blog/2025/hello.md")),
+                [
+                    "_defaults.toml",
+                    "blog/_defaults.toml",
+                    "blog/2025/_defaults.toml"
+                ]
+                .map(Utf8PathBuf::from)
+            );
+
+            let sections =
+                parse_source_sections(Slug::new("blog/2025/hello"), Ext::Markdown).unwrap();
+            let metadata = &sections[0].1.metadata;
+            assert_eq!(metadata.get_str("author").map(String::as_str), Some("Site"));
+            assert_eq!(
+                metadata.get_str("backlinks").map(String::as_str),
+                Some("false")
+            );
+            assert_eq!(
+                metadata.get_str("footer-mode").map(String::as_str),
+                Some("embed")
+            );
+            assert_eq!(
+                metadata.get(KEY_TAXON).and_then(HTMLContent::as_str),
+                Some("Post. ")
+            );
+            assert_eq!(metadata.data_taxon().map(String::as_str), Some("Post"));
+
+            let (_, aside) = &sections[1];
+            assert_eq!(
+                aside.metadata.get_str("author").map(String::as_str),
+                Some("Site")
+            );
+            assert_eq!(
+                aside.metadata.data_taxon().map(String::as_str),
+                Some("Remark")
+            );
+
+            let source = Utf8Path::new("blog/2025/hello.md");
+            assert!(is_defaults_modified(source).unwrap());
+            assert!(!is_defaults_modified(source).unwrap());
+            fs::write(
+                trees.join("blog").join(DEFAULTS_FILE_NAME).as_std_path(),
+                "taxon = \"note\"\n",
+            )
+            .unwrap();
+            assert!(is_defaults_modified(source).unwrap());
+
+            // Check mode compares without touching the record.
+            let record = environment::hash_file_path("blog/2025/hello.md.defaults");
+            let recorded = fs::read_to_string(record.as_std_path()).unwrap();
+            environment::init_environment(config_path.clone(), BuildMode::Check).unwrap();
+            fs::write(
+                trees.join("blog").join(DEFAULTS_FILE_NAME).as_std_path(),
+                "taxon = \"post\"\n",
+            )
+            .unwrap();
+            assert!(is_defaults_modified(source).unwrap());
+            assert!(is_defaults_modified(source).unwrap());
+            assert_eq!(fs::read_to_string(record.as_std_path()).unwrap(), recorded);
+        });
+
+        let _ = fs::remove_dir_all(root.as_std_path());
+    }
+}
@@ -0,0 +1,525 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+};
+
+use camino::{Utf8Path, Utf8PathBuf};
+
+use crate::{
+    environment, path_utils,
+    slug::{Ext, Slug},
+    vfs,
+};
+
+use super::{
+    incremental::source_relative_path, parse_source_sections, stale::entry_cache_path_no_create,
+    ParsedSections, Workspace,
+};
+
+thread_local! {
+    static RECORDED: RefCell<Option<BTreeSet<Utf8PathBuf>>> = const { RefCell::new(None) };
+    static RECORDED_MATH: RefCell<Option<BTreeSet<String>>> = const { RefCell::new(None) };
+}
+
+/// What parsing a source recorded, stored with its entry cache.
+#[derive(Debug, Default)]
+pub(crate) struct Recorded {
+    /// Files read, relative to the trees directory.
+    pub dependencies: Vec<Utf8PathBuf>,
+    /// File names of the renderings under `.cache/math` used by the source.
+    pub math: Vec<String>,
+}
+
+/// Record a file read while parsing the current source, as a path relative to the
+/// trees directory. Does nothing outside [`parse_with_dependencies`].
+pub(crate) fn record_dependency(path: Utf8PathBuf) {
+    RECORDED.with_borrow_mut(|recorded| {
+        if let Some(recorded) = recorded {
+            recorded.insert(path);
+        }
+    });
+}
+
+/// Record a Markdown math rendering used by the current source, so cache GC
+/// keeps it. Does nothing outside [`parse_with_dependencies`].
+pub(crate) fn record_math(name: String) {
+    RECORDED_MATH.with_borrow_mut(|recorded| {
+        if let Some(recorded) = recorded {
+            recorded.insert(name);
+        }
+    });
+}
+
+/// Record a file read while parsing the current source, given relative to the
+/// project root (e.g. the target of `#:include`).
+pub(crate) fn record_root_dependency(root_relative: &str) {
+    let path = path_utils::pretty_path(&environment::root_dir().join(root_relative));
+    let mounted = environment::tree_mounts()
+        .into_iter()
+        .skip(1)
+        .find_map(|mount| {
+            let dir = path_utils::pretty_path(&mount.dir);
+            let inner = Utf8Path::new(&path).strip_prefix(&dir).ok()?;
+            Some(mount.virtual_path(inner))
+        });
+    let trees = environment::trees_dir_without_root();
+    record_dependency(mounted.unwrap_or_else(|| root_to_trees_relative(root_relative, &trees)));
+}
+
+/// Express a project-root-relative path relative to the trees directory. Files
+/// outside the trees directory get a `../` prefix, so that every dependency uses
+/// the same keys as the dirty paths reported by the serve watcher.
+pub(crate) fn root_to_trees_relative(
+    root_relative: &str,
+    trees_dir_without_root: &str,
+) -> Utf8PathBuf {
+    let path = path_utils::pretty_path(Utf8Path::new(root_relative));
+    let trees = path_utils::pretty_path(Utf8Path::new(trees_dir_without_root));
+    if trees.is_empty() {
+        return Utf8PathBuf::from(path);
+    }
+    if let Some(inner) = path.strip_prefix(&format!("{trees}/")) {
+        return Utf8PathBuf::from(inner);
+    }
+    let depth = trees.split('/').count();
+    Utf8PathBuf::from(format!("{}{path}", "../".repeat(depth)))
+}
+
+/// Parse a source and collect its direct file dependencies: the files recorded by
+/// the Markdown processors, plus the imports of a Typst source.
+pub(super) fn parse_with_dependencies(
+    source_slug: Slug,
+    ext: Ext,
+) -> eyre::Result<(ParsedSections, Recorded)> {
+    let previous = RECORDED.replace(Some(BTreeSet::new()));
+    let previous_math = RECORDED_MATH.replace(Some(BTreeSet::new()));
+    let parsed = parse_source_sections(source_slug, ext);
+    let recorded = RECORDED.replace(previous).unwrap_or_default();
+    let math = RECORDED_MATH.replace(previous_math).unwrap_or_default();
+
+    let mut dependencies: Vec<Utf8PathBuf> = recorded.into_iter().collect();
+    dependencies.extend(source_dependencies(source_slug, ext));
+    dependencies.sort();
+    dependencies.dedup();
+    let recorded = Recorded {
+        dependencies,
+        math: math.into_iter().collect(),
+    };
+    Ok((parsed?, recorded))
+}
+
+/// Direct file dependencies of a source that can be read off its text, as paths
+/// relative to the trees directory.
+pub(super) fn source_dependencies(source_slug: Slug, ext: Ext) -> Vec<Utf8PathBuf> {
+    match ext {
+        Ext::Typst => {
+            let relative = source_relative_path(source_slug, ext);
+            typst_file_dependencies(relative.as_path())
+        }
+        Ext::Markdown => Vec::new(),
+    }
+}
+
+/// Read a Typst file and resolve the files it imports or includes.
+/// Unreadable files have no known dependencies.
+pub(super) fn typst_file_dependencies(relative: &Utf8Path) -> Vec<Utf8PathBuf> {
+    let (mount, inner) = environment::locate_tree_path(relative);
+    let Ok(source) = environment::fs().read_to_string(&mount.dir.join(inner)) else {
+        return Vec::new();
+    };
+    let mut deps: Vec<Utf8PathBuf> = typst_imports(&source)
+        .iter()
+        .filter_map(|spec| resolve_typst_import(relative, spec, &mount.prefix))
+        .collect();
+    deps.sort();
+    deps.dedup();
+    deps
+}
+
+fn is_typst_file(path: &Utf8Path) -> bool {
+    matches!(path.extension(), Some("typ") | Some("typst"))
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+/// Collect the string arguments of `import` and `include` expressions in a Typst source.
+///
+/// This is a lexical scan rather than a full parse: comments are skipped, and any
+/// `import "..."` / `include "..."` occurrence counts. Over-reporting only makes
+/// incremental rebuilds more conservative.
+pub(super) fn typst_imports(source: &str) -> Vec<String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut specs = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        if c == '/' && next == Some('/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        }
+
+        if c == '/' && next == Some('*') {
+            let mut depth = 0;
+            while i < chars.len() {
+                match (chars[i], chars.get(i + 1).copied()) {
+                    ('/', Some('*')) => {
+                        depth += 1;
+                        i += 2;
+                    }
+                    ('*', Some('/')) => {
+                        depth -= 1;
+                        i += 2;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    _ => i += 1,
+                }
+            }
+            continue;
+        }
+
+        if !is_ident_char(c) || (i > 0 && is_ident_char(chars[i - 1])) {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        while i < chars.len() && is_ident_char(chars[i]) {
+            i += 1;
+        }
+        let ident: String = chars[start..i].iter().collect();
+        if ident != "import" && ident != "include" {
+            continue;
+        }
+
+        let mut j = i;
+        while j < chars.len() && chars[j].is_whitespace() {
+            j += 1;
+        }
+        if chars.get(j) != Some(&'"') {
+            continue;
+        }
+
+        let mut spec = String::new();
+        j += 1;
+        while j < chars.len() && chars[j] != '"' {
+            if chars[j] == '\\' {
+                j += 1;
+            }
+            if let Some(&ch) = chars.get(j) {
+                spec.push(ch);
+            }
+            j += 1;
+        }
+        if j < chars.len() {
+            specs.push(spec);
+        }
+        i = j + 1;
+    }
+
+    specs
+}
+
+/// Resolve an import path written in `importer` to a path relative to the trees
+/// directory. Package imports (`@preview/...`) are not tracked.
+///
+/// Absolute paths are resolved against `root_prefix`, the mount holding the
+/// importer, which is also its Typst root.
+pub(super) fn resolve_typst_import(
+    importer: &Utf8Path,
+    spec: &str,
+    root_prefix: &str,
+) -> Option<Utf8PathBuf> {
+    let spec = spec.trim();
+    if spec.is_empty() || spec.starts_with('@') {
+        return None;
+    }
+    let path = if spec.starts_with('/') {
+        Utf8Path::new(root_prefix).join(spec.trim_start_matches('/'))
+    } else {
+        path_utils::relative_to_current(importer, spec)
+    };
+    let pretty = path_utils::pretty_path(&path);
+    (!pretty.is_empty()).then(|| Utf8PathBuf::from(pretty))
+}
+
+#[derive(serde::Deserialize)]
+struct CachedDependencies {
+    #[serde(default)]
+    dependencies: Vec<Utf8PathBuf>,
+}
+
+/// Read the dependencies recorded in the entry cache of a source.
+/// Returns `None` if the source has no readable cache entry.
+pub(super) fn cached_dependencies(
+    entry_dir: &Utf8Path,
+    source_relative: &Utf8Path,
+) -> Option<Vec<Utf8PathBuf>> {
+    let entry_path = entry_cache_path_no_create(entry_dir, source_relative);
+    let content = environment::fs().read_to_string(&entry_path).ok()?;
+    serde_json::from_str::<CachedDependencies>(&content)
+        .ok()
+        .map(|cached| cached.dependencies)
+}
+
+/// All dependencies recorded in the entry cache, across every source.
+pub fn recorded_dependencies() -> BTreeSet<Utf8PathBuf> {
+    let fs = environment::fs();
+    vfs::walk_files(fs.as_ref(), &environment::entry_dir())
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|path| fs.read_to_string(&path).ok())
+        .filter_map(|content| serde_json::from_str::<CachedDependencies>(&content).ok())
+        .flat_map(|cached| cached.dependencies)
+        .collect()
+}
+
+/// Source files that must be reparsed because they (transitively) depend on one
+/// of `changed`. Sources without known dependencies are returned as well, so that
+/// a missing cache never hides a rebuild.
+pub(super) fn dependent_sources(
+    workspace: &Workspace,
+    changed: &HashSet<Utf8PathBuf>,
+    source_deps: impl Fn(&Utf8Path) -> Option<Vec<Utf8PathBuf>>,
+    file_deps: impl Fn(&Utf8Path) -> Vec<Utf8PathBuf>,
+) -> HashSet<Utf8PathBuf> {
+    let mut dependents = HashSet::new();
+    let mut edges: HashMap<Utf8PathBuf, Vec<Utf8PathBuf>> = HashMap::new();
+    let mut pending: VecDeque<Utf8PathBuf> = VecDeque::new();
+
+    let source_paths: HashSet<Utf8PathBuf> = workspace
+        .slug_exts
+        .iter()
+        .map(|(&slug, &ext)| source_relative_path(slug, ext))
+        .collect();
+
+    for source in &source_paths {
+        match source_deps(source) {
+            Some(deps) => {
+                pending.extend(deps.iter().cloned());
+                edges.insert(source.clone(), deps);
+            }
+            None => {
+                dependents.insert(source.clone());
+            }
+        }
+    }
+
+    // Intermediate Typst files (e.g. `_lib/*.typ`) are not cached; read them on demand.
+    while let Some(path) = pending.pop_front() {
+        if edges.contains_key(&path) || source_paths.contains(&path) || !is_typst_file(&path) {
+            continue;
+        }
+        let deps = file_deps(&path);
+        pending.extend(deps.iter().cloned());
+        edges.insert(path, deps);
+    }
+
+    let mut reverse: HashMap<&Utf8Path, Vec<&Utf8Path>> = HashMap::new();
+    for (from, deps) in &edges {
+        for dep in deps {
+            reverse
+                .entry(dep.as_path())
+                .or_default()
+                .push(from.as_path());
+        }
+    }
+
+    let mut visited: HashSet<&Utf8Path> = HashSet::new();
+    let mut queue: VecDeque<&Utf8Path> = changed.iter().map(|p| p.as_path()).collect();
+    while let Some(path) = queue.pop_front() {
+        let Some(importers) = reverse.get(path) else {
+            continue;
+        };
+        for &importer in importers {
+            if visited.insert(importer) {
+                queue.push_back(importer);
+            }
+        }
+    }
+
+    dependents.extend(
+        visited
+            .into_iter()
+            .filter(|path| source_paths.contains(*path))
+            .map(Utf8Path::to_owned),
+    );
+    dependents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typst_imports_collects_import_and_include_paths() {
+        let source = r#"
+#import "_lib/kodama.typ": *
+#import "/macros.typ" as m
+#include "chapter.typ"
+#let x = { import "inner.typ"; 1 }
+#import "@preview/cetz:0.3.0"
+"#;
+        assert_eq!(
+            typst_imports(source),
+            vec![
+                "_lib/kodama.typ",
+                "/macros.typ",
+                "chapter.typ",
+                "inner.typ",
+                "@preview/cetz:0.3.0"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_typst_imports_skips_comments_and_non_keywords() {
+        let source = r#"
+// #import "commented.typ"
+/* #include "block.typ" /* nested */ #import "still-block.typ" */
+#reimport "not-a-keyword.typ"
+#import sym: arrow
+"#;
+        assert!(typst_imports(source).is_empty());
+    }
+
+    #[test]
+    fn test_resolve_typst_import_relative_absolute_and_package() {
+        let importer = Utf8Path::new("notes/a.typst");
+        assert_eq!(
+            resolve_typst_import(importer, "../_lib/kodama.typ", ""),
+            Some(Utf8PathBuf::from("_lib/kodama.typ"))
+        );
+        assert_eq!(
+            resolve_typst_import(importer, "local.typ", ""),
+            Some(Utf8PathBuf::from("notes/local.typ"))
+        );
+        assert_eq!(
+            resolve_typst_import(importer, "/macros.typ", ""),
+            Some(Utf8PathBuf::from("macros.typ"))
+        );
+        assert_eq!(
+            resolve_typst_import(Utf8Path::new("found/a.typst"), "/macros.typ", "found"),
+            Some(Utf8PathBuf::from("found/macros.typ"))
+        );
+        assert_eq!(
+            resolve_typst_import(importer, "@preview/cetz:0.3.0", ""),
+            None
+        );
+    }
+
+    #[test]
+    fn test_root_to_trees_relative_maps_inside_and_outside_trees() {
+        assert_eq!(
+            root_to_trees_relative("trees/notes/a.md", "trees"),
+            Utf8PathBuf::from("notes/a.md")
+        );
+        assert_eq!(
+            root_to_trees_relative("/import-meta.html", "./trees"),
+            Utf8PathBuf::from("../import-meta.html")
+        );
+        assert_eq!(
+            root_to_trees_relative("src/lib.rs", "content/trees"),
+            Utf8PathBuf::from("../../src/lib.rs")
+        );
+        assert_eq!(
+            root_to_trees_relative("src/lib.rs", "."),
+            Utf8PathBuf::from("src/lib.rs")
+        );
+    }
+
+    #[test]
+    fn test_parse_with_dependencies_records_markdown_includes() {
+        let root = crate::test_io::case_dir("dependency-record-include");
+        let trees = root.join("trees");
+        std::fs::create_dir_all(trees.join("_includes")).unwrap();
+        std::fs::write(root.join("Kodama.toml"), "").unwrap();
+        std::fs::write(trees.join("_includes/note.md"), "included").unwrap();
+        std::fs::write(root.join("snippet.txt"), "snippet").unwrap();
+        std::fs::write(
+            trees.join("a.md"),
+            "# A\n\n[](/trees/_includes/note.md#:include)\n\n[](/snippet.txt#:include)\n",
+        )
+        .unwrap();
+
+        environment::with_test_environment(root.clone(), environment::BuildMode::Publish, || {
+            environment::init_environment(
+                root.join("Kodama.toml"),
+                environment::BuildMode::Publish,
+            )
+            .unwrap();
+            let (_, recorded) = parse_with_dependencies(Slug::new("a"), Ext::Markdown).unwrap();
+            assert_eq!(
+                recorded.dependencies,
+                vec![
+                    Utf8PathBuf::from("../snippet.txt"),
+                    // Candidate defaults files are recorded even when missing.
+                    Utf8PathBuf::from("_defaults.toml"),
+                    Utf8PathBuf::from("_includes/note.md")
+                ]
+            );
+            // Recording stops once the source has been parsed.
+            record_dependency(Utf8PathBuf::from("stray.md"));
+            let (_, recorded) = parse_with_dependencies(Slug::new("a"), Ext::Markdown).unwrap();
+            assert!(!recorded
+                .dependencies
+                .contains(&Utf8PathBuf::from("stray.md")));
+        });
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn test_dependent_sources_follows_transitive_imports() {
+        let mut slug_exts = HashMap::new();
+        slug_exts.insert(Slug::new("a"), Ext::Typst);
+        slug_exts.insert(Slug::new("b"), Ext::Typst);
+        slug_exts.insert(Slug::new("c"), Ext::Typst);
+        slug_exts.insert(Slug::new("d"), Ext::Markdown);
+        let workspace = Workspace { slug_exts };
+
+        let source_deps = |source: &Utf8Path| match source.as_str() {
+            "a.typst" => Some(vec![Utf8PathBuf::from("_lib/kodama.typ")]),
+            "b.typst" => Some(vec![Utf8PathBuf::from("a.typst")]),
+            "c.typst" => Some(vec![]),
+            "d.md" => Some(vec![]),
+            _ => None,
+        };
+        let file_deps = |file: &Utf8Path| match file.as_str() {
+            "_lib/kodama.typ" => vec![Utf8PathBuf::from("_lib/util.typ")],
+            _ => vec![],
+        };
+
+        let changed = HashSet::from([Utf8PathBuf::from("_lib/util.typ")]);
+        let dirty = dependent_sources(&workspace, &changed, source_deps, file_deps);
+        assert_eq!(
+            dirty,
+            HashSet::from([Utf8PathBuf::from("a.typst"), Utf8PathBuf::from("b.typst")])
+        );
+    }
+
+    #[test]
+    fn test_dependent_sources_treats_unknown_dependencies_as_dirty() {
+        let mut slug_exts = HashMap::new();
+        slug_exts.insert(Slug::new("a"), Ext::Typst);
+        slug_exts.insert(Slug::new("b"), Ext::Typst);
+        let workspace = Workspace { slug_exts };
+
+        let source_deps = |source: &Utf8Path| (source.as_str() == "a.typst").then(Vec::new);
+        let changed = HashSet::from([Utf8PathBuf::from("shared.typ")]);
+        let dirty = dependent_sources(&workspace, &changed, source_deps, |_| Vec::new());
+        assert_eq!(dirty, HashSet::from([Utf8PathBuf::from("b.typst")]));
+    }
+}
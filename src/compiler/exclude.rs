@@ -0,0 +1,222 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+use std::io::ErrorKind;
+
+use camino::Utf8Path;
+use eyre::{eyre, WrapErr};
+use regex_lite::Regex;
+
+use crate::environment;
+
+/// Gitignore-style file in the trees directory listing additional exclude patterns.
+pub const IGNORE_FILE_NAME: &str = ".kodamaignore";
+
+#[derive(Debug)]
+struct ExcludeRule {
+    regex: Regex,
+    negated: bool,
+    dir_only: bool,
+}
+
+/// Exclude patterns from `[kodama].exclude` and `.kodamaignore`, matched against
+/// paths relative to the trees directory.
+///
+/// Patterns follow gitignore rules: `*` and `?` do not cross `/`, `**` does, a
+/// trailing `/` matches directories only, a pattern containing `/` is anchored to
+/// the trees directory, and `!` re-includes a path excluded by an earlier pattern.
+#[derive(Debug, Default)]
+pub struct ExcludeRules {
+    rules: Vec<ExcludeRule>,
+}
+
+impl ExcludeRules {
+    pub fn new<I, S>(patterns: I) -> eyre::Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut rules = Vec::new();
+        for pattern in patterns {
+            let pattern = pattern.as_ref();
+            if let Some(rule) = parse_rule(pattern)
+                .wrap_err_with(|| eyre!("invalid exclude pattern `{}`", pattern))?
+            {
+                rules.push(rule);
+            }
+        }
+        Ok(Self { rules })
+    }
+
+    /// Load the configured exclude patterns followed by `<trees>/.kodamaignore`.
+    pub fn load(trees_dir: &Utf8Path) -> eyre::Result<Self> {
+        Self::load_with(environment::exclude_patterns(), trees_dir)
+    }
+
+    /// Like [`ExcludeRules::load`], with `patterns` in place of the configured ones.
+    pub fn load_with(mut patterns: Vec<String>, trees_dir: &Utf8Path) -> eyre::Result<Self> {
+        let ignore_file = trees_dir.join(IGNORE_FILE_NAME);
+        match environment::fs().read_to_string(&ignore_file) {
+            Ok(content) => patterns.extend(content.lines().map(str::to_string)),
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => {
+                return Err(err).wrap_err_with(|| eyre!("failed to read `{}`", ignore_file));
+            }
+        }
+        Self::new(patterns).wrap_err_with(|| eyre!("failed to load exclude patterns"))
+    }
+
+    /// Whether `relative` or one of its parent directories is excluded.
+    pub fn is_excluded(&self, relative: &Utf8Path, is_dir: bool) -> bool {
+        if self.rules.is_empty() {
+            return false;
+        }
+        let mut parents: Vec<&Utf8Path> = relative
+            .ancestors()
+            .skip(1)
+            .filter(|dir| !dir.as_str().is_empty())
+            .collect();
+        parents.reverse();
+        parents.into_iter().any(|dir| self.matches(dir, true)) || self.matches(relative, is_dir)
+    }
+
+    fn matches(&self, path: &Utf8Path, is_dir: bool) -> bool {
+        let path = path.as_str().trim_start_matches("./");
+        let mut excluded = false;
+        for rule in &self.rules {
+            if rule.dir_only && !is_dir {
+                continue;
+            }
+            if rule.regex.is_match(path) {
+                excluded = !rule.negated;
+            }
+        }
+        excluded
+    }
+}
+
+fn parse_rule(line: &str) -> eyre::Result<Option<ExcludeRule>> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    let (negated, pattern) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line.strip_prefix('\\').unwrap_or(line)),
+    };
+    let (dir_only, pattern) = match pattern.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, pattern),
+    };
+    let anchored = pattern.contains('/');
+    let pattern = pattern.trim_start_matches('/');
+    if pattern.is_empty() {
+        return Ok(None);
+    }
+
+    let prefix = if anchored { "^" } else { "^(?:.*/)?" };
+    let regex = Regex::new(&format!("{prefix}{}$", glob_to_regex(pattern)))?;
+    Ok(Some(ExcludeRule {
+        regex,
+        negated,
+        dir_only,
+    }))
+}
+
+fn glob_to_regex(pattern: &str) -> String {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut regex = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                i += 2;
+                if chars.get(i) == Some(&'/') {
+                    regex.push_str("(?:.*/)?");
+                    i += 1;
+                } else {
+                    regex.push_str(".*");
+                }
+                continue;
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '[' => match chars[i + 1..].iter().position(|&c| c == ']') {
+                Some(len) if len > 0 => {
+                    let class: String = chars[i + 1..i + 1 + len].iter().collect();
+                    let class = match class.strip_prefix('!') {
+                        Some(rest) => format!("^{rest}"),
+                        None => class,
+                    };
+                    regex.push('[');
+                    regex.push_str(&class.replace('\\', "\\\\"));
+                    regex.push(']');
+                    i += len + 2;
+                    continue;
+                }
+                _ => regex.push_str("\\["),
+            },
+            '\\' if i + 1 < chars.len() => {
+                i += 1;
+                regex.push_str(&regex_lite::escape(&chars[i].to_string()));
+            }
+            c => regex.push_str(&regex_lite::escape(&c.to_string())),
+        }
+        i += 1;
+    }
+
+    regex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(patterns: &[&str]) -> ExcludeRules {
+        ExcludeRules::new(patterns).unwrap()
+    }
+
+    #[test]
+    fn test_exclude_rules_match_basenames_at_any_depth() {
+        let rules = rules(&["*.draft.md", "scratch"]);
+        assert!(rules.is_excluded(Utf8Path::new("a.draft.md"), false));
+        assert!(rules.is_excluded(Utf8Path::new("notes/b.draft.md"), false));
+        assert!(rules.is_excluded(Utf8Path::new("scratch/idea.md"), false));
+        assert!(rules.is_excluded(Utf8Path::new("deep/scratch/idea.md"), false));
+        assert!(!rules.is_excluded(Utf8Path::new("notes/b.md"), false));
+    }
+
+    #[test]
+    fn test_exclude_rules_anchor_patterns_with_slashes() {
+        let rules = rules(&["/archive", "vendor/**/*.typ", "notes/*.md"]);
+        assert!(rules.is_excluded(Utf8Path::new("archive/old.md"), false));
+        assert!(!rules.is_excluded(Utf8Path::new("notes/archive/old.md"), false));
+        assert!(rules.is_excluded(Utf8Path::new("vendor/pkg/lib/util.typ"), false));
+        assert!(rules.is_excluded(Utf8Path::new("vendor/util.typ"), false));
+        assert!(rules.is_excluded(Utf8Path::new("notes/a.md"), false));
+        assert!(!rules.is_excluded(Utf8Path::new("notes/sub/a.md"), false));
+    }
+
+    #[test]
+    fn test_exclude_rules_support_negation_dir_only_and_comments() {
+        let rules = rules(&["# comment", "", "*.md", "!keep.md", "build/"]);
+        assert_eq!(rules.rules.len(), 3);
+        assert!(rules.is_excluded(Utf8Path::new("a.md"), false));
+        assert!(!rules.is_excluded(Utf8Path::new("keep.md"), false));
+        assert!(rules.is_excluded(Utf8Path::new("build"), true));
+        assert!(!rules.is_excluded(Utf8Path::new("build"), false));
+        assert!(rules.is_excluded(Utf8Path::new("build/keep.typ"), false));
+    }
+
+    #[test]
+    fn test_exclude_rules_character_classes() {
+        let rules = rules(&["draft-[0-9].md", "tmp[!a].typ"]);
+        assert!(rules.is_excluded(Utf8Path::new("draft-3.md"), false));
+        assert!(!rules.is_excluded(Utf8Path::new("draft-x.md"), false));
+        assert!(rules.is_excluded(Utf8Path::new("tmpb.typ"), false));
+        assert!(!rules.is_excluded(Utf8Path::new("tmpa.typ"), false));
+    }
+}
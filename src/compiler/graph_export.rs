@@ -0,0 +1,347 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{entry::MetaData, slug::Slug};
+
+use super::{is_internal_anonymous_subtree, state::CompileState, UnresolvedSections};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum GraphFormat {
+    /// Graphviz.
+    Dot,
+    /// GraphML, for Gephi and yEd.
+    Graphml,
+    /// A Mermaid flowchart.
+    Mermaid,
+}
+
+/// Which part of the forest to export.
+#[derive(Debug, Clone, Default)]
+pub struct GraphFilter {
+    /// Only this section and the sections below it in the parent tree.
+    pub root: Option<Slug>,
+    /// Only sections at most this many levels below the root, or below `index`.
+    pub depth: Option<usize>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum EdgeKind {
+    /// From the parent in the page header to the section.
+    Parent,
+    /// From a section to the sections in its references footer.
+    Reference,
+    /// From a linking section to the section listing it as a backlink.
+    Backlink,
+}
+
+impl EdgeKind {
+    fn name(self) -> &'static str {
+        match self {
+            EdgeKind::Parent => "parent",
+            EdgeKind::Reference => "reference",
+            EdgeKind::Backlink => "backlink",
+        }
+    }
+}
+
+struct Node {
+    title: String,
+    taxon: String,
+}
+
+struct Graph {
+    nodes: BTreeMap<Slug, Node>,
+    edges: BTreeSet<(Slug, Slug, EdgeKind)>,
+}
+
+/// Render the section graph with typed edges, keeping only edges between
+/// exported sections.
+pub fn export_graph(
+    shallows: &UnresolvedSections,
+    state: &CompileState,
+    format: GraphFormat,
+    filter: &GraphFilter,
+) -> String {
+    let graph = build_graph(shallows, state, filter);
+    match format {
+        GraphFormat::Dot => to_dot(&graph),
+        GraphFormat::Graphml => to_graphml(&graph),
+        GraphFormat::Mermaid => to_mermaid(&graph),
+    }
+}
+
+fn build_graph(shallows: &UnresolvedSections, state: &CompileState, filter: &GraphFilter) -> Graph {
+    let visible = |slug: &Slug| {
+        state.compiled().contains_key(slug)
+            && shallows
+                .get(slug)
+                .is_some_and(|section| !is_internal_anonymous_subtree(section))
+    };
+
+    let mut slugs: BTreeSet<Slug> = state.compiled().keys().copied().filter(visible).collect();
+    if filter.root.is_some() || filter.depth.is_some() {
+        let root = filter.root.unwrap_or(Slug::new("index"));
+        let depth = filter.depth.unwrap_or(usize::MAX);
+        let below = state.descendants(root);
+        slugs.retain(|slug| *slug == root || below.get(slug).is_some_and(|d| *d <= depth));
+    }
+
+    let nodes = slugs
+        .iter()
+        .map(|&slug| {
+            let metadata = &shallows[&slug].metadata;
+            let node = Node {
+                title: metadata
+                    .page_title()
+                    .filter(|title| !title.trim().is_empty())
+                    .map_or_else(|| slug.to_string(), |title| title.trim().to_string()),
+                taxon: metadata.data_taxon().cloned().unwrap_or_default(),
+            };
+            (slug, node)
+        })
+        .collect();
+
+    let mut edges = BTreeSet::new();
+    for &slug in &slugs {
+        if let Some(parent) = state.parent_of(slug) {
+            edges.insert((parent, slug, EdgeKind::Parent));
+        }
+        for &target in &state.compiled()[&slug].references {
+            edges.insert((slug, target, EdgeKind::Reference));
+        }
+        if let Some(callback) = state.callback().0.get(&slug) {
+            for &source in &callback.backlinks {
+                edges.insert((source, slug, EdgeKind::Backlink));
+            }
+        }
+    }
+    edges.retain(|(from, to, _)| slugs.contains(from) && slugs.contains(to));
+
+    Graph { nodes, edges }
+}
+
+fn to_dot(graph: &Graph) -> String {
+    let quote = |text: &str| {
+        let escaped = text
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n");
+        format!("\"{}\"", escaped)
+    };
+
+    let mut output = String::from("digraph forest {\n  node [shape=box];\n");
+    for (slug, node) in &graph.nodes {
+        output.push_str(&format!(
+            "  {} [label={}, taxon={}];\n",
+            quote(slug.as_str()),
+            quote(&node.title),
+            quote(&node.taxon)
+        ));
+    }
+    for (from, to, kind) in &graph.edges {
+        let style = match kind {
+            EdgeKind::Parent => "solid",
+            EdgeKind::Reference => "dashed",
+            EdgeKind::Backlink => "dotted",
+        };
+        output.push_str(&format!(
+            "  {} -> {} [kind={}, style={}];\n",
+            quote(from.as_str()),
+            quote(to.as_str()),
+            kind.name(),
+            style
+        ));
+    }
+    output.push_str("}\n");
+    output
+}
+
+fn to_graphml(graph: &Graph) -> String {
+    let escape = |text: &str| htmlize::escape_attribute(text).into_owned();
+
+    let mut output = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+        "  <key id=\"title\" for=\"node\" attr.name=\"title\" attr.type=\"string\"/>\n",
+        "  <key id=\"taxon\" for=\"node\" attr.name=\"taxon\" attr.type=\"string\"/>\n",
+        "  <key id=\"kind\" for=\"edge\" attr.name=\"kind\" attr.type=\"string\"/>\n",
+        "  <graph id=\"forest\" edgedefault=\"directed\">\n",
+    ));
+    for (slug, node) in &graph.nodes {
+        output.push_str(&format!(
+            "    <node id=\"{}\"><data key=\"title\">{}</data><data key=\"taxon\">{}</data></node>\n",
+            escape(slug.as_str()),
+            escape(&node.title),
+            escape(&node.taxon)
+        ));
+    }
+    for (index, (from, to, kind)) in graph.edges.iter().enumerate() {
+        output.push_str(&format!(
+            "    <edge id=\"e{}\" source=\"{}\" target=\"{}\"><data key=\"kind\">{}</data></edge>\n",
+            index,
+            escape(from.as_str()),
+            escape(to.as_str()),
+            kind.name()
+        ));
+    }
+    output.push_str("  </graph>\n</graphml>\n");
+    output
+}
+
+/// Slugs contain `/` and `-`, which Mermaid IDs cannot, so nodes are numbered.
+fn to_mermaid(graph: &Graph) -> String {
+    let label = |node: &Node| {
+        let text = match node.taxon.is_empty() {
+            true => node.title.clone(),
+            false => format!("{}: {}", node.taxon, node.title),
+        };
+        text.replace('"', "#quot;")
+    };
+    let ids: BTreeMap<Slug, usize> = graph
+        .nodes
+        .keys()
+        .enumerate()
+        .map(|(index, slug)| (*slug, index))
+        .collect();
+
+    let mut output = String::from("flowchart TD\n");
+    for (slug, node) in &graph.nodes {
+        output.push_str(&format!("  n{}[\"{}\"]\n", ids[slug], label(node)));
+    }
+    for (from, to, kind) in &graph.edges {
+        let arrow = match kind {
+            EdgeKind::Parent => "-->",
+            EdgeKind::Reference | EdgeKind::Backlink => "-.->",
+        };
+        output.push_str(&format!(
+            "  n{} {}|{}| n{}\n",
+            ids[from],
+            arrow,
+            kind.name(),
+            ids[to]
+        ));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{
+        compiler::{
+            section::{EmbedContent, HTMLContent, LazyContent, LocalLink, UnresolvedSection},
+            state::compile_all_without_missing_index_warning,
+        },
+        entry::{HTMLMetaData, KEY_ASREF, KEY_EXT, KEY_PAGE_TITLE, KEY_SLUG, KEY_TAXON},
+        ordered_map::OrderedMap,
+    };
+
+    fn shallow(slug: &str, title: &str, content: Vec<LazyContent>) -> UnresolvedSection {
+        let mut metadata = OrderedMap::new();
+        metadata.insert(KEY_SLUG.to_string(), HTMLContent::Plain(slug.to_string()));
+        metadata.insert(KEY_EXT.to_string(), HTMLContent::Plain("md".to_string()));
+        metadata.insert(
+            KEY_PAGE_TITLE.to_string(),
+            HTMLContent::Plain(title.to_string()),
+        );
+        let mut metadata = HTMLMetaData(metadata);
+        if slug == "lemma" {
+            metadata.0.insert(
+                KEY_TAXON.to_string(),
+                HTMLContent::Plain("Lemma".to_string()),
+            );
+            metadata.0.insert(
+                KEY_ASREF.to_string(),
+                HTMLContent::Plain("true".to_string()),
+            );
+            metadata.compute_textual_attrs();
+        }
+        UnresolvedSection {
+            metadata,
+            content: HTMLContent::Lazy(content),
+        }
+    }
+
+    fn embed(slug: &str) -> LazyContent {
+        LazyContent::Embed(EmbedContent {
+            url: format!("/{}.md", slug),
+            title: None,
+            option: Default::default(),
+        })
+    }
+
+    fn link(slug: &str) -> LazyContent {
+        LazyContent::Local(LocalLink {
+            url: format!("/{}.md", slug),
+            text: None,
+        })
+    }
+
+    fn state() -> (UnresolvedSections, CompileState) {
+        let mut shallows = HashMap::new();
+        for (slug, title, content) in [
+            ("index", "Home", vec![embed("chapter")]),
+            ("chapter", "Chapter \"1\"", vec![embed("lemma")]),
+            ("lemma", "Zorn", vec![]),
+            ("note", "Note", vec![link("lemma")]),
+        ] {
+            shallows.insert(Slug::new(slug), shallow(slug, title, content));
+        }
+        let state = compile_all_without_missing_index_warning(&shallows).unwrap();
+        (shallows, state)
+    }
+
+    #[test]
+    fn test_export_graph_dot_has_typed_edges() {
+        let (shallows, state) = state();
+        let dot = export_graph(&shallows, &state, GraphFormat::Dot, &GraphFilter::default());
+        assert_eq!(
+            dot,
+            r#"digraph forest {
+  node [shape=box];
+  "chapter" [label="Chapter \"1\"", taxon=""];
+  "index" [label="Home", taxon=""];
+  "lemma" [label="Zorn", taxon="Lemma"];
+  "note" [label="Note", taxon=""];
+  "chapter" -> "lemma" [kind=parent, style=solid];
+  "index" -> "chapter" [kind=parent, style=solid];
+  "index" -> "note" [kind=parent, style=solid];
+  "note" -> "lemma" [kind=reference, style=dashed];
+  "note" -> "lemma" [kind=backlink, style=dotted];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_export_graph_filters_by_root_and_depth() {
+        let (shallows, state) = state();
+        let filter = GraphFilter {
+            root: None,
+            depth: Some(1),
+        };
+        let mermaid = export_graph(&shallows, &state, GraphFormat::Mermaid, &filter);
+        assert_eq!(
+            mermaid,
+            "flowchart TD\n  n0[\"Chapter #quot;1#quot;\"]\n  n1[\"Home\"]\n  n2[\"Note\"]\n  n1 -->|parent| n0\n  n1 -->|parent| n2\n"
+        );
+
+        let filter = GraphFilter {
+            root: Some(Slug::new("chapter")),
+            depth: None,
+        };
+        let graphml = export_graph(&shallows, &state, GraphFormat::Graphml, &filter);
+        assert!(graphml.contains(
+            "<node id=\"chapter\"><data key=\"title\">Chapter &quot;1&quot;</data><data key=\"taxon\"></data></node>"
+        ));
+        assert!(graphml.contains(
+            "<edge id=\"e0\" source=\"chapter\" target=\"lemma\"><data key=\"kind\">parent</data></edge>"
+        ));
+        assert!(!graphml.contains("\"note\""));
+    }
+}
@@ -7,11 +7,14 @@ use std::collections::{HashSet, VecDeque};
 use camino::{Utf8Path, Utf8PathBuf};
 
 use crate::{
-    environment::verify_and_file_hash,
+    environment::{self, verify_and_file_hash},
     slug::{Ext, Slug},
 };
 
-use super::{state, DirtySet, Workspace};
+use super::{
+    dependency::{cached_dependencies, dependent_sources, typst_file_dependencies},
+    state, DirtySet, Workspace,
+};
 
 pub(super) fn source_relative_path(slug: Slug, ext: Ext) -> Utf8PathBuf {
     Utf8PathBuf::from(format!("{}.{}", slug, ext))
@@ -21,7 +24,7 @@ pub(super) fn is_source_modified(
     relative_path: &Utf8Path,
     dirty_paths: Option<&DirtySet>,
 ) -> eyre::Result<bool> {
-    if *crate::cli::build::no_cache_enabled() {
+    if crate::cli::build::no_cache_enabled() {
         return Ok(true);
     }
 
@@ -38,42 +41,32 @@ pub(super) fn is_source_modified(
 }
 
 pub fn expand_dirty_paths(workspace: &Workspace, dirty_paths: &DirtySet) -> DirtySet {
-    let mut expanded = dirty_paths.clone();
-    let source_paths: HashSet<Utf8PathBuf> = workspace
-        .slug_exts
-        .iter()
-        .map(|(&slug, &ext)| source_relative_path(slug, ext))
-        .collect();
-
-    let mut dirty_all_sources = false;
-    let mut dirty_all_typst_sources = false;
-    for path in dirty_paths {
-        let is_known_source = source_paths.contains(path);
-        match path.extension() {
-            Some("md") | Some("typst") if is_known_source => {}
-            Some("typst") | Some("typ") => dirty_all_typst_sources = true,
-            _ => {
-                // Unknown tree-side dependency (e.g. include file): conservatively reparse all.
-                dirty_all_sources = true;
-            }
-        }
-    }
+    let entry_dir = environment::entry_dir();
+    expand_dirty_paths_with(
+        workspace,
+        dirty_paths,
+        |source| cached_dependencies(entry_dir.as_path(), source),
+        typst_file_dependencies,
+    )
+}
 
-    if dirty_all_sources {
-        workspace.slug_exts.iter().for_each(|(&slug, &ext)| {
-            expanded.insert(source_relative_path(slug, ext));
-        });
-        return expanded;
-    }
+fn expand_dirty_paths_with(
+    workspace: &Workspace,
+    dirty_paths: &DirtySet,
+    source_deps: impl Fn(&Utf8Path) -> Option<Vec<Utf8PathBuf>>,
+    file_deps: impl Fn(&Utf8Path) -> Vec<Utf8PathBuf>,
+) -> DirtySet {
+    let mut expanded = dirty_paths.clone();
 
-    if dirty_all_typst_sources {
-        workspace
-            .slug_exts
-            .iter()
-            .filter(|(_, &ext)| matches!(ext, Ext::Typst))
-            .for_each(|(&slug, &ext)| {
-                expanded.insert(source_relative_path(slug, ext));
-            });
+    // Any changed file may be a recorded dependency, Markdown sources included:
+    // `#:include` and `#:transclude-raw` read them too.
+    if !dirty_paths.is_empty() {
+        expanded.extend(dependent_sources(
+            workspace,
+            dirty_paths,
+            source_deps,
+            file_deps,
+        ));
     }
 
     expanded
@@ -172,22 +165,80 @@ mod tests {
         }
     }
 
+    fn expand_without_dependencies(workspace: &Workspace, dirty: &DirtySet) -> DirtySet {
+        expand_dirty_paths_with(workspace, dirty, |_| Some(Vec::new()), |_| Vec::new())
+    }
+
     #[test]
-    fn test_expand_dirty_paths_typst_dependency_marks_all_typst_sources() {
+    fn test_expand_dirty_paths_typst_dependency_marks_only_importing_sources() {
+        use std::fs;
+
+        let root = crate::test_io::case_dir("incremental-typst-imports");
+        let trees = root.join("trees");
+        fs::create_dir_all(trees.join("_lib")).unwrap();
+        fs::write(trees.join("_lib/kodama.typ"), "#import \"util.typ\": *\n").unwrap();
+        fs::write(trees.join("_lib/util.typ"), "#let x = 1\n").unwrap();
+        fs::write(trees.join("b.typst"), "#import \"_lib/kodama.typ\": *\n").unwrap();
+        fs::write(trees.join("c.typst"), "= C\n").unwrap();
+        fs::write(trees.join("a.md"), "# A\n").unwrap();
+
         let mut slug_exts = HashMap::new();
         slug_exts.insert(Slug::new("a"), Ext::Markdown);
         slug_exts.insert(Slug::new("b"), Ext::Typst);
         slug_exts.insert(Slug::new("c"), Ext::Typst);
+        slug_exts.insert(Slug::new("d"), Ext::Typst);
+        let workspace = Workspace { slug_exts };
+
+        environment::with_test_environment(root.clone(), environment::BuildMode::Serve, || {
+            for (slug, ext) in [("a", Ext::Markdown), ("b", Ext::Typst), ("c", Ext::Typst)] {
+                let slug = Slug::new(slug);
+                let relative = source_relative_path(slug, ext);
+                let entry_path = environment::entry_file_path(&relative);
+                let recorded = crate::compiler::dependency::Recorded {
+                    dependencies: crate::compiler::dependency::source_dependencies(slug, ext),
+                    ..Default::default()
+                };
+                crate::compiler::write_entry_cache(entry_path.as_path(), &[], recorded).unwrap();
+            }
+
+            let mut dirty = DirtySet::new();
+            dirty.insert(Utf8PathBuf::from("_lib/util.typ"));
+
+            let expanded = expand_dirty_paths(&workspace, &dirty);
+            assert!(expanded.contains(&Utf8PathBuf::from("_lib/util.typ")));
+            assert!(expanded.contains(&Utf8PathBuf::from("b.typst")));
+            assert!(!expanded.contains(&Utf8PathBuf::from("c.typst")));
+            assert!(!expanded.contains(&Utf8PathBuf::from("a.md")));
+            // `d` has no entry cache yet, so its dependencies are unknown.
+            assert!(expanded.contains(&Utf8PathBuf::from("d.typst")));
+        });
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn test_expand_dirty_paths_typst_source_change_dirties_importers() {
+        let mut slug_exts = HashMap::new();
+        slug_exts.insert(Slug::new("b"), Ext::Typst);
+        slug_exts.insert(Slug::new("c"), Ext::Typst);
         let workspace = Workspace { slug_exts };
 
         let mut dirty = DirtySet::new();
-        dirty.insert(Utf8PathBuf::from("shared.typ"));
+        dirty.insert(Utf8PathBuf::from("b.typst"));
 
-        let expanded = expand_dirty_paths(&workspace, &dirty);
-        assert!(expanded.contains(&Utf8PathBuf::from("shared.typ")));
+        let expanded = expand_dirty_paths_with(
+            &workspace,
+            &dirty,
+            |source| {
+                Some(match source.as_str() {
+                    "c.typst" => vec![Utf8PathBuf::from("b.typst")],
+                    _ => Vec::new(),
+                })
+            },
+            |_| Vec::new(),
+        );
         assert!(expanded.contains(&Utf8PathBuf::from("b.typst")));
         assert!(expanded.contains(&Utf8PathBuf::from("c.typst")));
-        assert!(!expanded.contains(&Utf8PathBuf::from("a.md")));
     }
 
     #[test]
@@ -201,29 +252,82 @@ mod tests {
         let mut dirty = DirtySet::new();
         dirty.insert(Utf8PathBuf::from("b.typst"));
 
-        let expanded = expand_dirty_paths(&workspace, &dirty);
+        let expanded = expand_without_dependencies(&workspace, &dirty);
         assert!(expanded.contains(&Utf8PathBuf::from("b.typst")));
         assert!(!expanded.contains(&Utf8PathBuf::from("c.typst")));
         assert!(!expanded.contains(&Utf8PathBuf::from("a.md")));
     }
 
     #[test]
-    fn test_expand_dirty_paths_unknown_markdown_dependency_marks_all_sources() {
+    fn test_expand_dirty_paths_included_file_marks_only_recorded_dependents() {
         let mut slug_exts = HashMap::new();
         slug_exts.insert(Slug::new("a"), Ext::Markdown);
-        slug_exts.insert(Slug::new("b"), Ext::Typst);
+        slug_exts.insert(Slug::new("b"), Ext::Markdown);
+        slug_exts.insert(Slug::new("c"), Ext::Typst);
         let workspace = Workspace { slug_exts };
 
+        let source_deps = |source: &Utf8Path| match source.as_str() {
+            "a.md" => Some(vec![Utf8PathBuf::from("_includes/shared.md")]),
+            "b.md" => Some(vec![Utf8PathBuf::from("../src/lib.rs")]),
+            _ => Some(Vec::new()),
+        };
+
         let mut dirty = DirtySet::new();
         dirty.insert(Utf8PathBuf::from("_includes/shared.md"));
-
-        let expanded = expand_dirty_paths(&workspace, &dirty);
+        let expanded = expand_dirty_paths_with(&workspace, &dirty, source_deps, |_| Vec::new());
         assert!(expanded.contains(&Utf8PathBuf::from("a.md")));
-        assert!(expanded.contains(&Utf8PathBuf::from("b.typst")));
+        assert!(!expanded.contains(&Utf8PathBuf::from("b.md")));
+        assert!(!expanded.contains(&Utf8PathBuf::from("c.typst")));
+
+        let mut dirty = DirtySet::new();
+        dirty.insert(Utf8PathBuf::from("../src/lib.rs"));
+        let expanded = expand_dirty_paths_with(&workspace, &dirty, source_deps, |_| Vec::new());
+        assert!(!expanded.contains(&Utf8PathBuf::from("a.md")));
+        assert!(expanded.contains(&Utf8PathBuf::from("b.md")));
+    }
+
+    #[test]
+    fn test_expand_dirty_paths_transcluded_source_marks_dependents() {
+        use std::fs;
+
+        let root = crate::test_io::case_dir("incremental-transcluded-source");
+        let trees = root.join("trees");
+        fs::create_dir_all(&trees).unwrap();
+        fs::write(trees.join("a.md"), "# A\n\n[](/trees/b.md#:transclude-raw)\n").unwrap();
+        fs::write(trees.join("b.md"), "Shared text.\n").unwrap();
+        fs::write(trees.join("c.md"), "# C\n").unwrap();
+
+        let mut slug_exts = HashMap::new();
+        for slug in ["a", "b", "c"] {
+            slug_exts.insert(Slug::new(slug), Ext::Markdown);
+        }
+        let workspace = Workspace { slug_exts };
+
+        environment::with_test_environment(root.clone(), environment::BuildMode::Serve, || {
+            for slug in ["a", "b", "c"] {
+                let slug = Slug::new(slug);
+                let relative = source_relative_path(slug, Ext::Markdown);
+                let (sections, recorded) =
+                    crate::compiler::dependency::parse_with_dependencies(slug, Ext::Markdown)
+                        .unwrap();
+                let entry_path = environment::entry_file_path(&relative);
+                crate::compiler::write_entry_cache(entry_path.as_path(), &sections, recorded)
+                    .unwrap();
+            }
+
+            fs::write(trees.join("b.md"), "Edited text.\n").unwrap();
+            let dirty = DirtySet::from([Utf8PathBuf::from("b.md")]);
+            let expanded = expand_dirty_paths(&workspace, &dirty);
+            assert!(expanded.contains(&Utf8PathBuf::from("a.md")));
+            assert!(expanded.contains(&Utf8PathBuf::from("b.md")));
+            assert!(!expanded.contains(&Utf8PathBuf::from("c.md")));
+        });
+
+        let _ = fs::remove_dir_all(root);
     }
 
     #[test]
-    fn test_expand_dirty_paths_unknown_tree_file_marks_all_sources() {
+    fn test_expand_dirty_paths_unreferenced_tree_file_marks_no_sources() {
         let mut slug_exts = HashMap::new();
         slug_exts.insert(Slug::new("a"), Ext::Markdown);
         slug_exts.insert(Slug::new("b"), Ext::Typst);
@@ -232,9 +336,8 @@ mod tests {
         let mut dirty = DirtySet::new();
         dirty.insert(Utf8PathBuf::from("includes/snippet.txt"));
 
-        let expanded = expand_dirty_paths(&workspace, &dirty);
-        assert!(expanded.contains(&Utf8PathBuf::from("a.md")));
-        assert!(expanded.contains(&Utf8PathBuf::from("b.typst")));
+        let expanded = expand_without_dependencies(&workspace, &dirty);
+        assert_eq!(expanded, dirty);
     }
 
     #[test]
@@ -0,0 +1,281 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+use serde::Serialize;
+
+use crate::{
+    entry::{MetaData, KEY_INTERNAL_ANON_SUBTREE},
+    environment,
+    slug::Slug,
+};
+
+use super::state::CompileState;
+
+/// Neighborhoods larger than this keep only the nearest sections.
+pub const MAX_LOCAL_GRAPH_NODES: usize = 64;
+
+#[derive(Debug, Serialize)]
+pub struct LocalGraph {
+    pub nodes: Vec<LocalNode>,
+    pub edges: Vec<LocalEdge>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LocalNode {
+    pub slug: String,
+    pub title: String,
+    pub taxon: String,
+    pub url: String,
+    /// Hops from the current page.
+    pub depth: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LocalEdge {
+    pub source: String,
+    pub target: String,
+    /// `parent`, `reference` or `backlink`, as in `kodama graph export`.
+    pub kind: &'static str,
+}
+
+impl LocalGraph {
+    /// JSON that is safe to inline in a `<script>` element.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self)
+            .unwrap_or_default()
+            .replace("</", "<\\/")
+    }
+}
+
+/// The sections within `depth` hops of `slug`, following parent, child,
+/// reference and backlink relations in either direction. `None` when the page
+/// has no visible neighbors.
+pub fn local_graph(state: &CompileState, slug: Slug, depth: usize) -> Option<LocalGraph> {
+    let mut found: HashMap<Slug, usize> = HashMap::from([(slug, 0)]);
+    let mut order = vec![slug];
+    let mut queue = VecDeque::from([(slug, 0)]);
+    'search: while let Some((current, hops)) = queue.pop_front() {
+        if hops >= depth {
+            continue;
+        }
+        for next in neighbors(state, current) {
+            if found.contains_key(&next) || !is_visible(state, next) {
+                continue;
+            }
+            if found.len() >= MAX_LOCAL_GRAPH_NODES {
+                break 'search;
+            }
+            found.insert(next, hops + 1);
+            order.push(next);
+            queue.push_back((next, hops + 1));
+        }
+    }
+    if order.len() < 2 {
+        return None;
+    }
+
+    let nodes = order
+        .iter()
+        .map(|&slug| {
+            let metadata = &state.compiled()[&slug].metadata;
+            LocalNode {
+                slug: slug.to_string(),
+                title: metadata
+                    .page_title()
+                    .filter(|title| !title.trim().is_empty())
+                    .map_or_else(|| slug.to_string(), |title| title.trim().to_string()),
+                taxon: metadata.data_taxon().cloned().unwrap_or_default(),
+                url: environment::full_html_url(slug),
+                depth: found[&slug],
+            }
+        })
+        .collect();
+
+    let mut edges = BTreeSet::new();
+    for &slug in &order {
+        if let Some(parent) = state.parent_of(slug) {
+            edges.insert((parent, slug, "parent"));
+        }
+        for &target in &state.compiled()[&slug].references {
+            edges.insert((slug, target, "reference"));
+        }
+        for &source in backlinks(state, slug) {
+            edges.insert((source, slug, "backlink"));
+        }
+    }
+    let edges = edges
+        .into_iter()
+        .filter(|(source, target, _)| found.contains_key(source) && found.contains_key(target))
+        .map(|(source, target, kind)| LocalEdge {
+            source: source.to_string(),
+            target: target.to_string(),
+            kind,
+        })
+        .collect();
+
+    Some(LocalGraph { nodes, edges })
+}
+
+/// Adjacent sections in a stable order, so capped graphs are reproducible.
+fn neighbors(state: &CompileState, slug: Slug) -> Vec<Slug> {
+    let mut neighbors: Vec<Slug> = state.parent_of(slug).into_iter().collect();
+    neighbors.extend_from_slice(state.children_of(slug));
+    let mut linked: BTreeSet<Slug> = state.compiled()[&slug].references.iter().copied().collect();
+    linked.extend(backlinks(state, slug));
+    neighbors.extend(linked);
+    neighbors
+}
+
+fn backlinks(state: &CompileState, slug: Slug) -> impl Iterator<Item = &Slug> {
+    state
+        .callback()
+        .0
+        .get(&slug)
+        .into_iter()
+        .flat_map(|callback| callback.backlinks.iter())
+}
+
+fn is_visible(state: &CompileState, slug: Slug) -> bool {
+    state.compiled().get(&slug).is_some_and(|section| {
+        section
+            .metadata
+            .get_str(KEY_INTERNAL_ANON_SUBTREE)
+            .map(String::as_str)
+            != Some("true")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{
+        compiler::{
+            section::{EmbedContent, HTMLContent, LazyContent, LocalLink, UnresolvedSection},
+            state::compile_all_without_missing_index_warning,
+            UnresolvedSections,
+        },
+        entry::{HTMLMetaData, KEY_ASREF, KEY_EXT, KEY_PAGE_TITLE, KEY_SLUG},
+        environment::BuildMode,
+        ordered_map::OrderedMap,
+    };
+
+    fn with_test_env(f: impl FnOnce()) {
+        let root = crate::test_io::case_dir("local-graph");
+        std::fs::create_dir_all(root.as_std_path()).unwrap();
+        environment::with_test_environment(root.clone(), BuildMode::Publish, f);
+        let _ = std::fs::remove_dir_all(root.as_std_path());
+    }
+
+    fn shallow(slug: &str, content: Vec<LazyContent>) -> UnresolvedSection {
+        let mut metadata = OrderedMap::new();
+        metadata.insert(KEY_SLUG.to_string(), HTMLContent::Plain(slug.to_string()));
+        metadata.insert(KEY_EXT.to_string(), HTMLContent::Plain("md".to_string()));
+        metadata.insert(
+            KEY_PAGE_TITLE.to_string(),
+            HTMLContent::Plain(slug.to_uppercase()),
+        );
+        if slug == "lemma" {
+            metadata.insert(
+                KEY_ASREF.to_string(),
+                HTMLContent::Plain("true".to_string()),
+            );
+        }
+        UnresolvedSection {
+            metadata: HTMLMetaData(metadata),
+            content: HTMLContent::Lazy(content),
+        }
+    }
+
+    fn embed(slug: &str) -> LazyContent {
+        LazyContent::Embed(EmbedContent {
+            url: format!("/{}.md", slug),
+            title: None,
+            option: Default::default(),
+        })
+    }
+
+    fn link(slug: &str) -> LazyContent {
+        LazyContent::Local(LocalLink {
+            url: format!("/{}.md", slug),
+            text: None,
+        })
+    }
+
+    /// `index` embeds `chapter`, which embeds `lemma`; `note` links to `lemma`.
+    fn state() -> CompileState {
+        let mut shallows: UnresolvedSections = HashMap::new();
+        for (slug, content) in [
+            ("index", vec![embed("chapter")]),
+            ("chapter", vec![embed("lemma")]),
+            ("lemma", vec![]),
+            ("note", vec![link("lemma")]),
+        ] {
+            shallows.insert(Slug::new(slug), shallow(slug, content));
+        }
+        compile_all_without_missing_index_warning(&shallows).unwrap()
+    }
+
+    fn slugs(graph: &LocalGraph) -> Vec<(&str, usize)> {
+        graph
+            .nodes
+            .iter()
+            .map(|node| (node.slug.as_str(), node.depth))
+            .collect()
+    }
+
+    fn edges(graph: &LocalGraph) -> Vec<String> {
+        graph
+            .edges
+            .iter()
+            .map(|edge| format!("{} -{}-> {}", edge.source, edge.kind, edge.target))
+            .collect()
+    }
+
+    #[test]
+    fn test_local_graph_follows_relations_within_hops() {
+        with_test_env(|| {
+            let state = state();
+            let graph = local_graph(&state, Slug::new("lemma"), 1).unwrap();
+            assert_eq!(slugs(&graph), [("lemma", 0), ("chapter", 1), ("note", 1)]);
+            assert_eq!(
+                edges(&graph),
+                [
+                    "chapter -parent-> lemma",
+                    "note -backlink-> lemma",
+                    "note -reference-> lemma"
+                ]
+            );
+            assert_eq!(graph.nodes[1].title, "CHAPTER");
+
+            let graph = local_graph(&state, Slug::new("lemma"), 2).unwrap();
+            assert_eq!(
+                slugs(&graph),
+                [("lemma", 0), ("chapter", 1), ("note", 1), ("index", 2)]
+            );
+
+            assert!(local_graph(&state, Slug::new("lemma"), 0).is_none());
+        });
+    }
+
+    #[test]
+    fn test_local_graph_json_is_script_safe() {
+        let graph = LocalGraph {
+            nodes: vec![LocalNode {
+                slug: "a".to_string(),
+                title: "</script>".to_string(),
+                taxon: String::new(),
+                url: "/a".to_string(),
+                depth: 0,
+            }],
+            edges: vec![],
+        };
+        let json = graph.to_json();
+        assert!(!json.contains("</"));
+        assert!(json.contains(r#""title":"<\/script>""#));
+    }
+}
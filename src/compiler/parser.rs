@@ -18,7 +18,8 @@ use crate::{
     ordered_map::OrderedMap,
     process::{
         content::to_contents, embed_markdown::Embed, figure::Figure, filter_raw_html,
-        footnote::Footnote, ignore_paragraph, metadata::Metadata, text_elaborator::TextElaborator,
+        footnote::Footnote, ignore_paragraph, link_action::LinkAction,
+        markdown_math::MarkdownMathRenderer, metadata::Metadata, text_elaborator::TextElaborator,
         typst_image::TypstImage,
     },
     slug::Slug,
@@ -29,13 +30,16 @@ use super::subtree_slug::ensure_unique_section_slugs;
 use super::{section::LazyContent, HTMLContent, UnresolvedSection};
 
 mod subtree;
+mod transclude;
 use subtree::{
     apply_subtree_defaults, compose_subtree_source, extract_shared_reference_definitions,
     extract_subtrees_nested, extract_subtrees_root, patch_root_subtree_embeds, SubtreeSpec,
 };
+use transclude::expand_transclusions;
 
 pub const OPTIONS: Options = Options::ENABLE_MATH
     .union(Options::ENABLE_YAML_STYLE_METADATA_BLOCKS)
+    .union(Options::ENABLE_PLUSES_DELIMITED_METADATA_BLOCKS)
     .union(Options::ENABLE_TABLES)
     .union(Options::ENABLE_SMART_PUNCTUATION)
     .union(Options::ENABLE_FOOTNOTES)
@@ -49,7 +53,8 @@ pub const OPTIONS: Options = Options::ENABLE_MATH
 pub fn initialize(slug: Slug) -> eyre::Result<String> {
     let fullname = format!("{}.md", slug);
     let markdown_path = input_path(&fullname);
-    std::fs::read_to_string(&markdown_path)
+    crate::environment::fs()
+        .read_to_string(&markdown_path)
         .wrap_err_with(|| eyre!("failed to read markdown file `{:?}`", markdown_path))
 }
 
@@ -131,7 +136,8 @@ pub(super) fn parse_markdown_source(source: &str, slug: Slug) -> eyre::Result<Un
     metadata.insert(KEY_SLUG.to_string(), HTMLContent::Plain(slug.to_string()));
     metadata.insert(KEY_EXT.to_string(), HTMLContent::Plain("md".to_string()));
 
-    let events = pulldown_cmark::Parser::new_ext(source, OPTIONS);
+    let source = expand_transclusions(source, slug)?;
+    let events = pulldown_cmark::Parser::new_ext(&source, OPTIONS);
     let events = filter_raw_html(events, environment::allow_unsafe_html());
 
     let content = Metadata::process(events, &mut metadata)
@@ -139,6 +145,8 @@ pub(super) fn parse_markdown_source(source: &str, slug: Slug) -> eyre::Result<Un
             let events = Footnote::process(events, slug);
             let events = Figure::process(events);
             let events = TypstImage::process(events, slug);
+            let events = LinkAction::process(events, slug);
+            let events = MarkdownMathRenderer::process(events, slug);
             let events = TextElaborator::process(events);
             let events = Embed::process(events, slug);
             normalize_html_content(to_contents(events))
@@ -155,6 +163,8 @@ pub fn parse_spanned_markdown(markdown_input: &str, slug: Slug) -> HTMLContent {
     let events = ignore_paragraph(events);
     let events = Footnote::process(events, slug);
     let events = TypstImage::process(events, slug);
+    let events = LinkAction::process(events, slug);
+    let events = MarkdownMathRenderer::process(events, slug);
     let events = TextElaborator::process(events);
     let events = Embed::process(events, slug);
     normalize_html_content(to_contents(events))
@@ -36,10 +36,131 @@ pub fn test_code_block() {
     let content = normalize_html_content(to_contents(events));
     assert_eq!(
         content.as_str().unwrap(),
-        "<pre><code class=\"language-rs\">let x = 1;\n</code></pre>\n"
+        concat!(
+            "<pre><code class=\"language-rs\">",
+            "<span class=\"hl-source hl-rust\">",
+            "<span class=\"hl-storage hl-type hl-rust\">let</span> x ",
+            "<span class=\"hl-keyword hl-operator hl-rust\">=</span> ",
+            "<span class=\"hl-constant hl-numeric hl-integer hl-decimal hl-rust\">1</span>",
+            "<span class=\"hl-punctuation hl-terminator hl-rust\">;</span></span>\n",
+            "</code></pre>\n"
+        )
     );
 }
 
+#[test]
+pub fn test_code_block_without_known_language_is_escaped() {
+    let source = "```\nx < y\n```\n\n    indented & raw\n";
+    let content = parse_spanned_markdown(source, Slug::new("-"));
+    assert_eq!(
+        content.as_str().unwrap(),
+        "<pre><code>x &lt; y\n</code></pre>\n<pre><code>indented &amp; raw\n</code></pre>\n"
+    );
+}
+
+#[test]
+pub fn test_code_block_line_numbers_and_disabled_highlighting() {
+    use std::fs;
+
+    let root = crate::test_io::case_dir("parser-code-line-numbers");
+    fs::create_dir_all(root.as_std_path()).unwrap();
+    let config_path = root.join("Kodama.toml");
+    fs::write(
+        config_path.as_std_path(),
+        r#"
+[build]
+highlight = false
+line-numbers = true
+"#,
+    )
+    .unwrap();
+
+    crate::environment::with_test_environment(
+        root.clone(),
+        crate::environment::BuildMode::Publish,
+        || {
+            crate::environment::init_environment(
+                config_path.clone(),
+                crate::environment::BuildMode::Publish,
+            )
+            .unwrap();
+
+            let content = parse_spanned_markdown("```rs\nlet a;\nlet b;\n```", Slug::new("-"));
+            assert_eq!(
+                content.as_str().unwrap(),
+                concat!(
+                    "<pre class=\"line-numbers\"><code class=\"language-rs\">",
+                    "<span class=\"line\">let a;</span>\n",
+                    "<span class=\"line\">let b;</span>\n",
+                    "</code></pre>\n"
+                )
+            );
+        },
+    );
+
+    let _ = fs::remove_dir_all(root.as_std_path());
+}
+
+#[test]
+pub fn test_include_line_range_and_region_with_caption() {
+    use std::fs;
+
+    let root = crate::test_io::case_dir("parser-include-excerpt");
+    fs::create_dir_all(root.join("examples").as_std_path()).unwrap();
+    fs::write(
+        root.join("examples/demo.rs"),
+        "fn main() {\n    // region: setup\n    let a = 1;\n    // endregion\n}\n",
+    )
+    .unwrap();
+    let config_path = root.join("Kodama.toml");
+    fs::write(
+        config_path.as_std_path(),
+        r#"
+[build]
+highlight = false
+line-numbers = true
+"#,
+    )
+    .unwrap();
+
+    crate::environment::with_test_environment(
+        root.clone(),
+        crate::environment::BuildMode::Publish,
+        || {
+            crate::environment::init_environment(
+                config_path.clone(),
+                crate::environment::BuildMode::Publish,
+            )
+            .unwrap();
+
+            let content = parse_spanned_markdown(
+                "[rs](/examples/demo.rs#setup#:include \"Setup\")",
+                Slug::new("-"),
+            );
+            assert_eq!(
+                content.as_str().unwrap(),
+                concat!(
+                    "<figure class=\"code-excerpt\">",
+                    "<pre class=\"line-numbers\">",
+                    "<code class=\"language-rs\" style=\"counter-reset: line 2\">",
+                    "<span class=\"line\">    let a = 1;</span>\n",
+                    "</code></pre>",
+                    "<figcaption>Setup <span class=\"code-source\">examples/demo.rs:3</span>",
+                    "</figcaption></figure>"
+                )
+            );
+
+            let content =
+                parse_spanned_markdown("[rs](/examples/demo.rs#L1-L9#:include)", Slug::new("-"));
+            let html = content.as_str().unwrap();
+            assert!(html.contains("fn main() {"), "{html}");
+            assert!(!html.contains("region"), "{html}");
+        },
+    );
+
+    let _ = fs::remove_dir_all(root.as_std_path());
+}
+
 #[test]
 pub fn test_reference_link() {
     let source =
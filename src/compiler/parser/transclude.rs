@@ -0,0 +1,151 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+use std::{borrow::Cow, ops::Range};
+
+use eyre::eyre;
+use pulldown_cmark::{Event, Parser, Tag};
+
+use crate::{
+    compiler,
+    environment::{self, root_dir},
+    process::{
+        embed_markdown::{record_include_error, resolve_include_url},
+        processor::url_action,
+    },
+    recorder::State,
+    slug::Slug,
+};
+
+use super::OPTIONS;
+
+/// Replace every `#:transclude-raw` link in `source` with the body of the linked
+/// Markdown file, recursively, so it becomes part of the section source. Paths
+/// resolve like `#:include`. A file that transcludes itself, directly or not,
+/// is an error.
+pub(super) fn expand_transclusions(source: &str, slug: Slug) -> eyre::Result<Cow<'_, str>> {
+    if !source.contains(State::TranscludeRaw.strify()) {
+        return Ok(Cow::Borrowed(source));
+    }
+    let mut stack = Vec::new();
+    expand(source, slug.as_str(), &mut stack).map(Cow::Owned)
+}
+
+fn expand(source: &str, base: &str, stack: &mut Vec<String>) -> eyre::Result<String> {
+    let mut expanded = String::with_capacity(source.len());
+    let mut last = 0;
+    for (range, url) in transclusion_links(source) {
+        expanded.push_str(&source[last..range.start]);
+        last = range.end;
+
+        let path = resolve_include_url(&url, base);
+        if stack.contains(&path) {
+            let mut chain = stack.clone();
+            chain.push(path);
+            return Err(eyre!(
+                "cyclic transclusion detected: {}",
+                chain.join(" -> ")
+            ));
+        }
+
+        compiler::record_root_dependency(&path);
+        let full_path = root_dir().join(&path);
+        let content = match environment::fs().read_to_string(&full_path) {
+            Ok(content) => content,
+            Err(err) => {
+                record_include_error();
+                color_print::ceprintln!(
+                    "<y>Warning: failed to transclude file `{}` resolved to `{}`: {}</>",
+                    url,
+                    full_path,
+                    err
+                );
+                continue;
+            }
+        };
+
+        stack.push(path.clone());
+        let body = expand(markdown_body(&content), &path, stack)?;
+        stack.pop();
+        expanded.push_str(body.trim_end_matches('\n'));
+    }
+    expanded.push_str(&source[last..]);
+    Ok(expanded)
+}
+
+/// Byte ranges and URLs of the transclusion links, skipping code.
+fn transclusion_links(source: &str) -> Vec<(Range<usize>, String)> {
+    Parser::new_ext(source, OPTIONS)
+        .into_offset_iter()
+        .filter_map(|(event, range)| match event {
+            Event::Start(Tag::Link { dest_url, .. }) => {
+                let (url, action) = url_action(&dest_url);
+                (action == State::TranscludeRaw.strify()).then_some((range, url))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// The source without its leading metadata block.
+fn markdown_body(source: &str) -> &str {
+    match Parser::new_ext(source, OPTIONS).into_offset_iter().next() {
+        Some((Event::Start(Tag::MetadataBlock(_)), range)) => source[range.end..].trim_start(),
+        _ => source,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::environment::{self, BuildMode};
+
+    use super::*;
+
+    #[test]
+    fn test_expand_transclusions_splices_bodies_recursively() {
+        let root = crate::test_io::case_dir("parser-transclude");
+        fs::create_dir_all(root.join("partials")).unwrap();
+        fs::write(
+            root.join("partials/licence.md"),
+            "---\ntitle: Licence\n---\n\nCC BY 4.0. [](./notation.md#:transclude-raw)\n",
+        )
+        .unwrap();
+        fs::write(root.join("partials/notation.md"), "| a | b |\n").unwrap();
+
+        environment::with_test_environment(root.clone(), BuildMode::Publish, || {
+            let source = "# A\n\n[](/partials/licence.md#:transclude-raw)\n\n```\n[](/x.md#:transclude-raw)\n```\n";
+            let expanded = expand_transclusions(source, Slug::new("a")).unwrap();
+            assert_eq!(
+                expanded,
+                "# A\n\nCC BY 4.0. | a | b |\n\n```\n[](/x.md#:transclude-raw)\n```\n"
+            );
+
+            let untouched = expand_transclusions("[](./b.md#:include)", Slug::new("a")).unwrap();
+            assert!(matches!(untouched, Cow::Borrowed(_)));
+        });
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn test_expand_transclusions_detects_cycles() {
+        let root = crate::test_io::case_dir("parser-transclude-cycle");
+        fs::create_dir_all(root.join("partials")).unwrap();
+        fs::write(root.join("partials/a.md"), "[](./b.md#:transclude-raw)\n").unwrap();
+        fs::write(root.join("partials/b.md"), "[](./a.md#:transclude-raw)\n").unwrap();
+
+        environment::with_test_environment(root.clone(), BuildMode::Publish, || {
+            let err = expand_transclusions("[](/partials/a.md#:transclude-raw)", Slug::new("x"))
+                .unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "cyclic transclusion detected: partials/a.md -> partials/b.md -> partials/a.md"
+            );
+        });
+
+        let _ = fs::remove_dir_all(root);
+    }
+}
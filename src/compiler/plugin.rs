@@ -0,0 +1,176 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+use std::{borrow::Cow, collections::HashMap};
+
+use camino::Utf8Path;
+use eyre::{bail, eyre, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use crate::{config::plugin::Plugin, environment, external_command, slug::Slug};
+
+use super::{section::UnresolvedSection, CachedSection, UnresolvedSections};
+
+/// Version of the JSON exchanged with plugins. Bumped on incompatible changes.
+pub const PLUGIN_PROTOCOL_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct PluginRequest<'a> {
+    version: u32,
+    kodama: &'static str,
+    mode: &'static str,
+    root: &'a Utf8Path,
+    sections: Vec<PluginSection<'a>>,
+}
+
+#[derive(Serialize)]
+struct PluginSection<'a> {
+    slug: Slug,
+    section: &'a UnresolvedSection,
+}
+
+#[derive(Deserialize)]
+struct PluginResponse {
+    sections: Vec<CachedSection>,
+}
+
+/// Pass the sections through every configured `[[plugin]]`, in order.
+pub fn run_plugins(shallows: &UnresolvedSections) -> eyre::Result<Cow<'_, UnresolvedSections>> {
+    let plugins = environment::plugins();
+    if plugins.is_empty() {
+        return Ok(Cow::Borrowed(shallows));
+    }
+    let root = environment::root_dir();
+    let mode = environment::build_mode_name();
+    let mut sections = Cow::Borrowed(shallows);
+    for plugin in &plugins {
+        sections = Cow::Owned(
+            run_plugin(plugin, &sections, &root, mode)
+                .wrap_err_with(|| eyre!("plugin `{}` failed", plugin.name))?,
+        );
+    }
+    Ok(sections)
+}
+
+fn run_plugin(
+    plugin: &Plugin,
+    shallows: &UnresolvedSections,
+    root: &Utf8Path,
+    mode: &'static str,
+) -> eyre::Result<UnresolvedSections> {
+    let mut sections: Vec<PluginSection> = shallows
+        .iter()
+        .map(|(slug, section)| PluginSection {
+            slug: *slug,
+            section,
+        })
+        .collect();
+    sections.sort_by_key(|section| section.slug);
+    let request = serde_json::to_vec(&PluginRequest {
+        version: PLUGIN_PROTOCOL_VERSION,
+        kodama: env!("CARGO_PKG_VERSION"),
+        mode,
+        root,
+        sections,
+    })
+    .wrap_err("failed to serialize sections")?;
+
+    let stdout = external_command::run_piped(&plugin.command, root, request, plugin.timeout)?;
+    let response: PluginResponse =
+        serde_json::from_slice(&stdout).wrap_err("plugin wrote invalid JSON to stdout")?;
+
+    let mut sections = HashMap::with_capacity(response.sections.len());
+    for CachedSection { slug, section } in response.sections {
+        let metadata_slug = section.slug()?;
+        if metadata_slug != slug {
+            bail!(
+                "section `{}` has metadata slug `{}`; both must match",
+                slug,
+                metadata_slug
+            );
+        }
+        if sections.insert(slug, section).is_some() {
+            bail!("section `{}` is returned more than once", slug);
+        }
+    }
+    Ok(sections)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use crate::{
+        compiler::section::HTMLContent,
+        entry::{HTMLMetaData, KEY_EXT, KEY_SLUG},
+        ordered_map::OrderedMap,
+    };
+
+    use super::*;
+
+    fn shallow(slug: &str, content: &str) -> UnresolvedSection {
+        let mut metadata = OrderedMap::new();
+        metadata.insert(KEY_SLUG.to_string(), HTMLContent::Plain(slug.to_string()));
+        metadata.insert(KEY_EXT.to_string(), HTMLContent::Plain("md".to_string()));
+        UnresolvedSection {
+            metadata: HTMLMetaData(metadata),
+            content: HTMLContent::Plain(content.to_string()),
+        }
+    }
+
+    fn shallows() -> UnresolvedSections {
+        HashMap::from([(Slug::new("a"), shallow("a", "<p>TeX</p>"))])
+    }
+
+    fn plugin(script: &str, timeout: u64) -> Plugin {
+        Plugin {
+            name: "test".to_string(),
+            command: vec!["sh".to_string(), "-c".to_string(), script.to_string()],
+            timeout,
+        }
+    }
+
+    fn run(script: &str, timeout: u64) -> eyre::Result<UnresolvedSections> {
+        run_plugin(
+            &plugin(script, timeout),
+            &shallows(),
+            Utf8Path::new("."),
+            "build",
+        )
+    }
+
+    #[test]
+    fn test_run_plugin_replaces_sections_with_stdout() {
+        let sections = run("sed 's/TeX/LaTeX/'", 0).unwrap();
+        assert_eq!(
+            sections[&Slug::new("a")].content.as_str(),
+            Some("<p>LaTeX</p>")
+        );
+    }
+
+    #[test]
+    fn test_run_plugin_reports_exit_status_and_stderr() {
+        let err = run("cat > /dev/null; echo broken >&2; exit 3", 0).unwrap_err();
+        let message = format!("{err:#}");
+        assert!(message.contains("exited with"), "{message}");
+        assert!(message.contains("broken"), "{message}");
+    }
+
+    #[test]
+    fn test_run_plugin_rejects_invalid_output() {
+        let err = run("cat > /dev/null; echo '{}'", 0).unwrap_err();
+        assert!(format!("{err:#}").contains("invalid JSON"));
+
+        let duplicated = r#"cat > /dev/null; echo '{"sections": [
+            {"slug": "a", "section": {"metadata": {"slug": {"Plain": "a"}}, "content": {"Plain": ""}}},
+            {"slug": "a", "section": {"metadata": {"slug": {"Plain": "a"}}, "content": {"Plain": ""}}}
+        ]}'"#;
+        let err = run(duplicated, 0).unwrap_err();
+        assert!(format!("{err:#}").contains("more than once"));
+    }
+
+    #[test]
+    fn test_run_plugin_kills_plugin_after_timeout() {
+        let err = run("sleep 5", 1).unwrap_err();
+        assert!(format!("{err:#}").contains("timed out after 1s"));
+    }
+}
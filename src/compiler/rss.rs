@@ -5,7 +5,11 @@
 use eyre::eyre;
 use url::Url;
 
-use crate::{entry::MetaData, environment, slug::Slug};
+use crate::{
+    entry::{MetaData, KEY_CREATED},
+    environment,
+    slug::Slug,
+};
 
 use super::{state::CompileState, writer::Writer};
 
@@ -112,9 +116,11 @@ fn collect_items(state: &CompileState) -> eyre::Result<Vec<FeedItem>> {
             .filter(|value| !value.is_empty())
             .unwrap_or(slug.as_str())
             .to_string();
+        // Sections without a `date` fall back to when their file was created.
         let date = section
             .metadata
             .get_str("date")
+            .or_else(|| section.metadata.get_str(KEY_CREATED))
             .cloned()
             .unwrap_or_default();
         let link = environment::full_html_url(slug);
@@ -464,6 +470,21 @@ mod tests {
         assert!(xml.contains("<lastBuildDate>Sun, 15 Aug 2021 00:00:00 GMT</lastBuildDate>"));
     }
 
+    #[test]
+    fn test_feed_xml_falls_back_to_created_without_date() {
+        let mut post = shallow("post", "Post", None, "<p>Hello</p>");
+        post.metadata.0.insert(
+            KEY_CREATED.to_string(),
+            HTMLContent::Plain("2024-02-29T10:30:00Z".to_string()),
+        );
+        let mut shallows = HashMap::new();
+        shallows.insert(Slug::new("post"), post);
+        let state = compile_all_without_missing_index_warning(&shallows).unwrap();
+
+        let xml = feed_xml(&state).unwrap();
+        assert!(xml.contains("<pubDate>Thu, 29 Feb 2024 00:00:00 GMT</pubDate>"));
+    }
+
     #[test]
     fn test_feed_xml_keeps_raw_pub_date_when_unparseable() {
         let state = compile_state_for_feed("not-a-date", "<p>Hello</p>");
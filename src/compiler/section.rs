@@ -8,7 +8,7 @@ use serde::{Deserialize, Serialize};
 use std::{collections::HashSet, mem, sync::LazyLock};
 
 use crate::{
-    entry::{EntryMetaData, HTMLMetaData, MetaData},
+    entry::{EntryMetaData, HTMLMetaData, MetaData, MetaValue},
     slug::Slug,
 };
 
@@ -73,6 +73,8 @@ pub type LazyContents = Vec<LazyContent>;
 pub enum HTMLContent {
     Plain(String),
     Lazy(LazyContents),
+    /// Lists and tables from frontmatter or Typst metadata; never section content.
+    Structured(MetaValue),
 }
 
 impl HTMLContent {
@@ -133,6 +135,23 @@ impl HTMLContent {
                 }
                 str
             }
+            HTMLContent::Structured(value) => value.to_text(),
+        }
+    }
+
+    /// Plain text for matching and display, with tags removed and entities unescaped.
+    pub fn to_text(&self) -> String {
+        match self {
+            HTMLContent::Structured(value) => value.to_text(),
+            content => htmlize::unescape(content.remove_all_tags()).into_owned(),
+        }
+    }
+
+    /// Structured values as they are, other content as its plain text.
+    pub fn to_meta_value(&self) -> MetaValue {
+        match self {
+            HTMLContent::Structured(value) => value.clone(),
+            content => MetaValue::Text(content.to_text()),
         }
     }
 }
@@ -8,16 +8,18 @@ use eyre::{eyre, WrapErr};
 
 use crate::{
     environment::{self, verify_and_file_hash},
+    ordered_map::OrderedMap,
     slug::{Ext, Slug},
 };
 
-use super::section::UnresolvedSection;
+use super::section::{HTMLContent, UnresolvedSection};
 use super::{
     compile_from_shallows,
+    dependency::parse_with_dependencies,
     incremental::{dirty_source_slugs, source_relative_path},
-    parse_source_sections,
+    indexes_from_shallows, run_plugins,
     stale::cleanup_stale_slug_artifacts,
-    write_entry_cache, CompileOutputs, DirtySet, Workspace,
+    write_entry_cache, CompileOutputs, DirtySet, SourceTimes, Workspace,
 };
 
 #[derive(Default)]
@@ -25,6 +27,8 @@ pub struct ServeCompileSession {
     initialized: bool,
     shallows: HashMap<Slug, UnresolvedSection>,
     source_sections: HashMap<Slug, Vec<Slug>>,
+    /// Section metadata after plugins, from the last compile.
+    indexes: HashMap<Slug, OrderedMap<String, HTMLContent>>,
 }
 
 impl ServeCompileSession {
@@ -43,7 +47,7 @@ impl ServeCompileSession {
         self.shallows = shallows;
         self.source_sections = source_sections;
         self.initialized = true;
-        compile_from_shallows(&workspace, &self.shallows, None, outputs, stale_slugs)
+        self.compile(&workspace, None, outputs, stale_slugs)
     }
 
     pub fn compile_incremental(
@@ -79,6 +83,7 @@ impl ServeCompileSession {
         }
 
         let needs_full_write = parse_targets.iter().any(|slug| !dirty_slugs.contains(slug));
+        let mut times = SourceTimes::new();
 
         for slug in parse_targets {
             let Some(&ext) = workspace.slug_exts.get(&slug) else {
@@ -88,8 +93,9 @@ impl ServeCompileSession {
             let _ = verify_and_file_hash(relative_path.as_path())
                 .wrap_err_with(|| eyre!("failed to verify hash of `{relative_path}`"))?;
             let entry_path = environment::entry_file_path(relative_path.as_path());
-            let sections = parse_source_sections(slug, ext)?;
-            write_entry_cache(entry_path.as_path(), &sections)?;
+            let (mut sections, recorded) = parse_with_dependencies(slug, ext)?;
+            write_entry_cache(entry_path.as_path(), &sections, recorded)?;
+            times.insert(slug, ext, &mut sections)?;
 
             if let Some(previous_slugs) = self.source_sections.remove(&slug) {
                 for previous_slug in previous_slugs {
@@ -118,9 +124,8 @@ impl ServeCompileSession {
         }
         self.initialized = true;
 
-        compile_from_shallows(
+        self.compile(
             &workspace,
-            &self.shallows,
             if needs_full_write {
                 None
             } else {
@@ -137,7 +142,26 @@ impl ServeCompileSession {
         let workspace = Workspace {
             slug_exts: HashMap::new(),
         };
-        compile_from_shallows(&workspace, &self.shallows, None, outputs, HashSet::new())
+        self.compile(&workspace, None, outputs, HashSet::new())
+    }
+
+    /// Section metadata after plugins, as written to the indexes artifact.
+    pub fn indexes(&self) -> &HashMap<Slug, OrderedMap<String, HTMLContent>> {
+        &self.indexes
+    }
+
+    /// Run the plugins once over the session's shallows, keeping their
+    /// metadata for [`Self::indexes`], and compile the result.
+    fn compile(
+        &mut self,
+        workspace: &Workspace,
+        dirty_paths: Option<&DirtySet>,
+        outputs: CompileOutputs,
+        stale_slugs: HashSet<Slug>,
+    ) -> eyre::Result<()> {
+        let shallows = run_plugins(&self.shallows)?;
+        self.indexes = indexes_from_shallows(&shallows);
+        compile_from_shallows(workspace, &shallows, dirty_paths, outputs, stale_slugs)
     }
 
     fn needs_refresh(&self, slug: Slug, ext: Ext) -> bool {
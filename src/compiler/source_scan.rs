@@ -9,11 +9,13 @@ use eyre::{bail, eyre, WrapErr};
 use walkdir::WalkDir;
 
 use crate::{
-    environment, path_utils,
+    environment::{self, TreeMount},
+    path_utils,
     slug::{Ext, Slug},
+    vfs::{DiskFs, FileSystem},
 };
 
-use super::DirtySet;
+use super::{DirtySet, ExcludeRules};
 
 #[derive(Debug)]
 pub struct Workspace {
@@ -36,12 +38,89 @@ fn to_slug_ext(source_dir: &Utf8Path, p: &Utf8Path) -> Option<(Slug, Ext)> {
     Some((slug, ext))
 }
 
-/// Collect all source file paths in `<trees>` dir.
+fn is_excluded(
+    fs: &dyn FileSystem,
+    rules: &ExcludeRules,
+    trees_dir: &Utf8Path,
+    path: &Utf8Path,
+) -> bool {
+    path.strip_prefix(trees_dir)
+        .is_ok_and(|relative| rules.is_excluded(relative, fs.is_dir(path)))
+}
+
+/// The configured tree mounts, with the main one rooted at `trees_dir`.
+fn mounts_with_main(trees_dir: &Utf8Path) -> Vec<TreeMount> {
+    let mut mounts = environment::tree_mounts();
+    mounts[0].dir = trees_dir.to_owned();
+    mounts
+}
+
+/// Collect all source file paths in `<trees>` dir and the mounted source
+/// directories, honoring the exclude patterns.
 pub fn all_trees_source(trees_dir: &Utf8Path) -> eyre::Result<Workspace> {
-    all_trees_source_inner(trees_dir)
+    all_mounts_source(&mounts_with_main(trees_dir))
 }
 
-fn all_trees_source_inner(trees_dir: &Utf8Path) -> eyre::Result<Workspace> {
+fn validate_mounts(mounts: &[TreeMount]) -> eyre::Result<()> {
+    let mut prefixes = HashMap::new();
+    for mount in mounts.iter().filter(|mount| !mount.is_main()) {
+        if mount.prefix.is_empty() || mount.prefix.split('/').any(|part| part == "..") {
+            bail!(
+                "mount `{}` needs a non-empty prefix inside the slug namespace",
+                mount.dir
+            );
+        }
+        if let Some(other) = prefixes.insert(mount.prefix.as_str(), &mount.dir) {
+            bail!(
+                "mounts `{}` and `{}` share the prefix `{}`",
+                other,
+                mount.dir,
+                mount.prefix
+            );
+        }
+    }
+    Ok(())
+}
+
+fn all_mounts_source(mounts: &[TreeMount]) -> eyre::Result<Workspace> {
+    validate_mounts(mounts)?;
+
+    let mut slug_exts = HashMap::new();
+    let mut origins: HashMap<Slug, Utf8PathBuf> = HashMap::new();
+    for mount in mounts {
+        let rules = ExcludeRules::load(&mount.dir)?;
+        let workspace = all_trees_source_inner(&mount.dir, &rules)?;
+        for (inner_slug, ext) in workspace.slug_exts {
+            let path = mount.dir.join(format!("{}.{}", inner_slug, ext));
+            let virtual_path = mount.virtual_path(Utf8Path::new(inner_slug.as_str()));
+            let slug = Slug::new(virtual_path.as_str());
+
+            if let Some((owner, _)) = environment::locate_in_mounts(mounts, &virtual_path) {
+                if owner.prefix != mount.prefix {
+                    bail!(
+                        "`{}` is shadowed by the mount `{}` with prefix `{}`",
+                        path,
+                        owner.dir,
+                        owner.prefix
+                    );
+                }
+            }
+            if let Some(previous) = origins.insert(slug, path.clone()) {
+                bail!(
+                    "slug `{}` is provided by both `{}` and `{}`",
+                    slug,
+                    previous,
+                    path
+                );
+            }
+            slug_exts.insert(slug, ext);
+        }
+    }
+
+    Ok(Workspace { slug_exts })
+}
+
+fn all_trees_source_inner(trees_dir: &Utf8Path, rules: &ExcludeRules) -> eyre::Result<Workspace> {
     let mut slug_exts = HashMap::new();
 
     let failed_to_read_dir = |dir: &Utf8Path| eyre!("failed to read directory `{}`", dir);
@@ -53,16 +132,39 @@ fn all_trees_source_inner(trees_dir: &Utf8Path) -> eyre::Result<Workspace> {
         )
     };
 
+    let fs = environment::fs();
+    let fs = fs.as_ref();
+    // Symbolic links are followed, so each pending directory carries the
+    // resolved paths of its ancestors to catch links that loop back.
+    let resolve = |dir: &Utf8Path, ancestors: &[Utf8PathBuf]| {
+        let resolved = fs
+            .canonicalize(dir)
+            .wrap_err_with(|| failed_to_read_dir(dir))?;
+        if let Some(ancestor) = ancestors.iter().find(|ancestor| **ancestor == resolved) {
+            bail!(
+                "failed to read directory `{}`: it links back to its ancestor `{}`",
+                dir,
+                ancestor
+            );
+        }
+        let mut chain = ancestors.to_vec();
+        chain.push(resolved);
+        Ok(chain)
+    };
     let mut collect_files = |source_dir: &Utf8Path| {
-        for entry in source_dir
-            .read_dir_utf8()
+        // The top level only skips `README.md`; nested directories only skip
+        // hidden and underscored directories.
+        let root = resolve(source_dir, &[])?;
+        let mut pending = Vec::new();
+        for path in fs
+            .read_dir(source_dir)
             .wrap_err_with(|| failed_to_read_dir(source_dir))?
         {
-            let path = entry
-                .wrap_err_with(|| failed_to_read_dir(source_dir))?
-                .into_path();
+            if is_excluded(fs, rules, source_dir, &path) {
+                continue;
+            }
 
-            if path.is_file() && !should_ignore_file(&path) {
+            if fs.is_file(&path) && !should_ignore_file(&path) {
                 let Some((slug, ext)) = to_slug_ext(source_dir, &path) else {
                     continue;
                 };
@@ -70,43 +172,37 @@ fn all_trees_source_inner(trees_dir: &Utf8Path) -> eyre::Result<Workspace> {
                 if let Some(ext) = slug_exts.insert(slug, ext) {
                     bail!(file_collide(&path, ext));
                 };
-            } else if path.is_dir() && !should_ignore_dir(&path) {
-                for entry in WalkDir::new(&path)
-                    .follow_links(true)
-                    .into_iter()
-                    .filter_entry(|e| {
-                        Utf8Path::from_path(e.path())
-                            .is_some_and(|p| p.is_file() || !should_ignore_dir(p))
-                    })
-                {
-                    let std_path = entry
-                        .wrap_err_with(|| failed_to_read_dir(&path))?
-                        .into_path();
-                    let path = match Utf8PathBuf::from_path_buf(std_path) {
-                        Ok(path) => path,
-                        Err(non_utf8) => {
-                            color_print::ceprintln!(
-                                "<y>Warning: skipping non-UTF-8 path `{}`.</>",
-                                non_utf8.display()
-                            );
-                            continue;
-                        }
+            } else if fs.is_dir(&path) && !should_ignore_dir(&path) {
+                let chain = resolve(&path, &root)?;
+                pending.push((path, chain));
+            }
+        }
+
+        while let Some((dir, ancestors)) = pending.pop() {
+            for path in fs
+                .read_dir(&dir)
+                .wrap_err_with(|| failed_to_read_dir(&dir))?
+            {
+                if is_excluded(fs, rules, source_dir, &path) {
+                    continue;
+                }
+                if fs.is_file(&path) {
+                    let Some((slug, ext)) = to_slug_ext(source_dir, &path) else {
+                        continue;
                     };
-                    if path.is_file() {
-                        let Some((slug, ext)) = to_slug_ext(source_dir, &path) else {
-                            continue;
-                        };
-                        if let Some(ext) = slug_exts.insert(slug, ext) {
-                            bail!(file_collide(&path, ext));
-                        }
+                    if let Some(ext) = slug_exts.insert(slug, ext) {
+                        bail!(file_collide(&path, ext));
                     }
+                } else if fs.is_dir(&path) && !should_ignore_dir(&path) {
+                    let chain = resolve(&path, &ancestors)?;
+                    pending.push((path, chain));
                 }
             }
         }
         Ok(())
     };
 
-    if !trees_dir.exists() {
+    if !fs.exists(trees_dir) {
         color_print::ceprintln!(
             "<y>Warning: Source directory `{}` does not exist, skipping.</>",
             trees_dir
@@ -123,60 +219,39 @@ pub fn sync_typst_svg_assets(
     trees_dir: &Utf8Path,
     dirty_paths: Option<&DirtySet>,
 ) -> eyre::Result<()> {
-    if !trees_dir.exists() {
-        return Ok(());
+    let mut mounts = Vec::new();
+    for mount in mounts_with_main(trees_dir) {
+        if mount.dir.exists() {
+            let rules = ExcludeRules::load(&mount.dir)?;
+            mounts.push((mount, rules));
+        }
     }
 
     match dirty_paths {
         Some(dirty_paths) => {
+            let tree_mounts: Vec<TreeMount> =
+                mounts.iter().map(|(mount, _)| mount.clone()).collect();
             for relative in dirty_paths {
                 if relative.extension() != Some("typ") || is_under_ignored_dir(relative.as_path()) {
                     continue;
                 }
-                let full_path = trees_dir.join(relative);
-                if !full_path.is_file() {
+                let Some((mount, inner)) = environment::locate_in_mounts(&tree_mounts, relative)
+                else {
+                    continue;
+                };
+                let excluded = mounts
+                    .iter()
+                    .find(|(candidate, _)| candidate.prefix == mount.prefix)
+                    .is_some_and(|(_, rules)| rules.is_excluded(&inner, false));
+                if excluded || !mount.dir.join(&inner).is_file() {
                     continue;
                 }
-                compile_typst_svg(trees_dir, relative.as_path());
+                compile_typst_svg(relative.as_path());
             }
         }
         None => {
-            for entry in WalkDir::new(trees_dir)
-                .follow_links(true)
-                .into_iter()
-                .filter_entry(|e| {
-                    Utf8Path::from_path(e.path())
-                        .is_some_and(|p| p.is_file() || !should_ignore_dir(p))
-                })
-            {
-                let std_path = match entry {
-                    Ok(entry) => entry.into_path(),
-                    Err(err) => {
-                        color_print::ceprintln!(
-                            "<y>Warning: failed to read path while scanning typ assets: {}</>",
-                            err
-                        );
-                        continue;
-                    }
-                };
-                let path = match Utf8PathBuf::from_path_buf(std_path) {
-                    Ok(path) => path,
-                    Err(non_utf8) => {
-                        color_print::ceprintln!(
-                            "<y>Warning: skipping non-UTF-8 path `{}`.</>",
-                            non_utf8.display()
-                        );
-                        continue;
-                    }
-                };
-                if !path.is_file() || path.extension() != Some("typ") {
-                    continue;
-                }
-                let relative = match path.strip_prefix(trees_dir) {
-                    Ok(relative) => relative,
-                    Err(_) => continue,
-                };
-                compile_typst_svg(trees_dir, relative);
+            for (mount, rules) in &mounts {
+                sync_mount_typst_svg_assets(mount, rules);
             }
         }
     }
@@ -184,11 +259,55 @@ pub fn sync_typst_svg_assets(
     Ok(())
 }
 
-fn compile_typst_svg(trees_dir: &Utf8Path, relative: &Utf8Path) {
+/// Typst runs on the disk, whatever the file system of the environment.
+fn sync_mount_typst_svg_assets(mount: &TreeMount, rules: &ExcludeRules) {
+    let source_dir = mount.dir.as_path();
+    for entry in WalkDir::new(source_dir)
+        .follow_links(true)
+        .into_iter()
+        .filter_entry(|e| {
+            Utf8Path::from_path(e.path()).is_some_and(|p| {
+                (p.is_file() || !should_ignore_dir(p))
+                    && !is_excluded(&DiskFs, rules, source_dir, p)
+            })
+        })
+    {
+        let std_path = match entry {
+            Ok(entry) => entry.into_path(),
+            Err(err) => {
+                color_print::ceprintln!(
+                    "<y>Warning: failed to read path while scanning typ assets: {}</>",
+                    err
+                );
+                continue;
+            }
+        };
+        let path = match Utf8PathBuf::from_path_buf(std_path) {
+            Ok(path) => path,
+            Err(non_utf8) => {
+                color_print::ceprintln!(
+                    "<y>Warning: skipping non-UTF-8 path `{}`.</>",
+                    non_utf8.display()
+                );
+                continue;
+            }
+        };
+        if !path.is_file() || path.extension() != Some("typ") {
+            continue;
+        }
+        let relative = match path.strip_prefix(source_dir) {
+            Ok(relative) => relative,
+            Err(_) => continue,
+        };
+        compile_typst_svg(&mount.virtual_path(relative));
+    }
+}
+
+fn compile_typst_svg(relative: &Utf8Path) {
     let svg_url = relative.with_extension("svg");
     let svg_path = environment::output_path(&svg_url);
     if let Err(err) = crate::typst_cli::write_svg(relative, &svg_path) {
-        let full_path = trees_dir.join(relative);
+        let full_path = environment::input_path(relative);
         color_print::ceprintln!("<r>{:?} at {}</>", err, full_path);
     }
 }
@@ -233,6 +352,108 @@ mod tests {
         assert!(workspace.slug_exts.is_empty());
     }
 
+    #[test]
+    fn test_all_trees_source_skips_excluded_paths() {
+        let trees = crate::test_io::case_dir("exclude-trees");
+        for file in [
+            "index.md",
+            "notes/a.md",
+            "notes/a.draft.md",
+            "archive/old.md",
+            "vendor/pkg/lib.typst",
+            "vendor/pkg/keep.typst",
+        ] {
+            let path = trees.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+
+        let rules =
+            ExcludeRules::new(["*.draft.md", "/archive/", "vendor/*/*.typst", "!keep.typst"])
+                .unwrap();
+        let workspace = all_trees_source_inner(trees.as_path(), &rules).unwrap();
+        let mut slugs: Vec<String> = workspace
+            .slug_exts
+            .keys()
+            .map(|slug| slug.to_string())
+            .collect();
+        slugs.sort();
+        assert_eq!(slugs, vec!["index", "notes/a", "vendor/pkg/keep"]);
+
+        let _ = std::fs::remove_dir_all(trees);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_all_trees_source_follows_links_but_rejects_loops() {
+        let trees = crate::test_io::case_dir("symlink-trees");
+        for file in ["index.md", "notes/a.md", "shared/b.md"] {
+            let path = trees.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        std::os::unix::fs::symlink("../shared", trees.join("notes/shared")).unwrap();
+
+        let rules = ExcludeRules::default();
+        let workspace = all_trees_source_inner(trees.as_path(), &rules).unwrap();
+        let mut slugs: Vec<String> = workspace
+            .slug_exts
+            .keys()
+            .map(|slug| slug.to_string())
+            .collect();
+        slugs.sort();
+        assert_eq!(
+            slugs,
+            vec!["index", "notes/a", "notes/shared/b", "shared/b"]
+        );
+
+        std::os::unix::fs::symlink("..", trees.join("notes/loop")).unwrap();
+        let err = all_trees_source_inner(trees.as_path(), &rules).unwrap_err();
+        assert!(err.to_string().contains("links back to its ancestor"));
+
+        let _ = std::fs::remove_dir_all(trees);
+    }
+
+    #[test]
+    fn test_all_mounts_source_prefixes_slugs_and_reports_shadowing() {
+        let root = crate::test_io::case_dir("mount-trees");
+        let trees = root.join("trees");
+        let vendor = root.join("vendor/foundations");
+        for path in [trees.join("index.md"), vendor.join("sets/empty.md")] {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        let mounts = vec![
+            TreeMount::main(trees.clone()),
+            TreeMount {
+                dir: vendor.clone(),
+                prefix: "found".to_string(),
+                edit: None,
+            },
+        ];
+
+        let workspace = all_mounts_source(&mounts).unwrap();
+        let mut slugs: Vec<String> = workspace
+            .slug_exts
+            .keys()
+            .map(|slug| slug.to_string())
+            .collect();
+        slugs.sort();
+        assert_eq!(slugs, vec!["found/sets/empty", "index"]);
+
+        std::fs::create_dir_all(trees.join("found")).unwrap();
+        std::fs::write(trees.join("found/local.md"), "").unwrap();
+        let err = all_mounts_source(&mounts).unwrap_err().to_string();
+        assert!(err.contains("is shadowed by the mount"), "{err}");
+
+        let mut duplicated = mounts.clone();
+        duplicated.push(mounts[1].clone());
+        let err = validate_mounts(&duplicated).unwrap_err().to_string();
+        assert!(err.contains("share the prefix `found`"), "{err}");
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
     #[test]
     fn test_sync_typst_svg_assets_ignores_missing_tree_root() {
         let missing = crate::test_io::case_dir("missing-typ-assets");
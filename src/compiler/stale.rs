@@ -3,15 +3,14 @@
 // Authors: Kokic (@kokic), Alias Qli (@AliasQli), Spore (@s-cerevisiae)
 
 use std::collections::HashSet;
-use std::{fs::File, io::BufReader};
 
 use camino::{Utf8Path, Utf8PathBuf};
 use eyre::{eyre, WrapErr};
-use walkdir::WalkDir;
 
 use crate::{
     environment, path_utils,
     slug::{Ext, Slug},
+    vfs,
 };
 
 use super::Workspace;
@@ -30,7 +29,7 @@ pub(super) fn source_from_entry_relative_path(
     Some((source_relative_path, slug, ext))
 }
 
-fn same_ext(a: Ext, b: Ext) -> bool {
+pub(super) fn same_ext(a: Ext, b: Ext) -> bool {
     matches!(
         (a, b),
         (Ext::Markdown, Ext::Markdown) | (Ext::Typst, Ext::Typst)
@@ -50,8 +49,21 @@ pub(super) fn hash_cache_path_no_create(
     hash_path
 }
 
+pub(super) fn entry_cache_path_no_create(
+    entry_dir: &Utf8Path,
+    source_relative_path: &Utf8Path,
+) -> Utf8PathBuf {
+    let mut entry_path = entry_dir.join(source_relative_path);
+    let ext = entry_path
+        .extension()
+        .map(|ext| format!("{ext}.entry"))
+        .unwrap_or_else(|| "entry".to_string());
+    entry_path.set_extension(ext);
+    entry_path
+}
+
 pub(super) fn remove_file_if_exists(path: &Utf8Path) -> eyre::Result<bool> {
-    match std::fs::remove_file(path) {
+    match environment::fs().remove_file(path) {
         Ok(()) => Ok(true),
         Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(false),
         Err(err) => Err(err).wrap_err_with(|| eyre!("failed to remove file `{}`", path)),
@@ -64,25 +76,11 @@ pub(super) fn cleanup_stale_slug_artifacts_with_paths(
     hash_dir: &Utf8Path,
 ) -> eyre::Result<HashSet<Slug>> {
     let mut stale_slugs = HashSet::new();
-    if !entry_dir.exists() {
-        return Ok(stale_slugs);
-    }
+    let entry_paths = vfs::walk_files(environment::fs().as_ref(), entry_dir)
+        .wrap_err_with(|| eyre!("failed to read cached entry directory `{}`", entry_dir))?;
 
-    for entry in WalkDir::new(entry_dir).follow_links(true).into_iter() {
-        let std_path = entry
-            .wrap_err_with(|| eyre!("failed to read cached entry directory `{}`", entry_dir))?
-            .into_path();
-        let entry_path = match Utf8PathBuf::from_path_buf(std_path) {
-            Ok(path) => path,
-            Err(non_utf8) => {
-                color_print::ceprintln!(
-                    "<y>Warning: skipping non-UTF-8 cache path `{}`.</>",
-                    non_utf8.display()
-                );
-                continue;
-            }
-        };
-        if !entry_path.is_file() || entry_path.extension() != Some("entry") {
+    for entry_path in entry_paths {
+        if entry_path.extension() != Some("entry") {
             continue;
         }
 
@@ -115,13 +113,12 @@ pub(super) fn cleanup_stale_slug_artifacts_with_paths(
     Ok(stale_slugs)
 }
 
-fn read_cached_slugs(entry_path: &Utf8Path, fallback_slug: Slug) -> Vec<Slug> {
+pub(super) fn read_cached_slugs(entry_path: &Utf8Path, fallback_slug: Slug) -> Vec<Slug> {
     let read_bundle = || -> eyre::Result<Vec<Slug>> {
-        let entry_file = BufReader::new(
-            File::open(entry_path)
-                .wrap_err_with(|| eyre!("failed to open cached entry `{}`", entry_path))?,
-        );
-        let cached: CachedSourceEntry = serde_json::from_reader(entry_file)
+        let entry = environment::fs()
+            .read(entry_path)
+            .wrap_err_with(|| eyre!("failed to open cached entry `{}`", entry_path))?;
+        let cached: CachedSourceEntry = serde_json::from_slice(&entry)
             .wrap_err_with(|| eyre!("failed to deserialize cached entry `{}`", entry_path))?;
         Ok(cached
             .sections
@@ -137,11 +134,10 @@ fn read_cached_slugs(entry_path: &Utf8Path, fallback_slug: Slug) -> Vec<Slug> {
     }
 
     let read_legacy = || -> eyre::Result<Vec<Slug>> {
-        let entry_file = BufReader::new(
-            File::open(entry_path)
-                .wrap_err_with(|| eyre!("failed to reopen cached entry `{}`", entry_path))?,
-        );
-        let section: UnresolvedSection = serde_json::from_reader(entry_file)
+        let entry = environment::fs()
+            .read(entry_path)
+            .wrap_err_with(|| eyre!("failed to reopen cached entry `{}`", entry_path))?;
+        let section: UnresolvedSection = serde_json::from_slice(&entry)
             .wrap_err_with(|| eyre!("failed to deserialize legacy entry `{}`", entry_path))?;
         let slug = section.slug().unwrap_or(fallback_slug);
         Ok(vec![slug])
@@ -3,7 +3,10 @@
 // Authors: Kokic (@kokic), Spore (@s-cerevisiae)
 
 use eyre::eyre;
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    sync::OnceLock,
+};
 
 use crate::{
     entry::{
@@ -31,6 +34,8 @@ pub struct CompileState {
     callback: Callback,
     visiting: HashSet<Slug>,
     compile_stack: Vec<Slug>,
+    /// Children in the parent tree, built on first use after compilation.
+    children: OnceLock<HashMap<Slug, Vec<Slug>>>,
 }
 
 type UnresolvedSections = HashMap<Slug, UnresolvedSection>;
@@ -79,6 +84,7 @@ impl CompileState {
             callback: Callback::new(),
             visiting: HashSet::new(),
             compile_stack: Vec::new(),
+            children: OnceLock::new(),
         }
     }
 
@@ -132,6 +138,10 @@ impl CompileState {
             HTMLContent::Plain(html) => {
                 children.push(SectionContent::Plain(html.to_string()));
             }
+            // Only reached through metadata, see `metadata_to_section`.
+            HTMLContent::Structured(value) => {
+                children.push(SectionContent::Plain(value.to_html()));
+            }
             HTMLContent::Lazy(lazy_contents) => {
                 let mut callback: Callback = Callback::new();
 
@@ -289,6 +299,48 @@ impl CompileState {
         &self.callback
     }
 
+    /// The parent shown in the page header: `index` unless the section is
+    /// embedded or has its parent set elsewhere.
+    pub fn parent_of(&self, slug: Slug) -> Option<Slug> {
+        let index = Slug::new("index");
+        let parent = self
+            .callback
+            .0
+            .get(&slug)
+            .map_or(index, |callback| callback.parent);
+        (slug != index && parent != slug && self.compiled.contains_key(&parent)).then_some(parent)
+    }
+
+    /// Sections whose parent is `slug`, sorted by slug.
+    pub fn children_of(&self, slug: Slug) -> &[Slug] {
+        let children = self.children.get_or_init(|| {
+            let mut children: HashMap<Slug, Vec<Slug>> = HashMap::new();
+            for &child in self.compiled.keys() {
+                if let Some(parent) = self.parent_of(child) {
+                    children.entry(parent).or_default().push(child);
+                }
+            }
+            children.values_mut().for_each(|slugs| slugs.sort());
+            children
+        });
+        children.get(&slug).map_or(&[], Vec::as_slice)
+    }
+
+    /// Sections below `slug` in the parent tree, with their depth below it.
+    pub fn descendants(&self, slug: Slug) -> HashMap<Slug, usize> {
+        let mut found = HashMap::new();
+        let mut queue = VecDeque::from([(slug, 0)]);
+        while let Some((current, depth)) = queue.pop_front() {
+            for &child in self.children_of(current) {
+                if child != slug && !found.contains_key(&child) {
+                    found.insert(child, depth + 1);
+                    queue.push_back((child, depth + 1));
+                }
+            }
+        }
+        found
+    }
+
     fn normalize_internal_anonymous_graph(&mut self) {
         let internal_slugs = self.collect_internal_anonymous_slugs();
         if internal_slugs.is_empty() {
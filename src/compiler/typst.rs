@@ -13,8 +13,8 @@ use super::section::{HTMLContent, HTMLContentBuilder, LazyContent};
 use super::UnresolvedSection;
 use crate::{
     entry::{
-        HTMLMetaData, KEY_EXT, KEY_INTERNAL_ANON_SUBTREE, KEY_SLUG, KEY_SOURCE_SLUG, KEY_TAXON,
-        KEY_TITLE,
+        HTMLMetaData, MetaValue, KEY_EXT, KEY_INTERNAL_ANON_SUBTREE, KEY_SLUG, KEY_SOURCE_SLUG,
+        KEY_TAXON, KEY_TITLE,
     },
     ordered_map::OrderedMap,
     process::metadata,
@@ -71,7 +71,11 @@ fn parse_typst_html(
         match span.kind {
             HTMLTagKind::Meta => {
                 let key = attr("key")?.as_ref();
-                let mut val = if let Some(value) = span.attrs.get("value") {
+                let mut val = if let Some(json) = span.attrs.get("json") {
+                    let value: serde_json::Value = serde_json::from_str(json)
+                        .wrap_err_with(|| eyre!("invalid metadata `{key}` in `{current_slug}`"))?;
+                    HTMLContent::Structured(MetaValue::from(value))
+                } else if let Some(value) = span.attrs.get("value") {
                     HTMLContent::Plain(value.to_string())
                 } else {
                     parse_typst_html(
@@ -284,13 +288,15 @@ fn parse_typst_sections_from_html(
     Ok(sections)
 }
 
+/// Compile the Typst source at `relative_path` under `root_dir` into the sections of `slug`.
 pub fn parse_typst_sections<P: AsRef<Utf8Path>>(
     slug: Slug,
     root_dir: P,
+    relative_path: &Utf8Path,
 ) -> eyre::Result<Vec<(Slug, UnresolvedSection)>> {
     let typst_root_dir = root_dir.as_ref();
-    let relative_path = format!("{}.typst", slug);
-    let html_str = typst_cli::file_to_html(&relative_path, typst_root_dir.as_ref())
+    let relative_path = relative_path.as_str();
+    let html_str = typst_cli::file_to_html(relative_path, typst_root_dir.as_ref())
         .wrap_err_with(|| eyre!("failed to compile typst file `{relative_path}` to html"))?;
 
     parse_typst_sections_from_html(slug, &html_str)
@@ -390,6 +396,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_typst_sections_reads_structured_metadata() {
+        let html = r#"
+<kodama-meta key="tags" json="[&quot;a&quot;,&quot;b&quot;]"></kodama-meta>
+<kodama-meta key="venue" json="{&quot;name&quot;:&quot;X&quot;,&quot;year&quot;:&quot;2025&quot;}"></kodama-meta>
+<p>root</p>
+"#;
+        let sections = parse_typst_sections_from_html(Slug::new("index"), html).unwrap();
+        let root = find_section(&sections, Slug::new("index"));
+        let tags = serde_json::to_string(root.metadata.0.get("tags").unwrap()).unwrap();
+        assert_eq!(tags, r#"{"Structured":["a","b"]}"#);
+        let venue = serde_json::to_string(root.metadata.0.get("venue").unwrap()).unwrap();
+        assert_eq!(venue, r#"{"Structured":{"name":"X","year":"2025"}}"#);
+    }
+
     #[test]
     fn test_parse_typst_sections_extracts_anonymous_subtree() {
         let html = r#"
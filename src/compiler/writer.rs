@@ -16,6 +16,7 @@ use crate::{
 
 use super::{
     callback::CallbackValue,
+    local_graph,
     section::{Section, SectionContent},
     state::CompileState,
     taxon::Taxon,
@@ -30,9 +31,9 @@ impl Writer {
         let filepath = crate::environment::output_path(&relative_path);
 
         match verify_update_hash(&relative_path, &html) {
-            Ok(true) => match std::fs::write(&filepath, html) {
+            Ok(true) => match crate::environment::fs().write(&filepath, html.as_bytes()) {
                 Ok(()) => {
-                    if *crate::cli::build::verbose() {
+                    if crate::cli::build::verbose() {
                         color_print::ceprintln!("<g>[build]</> {:?} {}", page_title, filepath);
                     }
                 }
@@ -91,6 +92,10 @@ impl Writer {
             .metadata
             .footer_sort_by()
             .unwrap_or_else(environment::footer_sort_by);
+        let local_graph_html = match section.metadata.local_graph_enabled()? {
+            true => Writer::local_graph(state, slug),
+            false => String::default(),
+        };
         let footer_html = Writer::footer(
             section.metadata.footer_mode()?,
             section.metadata.references_enabled()?,
@@ -98,6 +103,7 @@ impl Writer {
             state,
             &section.references,
             callback,
+            &local_graph_html,
         )?;
         let page_title = section.metadata.page_title().map_or("", |s| s.as_str());
 
@@ -145,6 +151,7 @@ impl Writer {
         state: &CompileState,
         references: &HashSet<Slug>,
         callback: Option<&CallbackValue>,
+        local_graph_html: &str,
     ) -> eyre::Result<String> {
         let mut references: Vec<Slug> = references.iter().copied().collect();
         Writer::sort_footer_slugs(&mut references, state, footer_sort_by);
@@ -196,10 +203,24 @@ impl Writer {
         } else {
             String::default()
         };
-        Ok(html_flake::html_footer(&references_html, &backlinks_html))
+        Ok(html_flake::html_footer(
+            &references_html,
+            &backlinks_html,
+            local_graph_html,
+        ))
+    }
+
+    fn local_graph(state: &CompileState, slug: Slug) -> String {
+        local_graph::local_graph(state, slug, environment::local_graph_depth()).map_or_else(
+            String::default,
+            |graph| {
+                let text = environment::get_footer_local_graph_text();
+                html_flake::html_local_graph(&text, &graph.to_json())
+            },
+        )
     }
 
-    fn sort_footer_slugs(slugs: &mut Vec<Slug>, state: &CompileState, footer_sort_by: &str) {
+    fn sort_footer_slugs(slugs: &mut [Slug], state: &CompileState, footer_sort_by: &str) {
         let sort_key = footer_sort_by.trim();
         slugs.sort_by(|left, right| {
             let left_section = state.compiled().get(left);
@@ -325,6 +346,7 @@ impl Writer {
                 state,
                 &section.references,
                 state.callback().0.get(&slug),
+                "",
             )?;
             contents += &backlinks_html;
         }
@@ -401,8 +423,8 @@ mod tests {
             state::compile_all,
         },
         entry::{
-            HTMLMetaData, KEY_EXT, KEY_INTERNAL_ANON_SUBTREE, KEY_PAGE_TITLE, KEY_REFERENCES,
-            KEY_SLUG, KEY_TITLE,
+            HTMLMetaData, KEY_EXT, KEY_INTERNAL_ANON_SUBTREE, KEY_LOCAL_GRAPH, KEY_PAGE_TITLE,
+            KEY_REFERENCES, KEY_SLUG, KEY_TITLE,
         },
         ordered_map::OrderedMap,
     };
@@ -486,6 +508,44 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_html_doc_local_graph_follows_page_metadata() {
+        with_test_env(|| {
+            let mut shallows = HashMap::new();
+            shallows.insert(
+                Slug::new("index"),
+                shallow_section_with_content(
+                    "index",
+                    "Root",
+                    HTMLContent::Lazy(vec![LazyContent::Embed(EmbedContent {
+                        url: "/a".to_string(),
+                        title: None,
+                        option: SectionOption::default(),
+                    })]),
+                ),
+            );
+            let mut section = shallow_section("a", "A</script>");
+            section.metadata.0.insert(
+                KEY_LOCAL_GRAPH.to_string(),
+                HTMLContent::Plain("true".to_string()),
+            );
+            shallows.insert(Slug::new("a"), section);
+
+            let state = compile_all(&shallows).unwrap();
+            let html_of = |slug| {
+                let section = state.compiled().get(&Slug::new(slug)).unwrap();
+                Writer::html_doc(section, &state).unwrap().0
+            };
+
+            let html = html_of("a");
+            assert!(html.contains(r#"id="local-graph""#));
+            assert!(html.contains(r#"<script type="application/json" class="local-graph-data">"#));
+            assert!(html.contains(r#""slug":"index""#));
+            assert!(html.contains(r#""title":"A<\/script>""#));
+            assert!(!html_of("index").contains(r#"id="local-graph""#));
+        });
+    }
+
     #[test]
     fn test_html_doc_toc_uses_hash_link_for_internal_anonymous_subtree() {
         with_test_env(|| {
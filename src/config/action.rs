@@ -0,0 +1,36 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_ACTION_EXTENSION: &str = "svg";
+pub const DEFAULT_ACTION_TIMEOUT: u64 = 30;
+
+/// A user-defined link action declared as `[actions.<name>]`.
+///
+/// A link `[caption](./graph.dot#:<name>)` pipes the file through `command`
+/// and shows the output as a figure.
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct Action {
+    /// Program and arguments, run from the project root with the file on stdin.
+    pub command: Vec<String>,
+    /// Extension of the generated file.
+    #[serde(default = "default_extension")]
+    pub extension: String,
+    /// Show the output inline like `#:span` instead of as a block figure.
+    #[serde(default)]
+    pub inline: bool,
+    /// Seconds to wait before the command is killed. `0` disables the limit.
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+}
+
+fn default_extension() -> String {
+    DEFAULT_ACTION_EXTENSION.to_string()
+}
+
+fn default_timeout() -> u64 {
+    DEFAULT_ACTION_TIMEOUT
+}
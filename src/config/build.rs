@@ -20,6 +20,18 @@ pub struct Build {
     pub asref: bool,
     pub output: String,
     pub edit: Option<String>,
+    /// Highlight code blocks and included files at build time.
+    pub highlight: bool,
+    /// Number the lines of code blocks and included files.
+    pub line_numbers: bool,
+    /// How `$...$` and `$$...$$` in Markdown are rendered.
+    pub markdown_math: MarkdownMath,
+    /// Where the computed `created` and `modified` metadata come from.
+    pub timestamps: Timestamps,
+    /// Show an interactive graph of each page's neighborhood in its footer.
+    pub local_graph: bool,
+    /// How many hops from the page the local graph reaches.
+    pub local_graph_depth: usize,
 }
 
 impl Default for Build {
@@ -36,6 +48,12 @@ impl Default for Build {
             asref: false,
             output: "./publish".to_string(),
             edit: None,
+            highlight: true,
+            line_numbers: false,
+            markdown_math: MarkdownMath::default(),
+            timestamps: Timestamps::default(),
+            local_graph: false,
+            local_graph_depth: 1,
         }
     }
 }
@@ -73,3 +91,31 @@ impl std::fmt::Display for FooterMode {
         }
     }
 }
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum MarkdownMath {
+    /// Left as `$...$` text for KaTeX to render in the browser.
+    #[default]
+    #[serde(rename = "katex")]
+    Katex,
+
+    /// Compiled to SVG by Typst at build time.
+    #[serde(rename = "typst")]
+    Typst,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Timestamps {
+    /// Commit dates from `git log` for tracked files, file times otherwise.
+    #[default]
+    #[serde(rename = "auto")]
+    Auto,
+
+    /// File creation and modification times only.
+    #[serde(rename = "mtime")]
+    Mtime,
+
+    /// No `created` or `modified` metadata.
+    #[serde(rename = "none")]
+    None,
+}
@@ -8,6 +8,10 @@ pub const DEFAULT_SOURCE_DIR: &str = "trees";
 pub const DEFAULT_ASSETS_DIR: &str = "assets";
 pub const DEFAULT_BASE_URL: &str = "/";
 
+/// Content version of sources written by this release. Bump it together with
+/// a new entry in `cli::upgrade::CONTENT_MIGRATIONS`.
+pub const CONTENT_VERSION: u32 = 0;
+
 #[derive(Deserialize, Debug, Serialize)]
 #[serde(default, rename_all = "kebab-case")]
 pub struct Kodama {
@@ -16,6 +20,14 @@ pub struct Kodama {
     pub base_url: String,
     pub theme_lock: bool,
     pub themes: Vec<String>,
+    /// Gitignore-style patterns, relative to `trees`, for paths that are not sources.
+    pub exclude: Vec<String>,
+    /// Additional source directories mounted under a slug prefix.
+    pub mounts: Vec<Mount>,
+    /// Last content migration of `kodama upgrade` applied to the sources.
+    /// Configs without the key predate content migrations.
+    #[serde(default)]
+    pub content_version: u32,
 }
 
 impl Default for Kodama {
@@ -26,6 +38,22 @@ impl Default for Kodama {
             base_url: DEFAULT_BASE_URL.to_string(),
             theme_lock: false,
             themes: vec![],
+            exclude: vec![],
+            mounts: vec![],
+            content_version: CONTENT_VERSION,
         }
     }
 }
+
+/// A source directory whose sections are published under `prefix`.
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct Mount {
+    /// Directory relative to the project root.
+    pub path: String,
+    /// Slug prefix, e.g. `found` for `found/algebra/group`.
+    pub prefix: String,
+    /// Deploy edit URL prefix for this directory, in place of `[build].edit`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edit: Option<String>,
+}
@@ -2,21 +2,32 @@
 // Released under the GPL-3.0 license as described in the file LICENSE.
 // Authors: Kokic (@kokic), Spore (@s-cerevisiae)
 
+pub mod action;
 pub mod build;
 pub mod kodama;
+pub mod overrides;
+pub mod plugin;
 pub mod publish;
+pub mod schema;
 pub mod serve;
 pub mod text;
 pub mod toc;
+pub mod validate;
 
+use action::Action;
 use build::Build;
 use camino::Utf8PathBuf;
+use indexmap::IndexMap;
 use kodama::Kodama;
+use overrides::ConfigOverrides;
+use plugin::Plugin;
 use publish::Publish;
+use schema::Schema;
 use serde::{Deserialize, Serialize};
 use serve::Serve;
 use text::Text;
 use toc::Toc;
+use validate::ConfigSeverity;
 
 pub const DEFAULT_CONFIG_PATH: &str = "./Kodama.toml";
 
@@ -39,6 +50,23 @@ pub struct Config {
 
     #[serde(default)]
     pub publish: Publish,
+
+    /// User-defined link actions, written as `[actions.<name>]` tables.
+    #[serde(default)]
+    pub actions: IndexMap<String, Action>,
+
+    /// External preprocessors, written as `[[plugin]]` tables.
+    #[serde(default)]
+    pub plugin: Vec<Plugin>,
+
+    /// Metadata rules checked by `kodama check`.
+    #[serde(default)]
+    pub schema: Schema,
+
+    /// Partial configs merged over this one by `--profile <name>`, written as
+    /// `[profile.<name>]` tables.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub profile: IndexMap<String, toml::Table>,
 }
 
 /// Try to find toml file in the current directory or the parent directory.
@@ -63,10 +91,87 @@ pub fn find_config(mut toml_file: Utf8PathBuf) -> eyre::Result<Utf8PathBuf> {
     Ok(toml_file)
 }
 
+/// Parse a config source. Invalid values found by [`validate::validate_config`]
+/// are errors; unknown keys are left to the caller to report.
 pub fn parse_config(config: &str) -> eyre::Result<Config> {
-    let config: Config =
+    let parsed: Config =
+        toml::from_str(config).map_err(|e| eyre::eyre!("failed to parse config file: {}", e))?;
+    reject_invalid_values(config)?;
+    Ok(parsed)
+}
+
+fn reject_invalid_values(config: &str) -> eyre::Result<()> {
+    let errors: Vec<_> = validate::validate_config(config)
+        .into_iter()
+        .filter(|d| d.severity == ConfigSeverity::Error)
+        .map(|d| format!("line {}: {}", d.line, d.message))
+        .collect();
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(eyre::eyre!(
+            "failed to parse config file: {}",
+            errors.join("\n")
+        )),
+    }
+}
+
+/// Parse `config` with the profile and key overrides merged over it.
+pub fn parse_config_with(config: &str, overrides: &ConfigOverrides) -> eyre::Result<Config> {
+    if overrides.is_empty() {
+        return parse_config(config);
+    }
+    let table = merged_table(config, overrides)?;
+    reject_invalid_values(config)?;
+    let errors: Vec<_> = merged_diagnostics(config, &table)?
+        .into_iter()
+        .filter(|d| d.severity == ConfigSeverity::Error)
+        .map(|d| d.message)
+        .collect();
+    if !errors.is_empty() {
+        return Err(eyre::eyre!(
+            "failed to apply config overrides: {}",
+            errors.join("\n")
+        ));
+    }
+    toml::Value::Table(table)
+        .try_into()
+        .map_err(|e| eyre::eyre!("failed to apply config overrides: {}", e))
+}
+
+/// Diagnostics for the values of `config` with `overrides` merged over it,
+/// leaving out those the file has on its own. They have no line.
+pub fn override_diagnostics(
+    config: &str,
+    overrides: &ConfigOverrides,
+) -> eyre::Result<Vec<validate::ConfigDiagnostic>> {
+    if overrides.is_empty() {
+        return Ok(Vec::new());
+    }
+    merged_diagnostics(config, &merged_table(config, overrides)?)
+}
+
+fn merged_table(config: &str, overrides: &ConfigOverrides) -> eyre::Result<toml::Table> {
+    let table: toml::Table =
         toml::from_str(config).map_err(|e| eyre::eyre!("failed to parse config file: {}", e))?;
-    Ok(config)
+    for (key, _) in &overrides.values {
+        validate::validate_key_path(key)
+            .map_err(|message| eyre::eyre!("invalid override `{}`: {}", key, message))?;
+    }
+    overrides.apply(table)
+}
+
+fn merged_diagnostics(
+    config: &str,
+    merged: &toml::Table,
+) -> eyre::Result<Vec<validate::ConfigDiagnostic>> {
+    let merged = toml::to_string(merged)
+        .map_err(|e| eyre::eyre!("failed to serialize merged config: {}", e))?;
+    let own = validate::validate_config(config);
+    Ok(validate::validate_config(&merged)
+        .into_iter()
+        .filter(|d| !own.iter().any(|own| own.message == d.message))
+        .map(|d| validate::ConfigDiagnostic { line: 0, ..d })
+        .collect())
 }
 
 mod test {
@@ -128,4 +233,146 @@ mod test {
         assert_eq!(config.serve.output, serve.output);
         assert!(config.publish.rss);
     }
+
+    #[test]
+    fn test_plugin_tables() {
+        let config = crate::config::parse_config(
+            r#"
+            [[plugin]]
+            name = "glossary"
+            command = ["python3", "plugins/glossary.py"]
+
+            [[plugin]]
+            name = "tables"
+            command = ["./plugins/tables"]
+            timeout = 5
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.plugin.len(), 2);
+        assert_eq!(config.plugin[0].name, "glossary");
+        assert_eq!(config.plugin[0].command, ["python3", "plugins/glossary.py"]);
+        assert_eq!(
+            config.plugin[0].timeout,
+            crate::config::plugin::DEFAULT_PLUGIN_TIMEOUT
+        );
+        assert_eq!(config.plugin[1].timeout, 5);
+    }
+
+    #[test]
+    fn test_action_tables() {
+        let config = crate::config::parse_config(
+            r#"
+            [actions.dot]
+            command = ["dot", "-Tsvg"]
+
+            [actions.plot]
+            command = ["gnuplot"]
+            extension = "png"
+            inline = true
+            "#,
+        )
+        .unwrap();
+
+        let dot = &config.actions["dot"];
+        assert_eq!(dot.command, ["dot", "-Tsvg"]);
+        assert_eq!(dot.extension, "svg");
+        assert!(!dot.inline);
+        let plot = &config.actions["plot"];
+        assert_eq!(plot.extension, "png");
+        assert!(plot.inline);
+    }
+
+    #[test]
+    fn test_schema_tables() {
+        use crate::config::schema::FieldType;
+
+        let config = crate::config::parse_config(
+            r#"
+            [schema]
+            allowed-taxa = ["note"]
+
+            [schema.types]
+            date = "date"
+
+            [schema.taxon.reference]
+            required = ["author"]
+            types = { status = { enum = ["draft", "final"] } }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.schema.allowed_taxa, ["note"]);
+        assert_eq!(config.schema.types["date"], FieldType::Date);
+        let reference = &config.schema.taxon["reference"];
+        assert_eq!(reference.required, ["author"]);
+        assert_eq!(reference.optional, None);
+        assert_eq!(
+            reference.types["status"],
+            FieldType::Enum(vec!["draft".to_string(), "final".to_string()])
+        );
+
+        let err = crate::config::parse_config("[schema.types]\ndate = \"day\"").unwrap_err();
+        assert!(err.to_string().contains("unknown metadata type `day`"));
+    }
+
+    #[test]
+    fn test_profile_tables() {
+        use crate::config::overrides::ConfigOverrides;
+
+        let source = r#"
+            [build]
+            inline-css = false
+
+            [profile.production.build]
+            inline-css = true
+            "#;
+        let config = crate::config::parse_config(source).unwrap();
+        assert!(!config.build.inline_css);
+        let serialized = toml::to_string(&config).unwrap();
+        assert!(serialized.contains("[profile.production.build]"));
+
+        let overrides = ConfigOverrides {
+            profile: Some("production".to_string()),
+            values: vec![],
+        };
+        let config = crate::config::parse_config_with(source, &overrides).unwrap();
+        assert!(config.build.inline_css);
+        assert!(config.profile.is_empty());
+    }
+
+    #[test]
+    fn test_override_values_are_validated() {
+        use crate::config::{overrides::ConfigOverrides, validate::ConfigSeverity};
+
+        let source = r#"
+            [serve]
+            edit = "vscode://file/"
+            output = "./.cache/publish"
+            command = ["miniserve", "<ouput>"]
+            "#;
+        // An unknown placeholder only warns, so older configs still build.
+        assert!(crate::config::parse_config(source).is_ok());
+
+        let sort_by = ConfigOverrides {
+            profile: None,
+            values: vec![("build.footer-sort-by".to_string(), "created at".to_string())],
+        };
+        let err = crate::config::parse_config_with(source, &sort_by).unwrap_err();
+        assert!(err.to_string().contains("`build.footer-sort-by` must be"));
+
+        let set = ConfigOverrides {
+            profile: None,
+            values: vec![(
+                "serve.command".to_string(),
+                r#"["miniserve", "<port>", "<output>"]"#.to_string(),
+            )],
+        };
+        let diagnostics = crate::config::override_diagnostics(source, &set).unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, ConfigSeverity::Warning);
+        assert_eq!(diagnostics[0].line, 0);
+        assert!(diagnostics[0].message.contains("`<port>`"));
+    }
 }
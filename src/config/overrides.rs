@@ -0,0 +1,234 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+use eyre::eyre;
+
+/// Environment variable selecting a profile when `--profile` is not given.
+pub const PROFILE_ENV: &str = "KODAMA_PROFILE";
+
+/// Prefix of environment variables overriding config keys, e.g.
+/// `KODAMA_BUILD__PRETTY_URLS=true` for `build.pretty-urls`.
+pub const ENV_PREFIX: &str = "KODAMA_";
+
+/// Layers merged over the config file, in increasing precedence: the
+/// `[profile.<name>]` table, then the `key=value` pairs in order.
+#[derive(Debug, Default, Clone)]
+pub struct ConfigOverrides {
+    pub profile: Option<String>,
+    /// Dotted kebab-case key paths, e.g. `build.pretty-urls`, with TOML
+    /// values. A value that is not valid TOML is taken as a string.
+    pub values: Vec<(String, String)>,
+}
+
+impl ConfigOverrides {
+    /// Overrides from `KODAMA_*` environment variables followed by the
+    /// `--profile` and `--set` arguments, which take precedence.
+    pub fn from_env_and_args(profile: Option<String>, sets: &[String]) -> eyre::Result<Self> {
+        Self::from_vars(std::env::vars(), profile, sets)
+    }
+
+    fn from_vars(
+        vars: impl IntoIterator<Item = (String, String)>,
+        profile: Option<String>,
+        sets: &[String],
+    ) -> eyre::Result<Self> {
+        let mut env_profile = None;
+        let mut values = Vec::new();
+        for (name, value) in vars {
+            if name == PROFILE_ENV {
+                env_profile = Some(value);
+            } else if let Some(key) = env_key(&name) {
+                values.push((key, value));
+            }
+        }
+        values.sort();
+        for set in sets {
+            let (key, value) = set
+                .split_once('=')
+                .ok_or_else(|| eyre!("invalid override `{}`: expected KEY=VALUE", set))?;
+            values.push((key.trim().to_string(), value.to_string()));
+        }
+        Ok(ConfigOverrides {
+            profile: profile.or(env_profile).filter(|name| !name.is_empty()),
+            values,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.profile.is_none() && self.values.is_empty()
+    }
+
+    /// Merge the overrides into the raw config table. The `profile` tables
+    /// are dropped from the result.
+    pub fn apply(&self, mut table: toml::Table) -> eyre::Result<toml::Table> {
+        let profiles = table.remove("profile");
+        if let Some(name) = &self.profile {
+            let profile = profiles
+                .as_ref()
+                .and_then(|profiles| profiles.get(name))
+                .ok_or_else(|| {
+                    let names = profiles
+                        .as_ref()
+                        .and_then(toml::Value::as_table)
+                        .map(|profiles| profiles.keys().cloned().collect::<Vec<_>>())
+                        .unwrap_or_default();
+                    match names.is_empty() {
+                        true => eyre!("unknown profile `{}`: no profiles are defined", name),
+                        false => eyre!(
+                            "unknown profile `{}`, expected one of: {}",
+                            name,
+                            names.join(", ")
+                        ),
+                    }
+                })?;
+            let profile = profile
+                .as_table()
+                .ok_or_else(|| eyre!("profile `{}` must be a table", name))?;
+            merge_tables(&mut table, profile.clone());
+        }
+        for (key, value) in &self.values {
+            set_key(&mut table, key, parse_value(value))?;
+        }
+        Ok(table)
+    }
+}
+
+/// `KODAMA_BUILD__PRETTY_URLS` to `build.pretty-urls`: `__` separates
+/// tables and `_` stands for `-`.
+fn env_key(name: &str) -> Option<String> {
+    let path = name.strip_prefix(ENV_PREFIX)?;
+    if !path.contains("__") {
+        return None;
+    }
+    let segments: Vec<_> = path
+        .split("__")
+        .map(|segment| segment.to_lowercase().replace('_', "-"))
+        .collect();
+    segments
+        .iter()
+        .all(|segment| !segment.is_empty())
+        .then(|| segments.join("."))
+}
+
+fn parse_value(value: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {value}"))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(value.to_string()))
+}
+
+/// Deep-merge `overlay` into `base`: tables merge key by key, any other value
+/// replaces the base value.
+fn merge_tables(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overlay)) => {
+                merge_tables(base, overlay)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+fn set_key(table: &mut toml::Table, key: &str, value: toml::Value) -> eyre::Result<()> {
+    let segments: Vec<_> = key.split('.').collect();
+    if segments.iter().any(|segment| segment.is_empty()) {
+        return Err(eyre!("invalid override key `{}`", key));
+    }
+    let (last, parents) = segments.split_last().expect("split yields a segment");
+    let mut current = table;
+    for (index, segment) in parents.iter().enumerate() {
+        let entry = current
+            .entry(segment.to_string())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        current = entry.as_table_mut().ok_or_else(|| {
+            eyre!(
+                "cannot override `{}`: `{}` is not a table",
+                key,
+                segments[..=index].join(".")
+            )
+        })?;
+    }
+    current.insert(last.to_string(), value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overrides_merge_profile_env_and_set_in_order() {
+        let source = r#"
+            [kodama]
+            base-url = "/"
+
+            [build]
+            inline-css = false
+            edit = "https://example.com/edit/"
+
+            [profile.staging.kodama]
+            base-url = "https://staging.example.com/"
+
+            [profile.staging.build]
+            inline-css = true
+        "#;
+        let vars = [
+            ("KODAMA_PROFILE".to_string(), "staging".to_string()),
+            ("KODAMA_BUILD__PRETTY_URLS".to_string(), "true".to_string()),
+            ("KODAMA_BUILD__INLINE_CSS".to_string(), "false".to_string()),
+            ("KODAMA_HOME".to_string(), "/tmp".to_string()),
+        ];
+        let sets = ["build.inline-css=true".to_string()];
+        let overrides = ConfigOverrides::from_vars(vars, None, &sets).unwrap();
+        assert_eq!(overrides.profile.as_deref(), Some("staging"));
+
+        let table = overrides.apply(toml::from_str(source).unwrap()).unwrap();
+        assert!(!table.contains_key("profile"));
+        let config: crate::config::Config = toml::Value::Table(table).try_into().unwrap();
+        assert_eq!(config.kodama.base_url, "https://staging.example.com/");
+        assert!(config.build.inline_css);
+        assert!(config.build.pretty_urls);
+        assert_eq!(
+            config.build.edit.as_deref(),
+            Some("https://example.com/edit/")
+        );
+
+        let unknown = ConfigOverrides {
+            profile: Some("prod".to_string()),
+            values: vec![],
+        };
+        let err = unknown.apply(toml::from_str(source).unwrap()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown profile `prod`, expected one of: staging"
+        );
+    }
+
+    #[test]
+    fn test_set_values_fall_back_to_strings() {
+        let overrides =
+            ConfigOverrides::from_vars([], None, &["kodama.base-url=https://x.org/".into()])
+                .unwrap();
+        let table = overrides.apply(toml::Table::new()).unwrap();
+        assert_eq!(table["kodama"]["base-url"].as_str(), Some("https://x.org/"));
+
+        let err = ConfigOverrides::from_vars([], None, &["build".into()]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid override `build`: expected KEY=VALUE"
+        );
+
+        let overrides = ConfigOverrides::from_vars([], None, &["kodama.trees.x=1".into()]).unwrap();
+        let mut table = toml::Table::new();
+        set_key(&mut table, "kodama.trees", parse_value("\"trees\"")).unwrap();
+        let err = overrides.apply(table).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot override `kodama.trees.x`: `kodama.trees` is not a table"
+        );
+    }
+}
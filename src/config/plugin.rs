@@ -0,0 +1,27 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_PLUGIN_TIMEOUT: u64 = 30;
+
+/// An external preprocessor declared with `[[plugin]]`.
+///
+/// Plugins run in declaration order. Each one reads the parsed sections as JSON
+/// on stdin and writes the sections to compile as JSON on stdout.
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct Plugin {
+    /// Name used in diagnostics.
+    pub name: String,
+    /// Program and arguments, run from the project root.
+    pub command: Vec<String>,
+    /// Seconds to wait before the plugin is killed. `0` disables the limit.
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+}
+
+fn default_timeout() -> u64 {
+    DEFAULT_PLUGIN_TIMEOUT
+}
@@ -0,0 +1,111 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+use std::fmt;
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+/// Metadata rules checked by `kodama check`, written as `[schema]`.
+#[derive(Deserialize, Debug, Default, Serialize, Clone, PartialEq)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Schema {
+    /// The only taxa sections may use. Empty allows any taxon.
+    pub allowed_taxa: Vec<String>,
+    /// Value types checked in every section.
+    pub types: IndexMap<String, FieldType>,
+    /// Rules for sections of one taxon, written as `[schema.taxon.<name>]`.
+    pub taxon: IndexMap<String, TaxonSchema>,
+}
+
+impl Schema {
+    pub fn is_empty(&self) -> bool {
+        self.allowed_taxa.is_empty() && self.types.is_empty() && self.taxon.is_empty()
+    }
+}
+
+#[derive(Deserialize, Debug, Default, Serialize, Clone, PartialEq)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct TaxonSchema {
+    /// Keys that must be present and non-empty.
+    pub required: Vec<String>,
+    /// When set, custom keys outside `required`, `optional` and `types` are reported.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub optional: Option<Vec<String>>,
+    /// Value types for this taxon, taking precedence over `[schema.types]`.
+    pub types: IndexMap<String, FieldType>,
+}
+
+/// The type of a metadata value: `"text"`, `"bool"`, `"date"`, `"slug"`,
+/// `"list"` or `{ enum = [...] }`.
+///
+/// A list value satisfies a scalar type when every item does.
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(try_from = "RawFieldType", into = "RawFieldType")]
+pub enum FieldType {
+    Text,
+    Bool,
+    Date,
+    /// The slug of an existing section.
+    Slug,
+    List,
+    Enum(Vec<String>),
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldType::Text => write!(f, "text"),
+            FieldType::Bool => write!(f, "a bool"),
+            FieldType::Date => write!(f, "a date"),
+            FieldType::Slug => write!(f, "a section slug"),
+            FieldType::List => write!(f, "a list"),
+            FieldType::Enum(values) => write!(f, "one of `{}`", values.join("`, `")),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum RawFieldType {
+    Name(String),
+    Enum {
+        #[serde(rename = "enum")]
+        values: Vec<String>,
+    },
+}
+
+impl TryFrom<RawFieldType> for FieldType {
+    type Error = String;
+
+    fn try_from(raw: RawFieldType) -> Result<Self, Self::Error> {
+        match raw {
+            RawFieldType::Name(name) => match name.as_str() {
+                "text" => Ok(FieldType::Text),
+                "bool" => Ok(FieldType::Bool),
+                "date" => Ok(FieldType::Date),
+                "slug" => Ok(FieldType::Slug),
+                "list" => Ok(FieldType::List),
+                _ => Err(format!(
+                    "unknown metadata type `{name}`, expected `text`, `bool`, `date`, `slug`, `list` or `{{ enum = [...] }}`"
+                )),
+            },
+            RawFieldType::Enum { values } => Ok(FieldType::Enum(values)),
+        }
+    }
+}
+
+impl From<FieldType> for RawFieldType {
+    fn from(field_type: FieldType) -> Self {
+        let name = match field_type {
+            FieldType::Text => "text",
+            FieldType::Bool => "bool",
+            FieldType::Date => "date",
+            FieldType::Slug => "slug",
+            FieldType::List => "list",
+            FieldType::Enum(values) => return RawFieldType::Enum { values },
+        };
+        RawFieldType::Name(name.to_string())
+    }
+}
@@ -11,6 +11,7 @@ pub struct Text {
     pub toc: String,
     pub references: String,
     pub backlinks: String,
+    pub local_graph: String,
 }
 
 impl Default for Text {
@@ -20,6 +21,7 @@ impl Default for Text {
             toc: "Table of Contents".to_string(),
             references: "References".to_string(),
             backlinks: "Backlinks".to_string(),
+            local_graph: "Local Graph".to_string(),
         }
     }
 }
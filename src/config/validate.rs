@@ -0,0 +1,550 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+//! Checks on the config source that deserialization lets through: keys that
+//! `#[serde(default)]` silently ignores, and values of the right type that
+//! are still wrong.
+
+use toml::{
+    de::{DeTable, DeValue},
+    Spanned,
+};
+
+/// The only placeholder of `serve.command`.
+const OUTPUT_PLACEHOLDER: &str = "<output>";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigSeverity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigDiagnostic {
+    pub severity: ConfigSeverity,
+    /// 1-based line in the config source, or 0 for the config merged with
+    /// its overrides, which has no source.
+    pub line: usize,
+    pub message: String,
+}
+
+impl ConfigDiagnostic {
+    /// Where the diagnostic points, e.g. `Kodama.toml:3`.
+    pub fn location(&self, config_file: &camino::Utf8Path) -> String {
+        match self.line {
+            0 => format!("{} (with overrides)", config_file),
+            line => format!("{}:{}", config_file, line),
+        }
+    }
+}
+
+/// Expected layout of a config value.
+enum Shape {
+    /// A table with a fixed set of keys.
+    Table(&'static [(&'static str, Shape)]),
+    /// A table whose keys are names chosen by the user.
+    Map(&'static Shape),
+    /// An array of tables, such as `[[plugin]]` or `mounts`.
+    Tables(&'static Shape),
+    /// Any other value. Its type is checked by deserialization.
+    Value,
+}
+
+static CONFIG: Shape = Shape::Table(&[
+    (
+        "kodama",
+        Shape::Table(&[
+            ("trees", Shape::Value),
+            ("assets", Shape::Value),
+            ("base-url", Shape::Value),
+            ("theme-lock", Shape::Value),
+            ("themes", Shape::Value),
+            ("exclude", Shape::Value),
+            (
+                "mounts",
+                Shape::Tables(&Shape::Table(&[
+                    ("path", Shape::Value),
+                    ("prefix", Shape::Value),
+                    ("edit", Shape::Value),
+                ])),
+            ),
+            ("content-version", Shape::Value),
+        ]),
+    ),
+    (
+        "toc",
+        Shape::Table(&[
+            ("placement", Shape::Value),
+            ("sticky", Shape::Value),
+            ("mobile-sticky", Shape::Value),
+            ("max-width", Shape::Value),
+        ]),
+    ),
+    (
+        "text",
+        Shape::Table(&[
+            ("edit", Shape::Value),
+            ("toc", Shape::Value),
+            ("references", Shape::Value),
+            ("backlinks", Shape::Value),
+            ("local-graph", Shape::Value),
+        ]),
+    ),
+    (
+        "build",
+        Shape::Table(&[
+            ("typst-root", Shape::Value),
+            ("short-slug", Shape::Value),
+            ("pretty-urls", Shape::Value),
+            ("footer-mode", Shape::Value),
+            ("footer-sort-by", Shape::Value),
+            ("inline-css", Shape::Value),
+            ("inline-script", Shape::Value),
+            ("allow-unsafe-html", Shape::Value),
+            ("asref", Shape::Value),
+            ("output", Shape::Value),
+            ("edit", Shape::Value),
+            ("highlight", Shape::Value),
+            ("line-numbers", Shape::Value),
+            ("markdown-math", Shape::Value),
+            ("timestamps", Shape::Value),
+            ("local-graph", Shape::Value),
+            ("local-graph-depth", Shape::Value),
+        ]),
+    ),
+    (
+        "serve",
+        Shape::Table(&[
+            ("edit", Shape::Value),
+            ("output", Shape::Value),
+            ("command", Shape::Value),
+        ]),
+    ),
+    ("publish", Shape::Table(&[("rss", Shape::Value)])),
+    (
+        "actions",
+        Shape::Map(&Shape::Table(&[
+            ("command", Shape::Value),
+            ("extension", Shape::Value),
+            ("inline", Shape::Value),
+            ("timeout", Shape::Value),
+        ])),
+    ),
+    (
+        "plugin",
+        Shape::Tables(&Shape::Table(&[
+            ("name", Shape::Value),
+            ("command", Shape::Value),
+            ("timeout", Shape::Value),
+        ])),
+    ),
+    (
+        "schema",
+        Shape::Table(&[
+            ("allowed-taxa", Shape::Value),
+            ("types", Shape::Map(&Shape::Value)),
+            (
+                "taxon",
+                Shape::Map(&Shape::Table(&[
+                    ("required", Shape::Value),
+                    ("optional", Shape::Value),
+                    ("types", Shape::Map(&Shape::Value)),
+                ])),
+            ),
+        ]),
+    ),
+    ("profile", Shape::Map(&CONFIG)),
+]);
+
+/// Diagnostics for the config `source`, in source order. A source that is not
+/// valid TOML has none; deserialization reports its syntax errors.
+pub fn validate_config(source: &str) -> Vec<ConfigDiagnostic> {
+    let Ok(table) = DeTable::parse(source) else {
+        return Vec::new();
+    };
+    let mut validator = Validator {
+        source,
+        diagnostics: Vec::new(),
+    };
+    let Shape::Table(fields) = &CONFIG else {
+        unreachable!("the config root is a table");
+    };
+    validator.table(fields, table.get_ref(), &mut Vec::new());
+    validator.diagnostics.sort_by_key(|d| d.line);
+    validator.diagnostics
+}
+
+/// Check that a dotted override key such as `build.pretty-urls` names a
+/// config key, with a suggestion when it does not.
+pub fn validate_key_path(key: &str) -> Result<(), String> {
+    let mut shape = &CONFIG;
+    let mut path: Vec<&str> = Vec::new();
+    for segment in key.split('.') {
+        shape = match shape {
+            Shape::Table(fields) => match fields.iter().find(|(name, _)| *name == segment) {
+                Some((_, shape)) => shape,
+                None => return Err(unknown_key_message(segment, fields, &path)),
+            },
+            Shape::Map(inner) => inner,
+            Shape::Tables(_) | Shape::Value => return Ok(()),
+        };
+        path.push(segment);
+    }
+    Ok(())
+}
+
+struct Validator<'s> {
+    source: &'s str,
+    diagnostics: Vec<ConfigDiagnostic>,
+}
+
+impl Validator<'_> {
+    fn table<'a>(
+        &mut self,
+        fields: &[(&str, Shape)],
+        table: &'a DeTable<'a>,
+        path: &mut Vec<&'a str>,
+    ) {
+        for (key, value) in table {
+            let name = key.get_ref().as_ref();
+            match fields.iter().find(|(field, _)| *field == name) {
+                Some((_, shape)) => {
+                    path.push(name);
+                    self.value(shape, value, path);
+                    path.pop();
+                }
+                None => self.push(
+                    ConfigSeverity::Warning,
+                    key.span().start,
+                    unknown_key_message(name, fields, path),
+                ),
+            }
+        }
+    }
+
+    fn value<'a>(
+        &mut self,
+        shape: &Shape,
+        value: &'a Spanned<DeValue<'a>>,
+        path: &mut Vec<&'a str>,
+    ) {
+        match (shape, value.get_ref()) {
+            (Shape::Table(fields), DeValue::Table(table)) => self.table(fields, table, path),
+            (Shape::Map(inner), DeValue::Table(table)) => {
+                for (key, value) in table {
+                    path.push(key.get_ref().as_ref());
+                    self.value(inner, value, path);
+                    path.pop();
+                }
+            }
+            (Shape::Tables(inner), DeValue::Array(items)) => {
+                for item in items {
+                    self.value(inner, item, path);
+                }
+            }
+            (Shape::Value, _) => self.check_value(path, value),
+            // Type mismatches are reported by deserialization.
+            _ => {}
+        }
+    }
+
+    fn check_value(&mut self, path: &[&str], value: &Spanned<DeValue<'_>>) {
+        // Keys of a profile are checked like the keys they override.
+        let key = match path {
+            ["profile", _, rest @ ..] => rest.join("."),
+            _ => path.join("."),
+        };
+        match (key.as_str(), value.get_ref()) {
+            ("build.footer-sort-by", DeValue::String(sort_by))
+                if sort_by.is_empty() || sort_by.contains(char::is_whitespace) =>
+            {
+                self.push(
+                    ConfigSeverity::Error,
+                    value.span().start,
+                    format!(
+                        "`build.footer-sort-by` must be a metadata key such as `slug`, `date` or `title`, found `{}`",
+                        sort_by
+                    ),
+                );
+            }
+            ("serve.command", DeValue::Array(args)) => self.check_serve_command(value, args),
+            _ => {}
+        }
+    }
+
+    fn check_serve_command(&mut self, value: &Spanned<DeValue<'_>>, args: &toml::de::DeArray<'_>) {
+        let mut has_output = false;
+        let mut is_empty = true;
+        for arg in args {
+            is_empty = false;
+            let DeValue::String(text) = arg.get_ref() else {
+                continue;
+            };
+            if text == OUTPUT_PLACEHOLDER {
+                has_output = true;
+            } else if text.contains(OUTPUT_PLACEHOLDER) {
+                has_output = true;
+                self.push(
+                    ConfigSeverity::Warning,
+                    arg.span().start,
+                    format!(
+                        "`{}` in `serve.command` is only replaced when it is a whole argument, not in `{}`",
+                        OUTPUT_PLACEHOLDER, text
+                    ),
+                );
+            } else if let Some(placeholder) = find_placeholder(text) {
+                self.push(
+                    ConfigSeverity::Warning,
+                    arg.span().start,
+                    format!(
+                        "unknown placeholder `{}` in `serve.command`, the only placeholder is `{}`",
+                        placeholder, OUTPUT_PLACEHOLDER
+                    ),
+                );
+            }
+        }
+        if is_empty {
+            self.push(
+                ConfigSeverity::Error,
+                value.span().start,
+                "`serve.command` cannot be empty".to_string(),
+            );
+        } else if !has_output {
+            self.push(
+                ConfigSeverity::Warning,
+                value.span().start,
+                format!(
+                    "`serve.command` has no `{}` argument, so the server is not told which directory to serve",
+                    OUTPUT_PLACEHOLDER
+                ),
+            );
+        }
+    }
+
+    fn push(&mut self, severity: ConfigSeverity, offset: usize, message: String) {
+        let line = self.source[..offset.min(self.source.len())]
+            .matches('\n')
+            .count()
+            + 1;
+        self.diagnostics.push(ConfigDiagnostic {
+            severity,
+            line,
+            message,
+        });
+    }
+}
+
+/// The first `<name>` in `text`, where `name` is made of letters, digits,
+/// `-` and `_`.
+fn find_placeholder(text: &str) -> Option<&str> {
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        let candidate = &rest[start..];
+        if let Some(end) = candidate[1..].find('>') {
+            let name = &candidate[1..end + 1];
+            if !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            {
+                return Some(&candidate[..end + 2]);
+            }
+        }
+        rest = &candidate[1..];
+    }
+    None
+}
+
+fn unknown_key_message(name: &str, fields: &[(&str, Shape)], path: &[&str]) -> String {
+    let suggestion = closest_key(name, fields.iter().map(|(field, _)| *field))
+        .map(|field| format!(", did you mean `{}`?", field))
+        .unwrap_or_default();
+    match path.is_empty() {
+        true => format!("unknown section `{}`{}", name, suggestion),
+        false => format!(
+            "unknown key `{}` in `{}`{}",
+            name,
+            path.join("."),
+            suggestion
+        ),
+    }
+}
+
+/// The candidate closest to `name` by edit distance, if it is close enough to
+/// be a likely typo.
+fn closest_key<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let max_distance = (name.chars().count() / 3).max(1);
+    candidates
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, left) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, right) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(left != *right);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(source: &str) -> Vec<(ConfigSeverity, usize, String)> {
+        validate_config(source)
+            .into_iter()
+            .map(|d| (d.severity, d.line, d.message))
+            .collect()
+    }
+
+    #[test]
+    fn test_validate_config_reports_unknown_keys_with_suggestions() {
+        let source = r#"
+[build]
+pretty-url = true
+
+[publsh]
+rss = true
+
+[[plugin]]
+name = "glossary"
+comand = ["python3"]
+
+[actions.dot]
+command = ["dot"]
+
+[schema.taxon.reference]
+required = ["author"]
+
+[profile.staging.build]
+inline-ccs = true
+"#;
+        assert_eq!(
+            messages(source),
+            [
+                (
+                    ConfigSeverity::Warning,
+                    3,
+                    "unknown key `pretty-url` in `build`, did you mean `pretty-urls`?".to_string()
+                ),
+                (
+                    ConfigSeverity::Warning,
+                    5,
+                    "unknown section `publsh`, did you mean `publish`?".to_string()
+                ),
+                (
+                    ConfigSeverity::Warning,
+                    10,
+                    "unknown key `comand` in `plugin`, did you mean `command`?".to_string()
+                ),
+                (
+                    ConfigSeverity::Warning,
+                    19,
+                    "unknown key `inline-ccs` in `profile.staging.build`, did you mean `inline-css`?"
+                        .to_string()
+                ),
+            ]
+        );
+        assert!(messages("[build]\nzzz = 1\n")[0].2.ends_with("in `build`"));
+    }
+
+    #[test]
+    fn test_validate_config_checks_values() {
+        let source = r#"
+[build]
+footer-sort-by = "created at"
+
+[serve]
+command = ["miniserve", "<ouput>", "--root=<output>"]
+"#;
+        let diagnostics = messages(source);
+        assert_eq!(diagnostics.len(), 3);
+        assert_eq!(diagnostics[0].0, ConfigSeverity::Error);
+        assert_eq!(diagnostics[0].1, 3);
+        assert_eq!(diagnostics[1].0, ConfigSeverity::Warning);
+        assert_eq!(
+            diagnostics[1].2,
+            "unknown placeholder `<ouput>` in `serve.command`, the only placeholder is `<output>`"
+        );
+        assert_eq!(diagnostics[2].0, ConfigSeverity::Warning);
+        assert_eq!(diagnostics[2].1, 6);
+
+        let empty = messages("[serve]\ncommand = []\n");
+        assert_eq!(
+            empty,
+            [(
+                ConfigSeverity::Error,
+                2,
+                "`serve.command` cannot be empty".to_string()
+            )]
+        );
+        assert!(messages("").is_empty());
+    }
+
+    #[test]
+    fn test_validate_key_path() {
+        assert!(validate_key_path("build.pretty-urls").is_ok());
+        assert!(validate_key_path("actions.dot.inline").is_ok());
+        assert!(validate_key_path("schema.types.status.enum").is_ok());
+        assert_eq!(
+            validate_key_path("build.pretty-url").unwrap_err(),
+            "unknown key `pretty-url` in `build`, did you mean `pretty-urls`?"
+        );
+    }
+
+    #[test]
+    fn test_config_shape_covers_every_serialized_key() {
+        use crate::config::{
+            action::Action,
+            kodama::Mount,
+            plugin::Plugin,
+            schema::{FieldType, TaxonSchema},
+            Config,
+        };
+
+        let mut config = Config::default();
+        config.build.edit = Some("https://example.com/edit/".to_string());
+        config.kodama.mounts.push(Mount {
+            path: "vendor".to_string(),
+            prefix: "vendor".to_string(),
+            edit: Some("https://example.com/".to_string()),
+        });
+        config.actions.insert(
+            "dot".to_string(),
+            toml::from_str::<Action>("command = [\"dot\"]").unwrap(),
+        );
+        config
+            .plugin
+            .push(toml::from_str::<Plugin>("name = \"p\"\ncommand = [\"p\"]").unwrap());
+        config
+            .schema
+            .types
+            .insert("date".to_string(), FieldType::Date);
+        config.schema.taxon.insert(
+            "reference".to_string(),
+            TaxonSchema {
+                optional: Some(vec![]),
+                ..TaxonSchema::default()
+            },
+        );
+        let mut profile = toml::Table::new();
+        profile.insert(
+            "publish".to_string(),
+            toml::Value::Table(toml::Table::new()),
+        );
+        config.profile.insert("staging".to_string(), profile);
+
+        let source = toml::to_string(&config).unwrap();
+        assert_eq!(messages(&source), []);
+    }
+}
@@ -18,6 +18,85 @@ pub struct HTMLMetaData(pub OrderedMap<String, HTMLContent>);
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EntryMetaData(pub OrderedMap<String, String>);
 
+/// A structured metadata value, such as `tags: [a, b]` or a nested table.
+///
+/// Serialized untagged, so "kodama.json" holds it as plain JSON inside the
+/// `{"Structured": ...}` tag of [`HTMLContent::Structured`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MetaValue {
+    Text(String),
+    List(Vec<MetaValue>),
+    Map(OrderedMap<String, MetaValue>),
+}
+
+impl MetaValue {
+    /// Escaped HTML for display: list items are joined with `, ` and table
+    /// entries are shown as `key: value`.
+    pub fn to_html(&self) -> String {
+        self.render(&|text| htmlize::escape_text(text).into_owned())
+    }
+
+    /// Plain text for display, joined like [`MetaValue::to_html`].
+    pub fn to_text(&self) -> String {
+        self.render(&str::to_string)
+    }
+
+    fn render(&self, text: &dyn Fn(&str) -> String) -> String {
+        match self {
+            MetaValue::Text(value) => text(value),
+            MetaValue::List(items) => items
+                .iter()
+                .map(|item| item.render(text))
+                .collect::<Vec<_>>()
+                .join(", "),
+            MetaValue::Map(entries) => entries
+                .iter()
+                .map(|(key, value)| format!("{}: {}", text(key), value.render(text)))
+                .collect::<Vec<_>>()
+                .join("; "),
+        }
+    }
+}
+
+impl From<serde_json::Value> for MetaValue {
+    fn from(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::String(text) => MetaValue::Text(text),
+            serde_json::Value::Array(items) => {
+                MetaValue::List(items.into_iter().map(MetaValue::from).collect())
+            }
+            serde_json::Value::Object(entries) => MetaValue::Map(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, MetaValue::from(value)))
+                    .collect(),
+            ),
+            serde_json::Value::Null => MetaValue::Text(String::new()),
+            other => MetaValue::Text(other.to_string()),
+        }
+    }
+}
+
+impl From<toml::Value> for MetaValue {
+    fn from(value: toml::Value) -> Self {
+        match value {
+            toml::Value::String(text) => MetaValue::Text(text),
+            toml::Value::Array(items) => {
+                MetaValue::List(items.into_iter().map(MetaValue::from).collect())
+            }
+            toml::Value::Table(entries) => MetaValue::Map(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, MetaValue::from(value)))
+                    .collect(),
+            ),
+            // Dates keep their TOML form, e.g. `2025-01-31`, which date sorting understands.
+            other => MetaValue::Text(other.to_string()),
+        }
+    }
+}
+
 pub const KEY_TITLE: &str = "title";
 
 /// Auto-detected
@@ -73,9 +152,26 @@ pub const KEY_FOOTER_MODE: &str = "footer-mode";
 /// `footer-sort-by: <metadata-key>`
 pub const KEY_FOOTER_SORT_BY: &str = "footer-sort-by";
 
+/// `local-graph: bool`:
+/// Controls whether the current page shows its local graph.
+/// Default is `[build].local-graph`.
+pub const KEY_LOCAL_GRAPH: &str = "local-graph";
+
+/// Computed: words in the section, each Han or kana character counting as one.
+pub const KEY_WORD_COUNT: &str = "word-count";
+
+/// Computed: estimated minutes to read the section.
+pub const KEY_READING_TIME: &str = "reading-time";
+
+/// Computed: when the source file was first committed or created.
+pub const KEY_CREATED: &str = "created";
+
+/// Computed: when the source file was last committed or modified.
+pub const KEY_MODIFIED: &str = "modified";
+
 const FANCY_METADATA: [&str; 2] = [KEY_TITLE, KEY_TAXON];
 
-const PLAIN_METADATA: [&str; 16] = [
+const PLAIN_METADATA: [&str; 21] = [
     KEY_SLUG,
     KEY_EXT,
     KEY_DATA_TAXON,
@@ -92,6 +188,11 @@ const PLAIN_METADATA: [&str; 16] = [
     KEY_ASBACK,
     KEY_FOOTER_MODE,
     KEY_FOOTER_SORT_BY,
+    KEY_LOCAL_GRAPH,
+    KEY_WORD_COUNT,
+    KEY_READING_TIME,
+    KEY_CREATED,
+    KEY_MODIFIED,
 ];
 
 pub fn is_plain_metadata(s: &str) -> bool {
@@ -212,6 +313,11 @@ where
     fn is_asback(&self) -> eyre::Result<Option<bool>> {
         self.get_bool(KEY_ASBACK)
     }
+
+    fn local_graph_enabled(&self) -> eyre::Result<bool> {
+        self.get_bool(KEY_LOCAL_GRAPH)
+            .map(|v| v.unwrap_or_else(environment::local_graph))
+    }
 }
 
 impl MetaData<HTMLContent> for HTMLMetaData {
@@ -2,36 +2,45 @@
 // Released under the GPL-3.0 license as described in the file LICENSE.
 // Authors: Kokic (@kokic), Spore (@s-cerevisiae)
 
-use std::sync::{OnceLock, RwLock};
+use std::{
+    cell::{Cell, RefCell},
+    sync::{Arc, Mutex, MutexGuard, OnceLock, RwLock},
+};
 
 use camino::Utf8PathBuf;
-use eyre::eyre;
+use eyre::{eyre, WrapErr};
 
 use crate::{
-    config::{self, Config},
+    config::{self, overrides::ConfigOverrides, Config},
     path_utils,
+    vfs::{DiskFs, FileSystem},
 };
 
 mod cache;
 mod config_access;
 mod hashing;
 mod imports;
+mod mounts;
 mod paths;
 
 pub use cache::ensure_cache_version;
 pub use config_access::{
     allow_unsafe_html, asref, assets_dir, assets_dir_without_root, base_url, base_url_raw,
-    deploy_edit_url, editor_url, feed_path, footer_mode, footer_sort_by, get_cache_dir,
-    get_edit_text, get_footer_backlinks_text, get_footer_references_text, get_toc_text, graph_path,
+    build_mode_name, deploy_edit_url, editor_url, exclude_patterns, feed_path, footer_mode,
+    footer_sort_by, get_cache_dir, get_edit_text, get_footer_backlinks_text,
+    get_footer_local_graph_text, get_footer_references_text, get_toc_text, graph_path, highlight,
     indexes_path, inline_css, inline_script, is_short_slug, is_toc_left, is_toc_mobile_sticky,
-    is_toc_sticky, output_dir, publish_rss, reload_marker_path, serve_command, theme_lock,
-    theme_paths, toc_max_width, trees_dir, trees_dir_without_root, typst_root_dir,
+    is_toc_sticky, line_numbers, link_action, local_graph, local_graph_depth, markdown_math,
+    output_dir, output_dirs, plugins, publish_rss, reload_marker_path, schema, serve_command,
+    theme_lock, theme_paths, timestamps, toc_max_width, trees_dir, trees_dir_without_root,
+    typst_root_dir,
 };
-pub use hashing::{verify_and_file_hash, verify_update_hash};
+pub use hashing::{content_hash, is_hash_updated, verify_and_file_hash, verify_update_hash};
 pub use imports::{import_fonts_html, import_math_html, import_meta_html, import_style_html};
+pub use mounts::{locate_in_mounts, locate_tree_path, tree_mounts, TreeMount};
 pub use paths::{
-    create_parent_dirs, entry_dir, entry_file_path, full_html_url, full_url, hash_dir,
-    hash_file_path, input_path, output_path,
+    create_parent_dirs, ensure_removable_output, entry_dir, entry_file_path, full_html_url,
+    full_url, hash_dir, hash_file_path, input_path, math_dir, output_path,
 };
 
 pub struct Environment {
@@ -45,7 +54,7 @@ pub struct Environment {
     pub build_mode: BuildMode,
 }
 
-static ENVIRONMENT: OnceLock<RwLock<Environment>> = OnceLock::new();
+static ENVIRONMENT: OnceLock<RwLock<Arc<Environment>>> = OnceLock::new();
 
 fn default_environment() -> Environment {
     Environment {
@@ -56,7 +65,7 @@ fn default_environment() -> Environment {
     }
 }
 
-fn read_environment<R>(lock: &RwLock<Environment>, f: impl FnOnce(&Environment) -> R) -> R {
+fn read_environment<R>(lock: &RwLock<Arc<Environment>>, f: impl FnOnce(&Environment) -> R) -> R {
     match lock.read() {
         Ok(env) => f(&env),
         Err(poisoned) => {
@@ -69,33 +78,35 @@ fn read_environment<R>(lock: &RwLock<Environment>, f: impl FnOnce(&Environment)
     }
 }
 
-fn write_environment(lock: &RwLock<Environment>, environment: Environment) {
+/// Replace the environment in `lock`, returning the previous one.
+fn write_environment(
+    lock: &RwLock<Arc<Environment>>,
+    environment: Arc<Environment>,
+) -> Arc<Environment> {
     match lock.write() {
-        Ok(mut env) => {
-            *env = environment;
-        }
+        Ok(mut env) => std::mem::replace(&mut *env, environment),
         Err(poisoned) => {
             color_print::ceprintln!(
                 "<y>Warning: environment write lock is poisoned; replacing with recovered state.</>"
             );
             let mut env = poisoned.into_inner();
-            *env = environment;
+            std::mem::replace(&mut *env, environment)
         }
     }
 }
 
-fn environment_lock(warn_if_uninitialized: bool) -> &'static RwLock<Environment> {
+fn environment_lock(warn_if_uninitialized: bool) -> &'static RwLock<Arc<Environment>> {
     if warn_if_uninitialized && ENVIRONMENT.get().is_none() {
         color_print::ceprintln!(
             "<y>Warning: environment accessed before initialization; using default configuration.</>"
         );
     }
-    ENVIRONMENT.get_or_init(|| RwLock::new(default_environment()))
+    ENVIRONMENT.get_or_init(|| RwLock::new(Arc::new(default_environment())))
 }
 
 fn update_environment(environment: Environment) {
     let lock = environment_lock(false);
-    write_environment(lock, environment);
+    write_environment(lock, Arc::new(environment));
 }
 
 fn with_environment<R>(f: impl FnOnce(&Environment) -> R) -> R {
@@ -107,13 +118,61 @@ fn with_config<R>(f: impl FnOnce(&Config) -> R) -> R {
     with_environment(|env| f(&env.config))
 }
 
+thread_local! {
+    /// Installed per thread, so unscoped callers on other threads keep the disk.
+    static FILE_SYSTEM: RefCell<Option<Arc<dyn FileSystem>>> = const { RefCell::new(None) };
+
+    /// Scopes entered on this thread, which holds the scope mutex while it
+    /// is above zero.
+    static SCOPE_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// The file system of the current scope, or the disk outside of one.
+pub fn fs() -> Arc<dyn FileSystem> {
+    static DISK: OnceLock<Arc<dyn FileSystem>> = OnceLock::new();
+    FILE_SYSTEM
+        .with_borrow(Option::clone)
+        .unwrap_or_else(|| DISK.get_or_init(|| Arc::new(DiskFs)).clone())
+}
+
+/// Run `f` with `environment` as the current environment and `fs` as the file
+/// system of this thread, then restore the previous ones.
+///
+/// The environment is process-wide, so scopes on different threads run one at
+/// a time. A scope nested on the same thread doesn't wait for the outer one;
+/// it swaps the environment in and restores the outer one when it ends.
+pub(crate) fn with_scoped_environment<R>(
+    environment: &Arc<Environment>,
+    fs: &Arc<dyn FileSystem>,
+    f: impl FnOnce() -> R,
+) -> R {
+    let _guard = (SCOPE_DEPTH.get() == 0).then(lock_scope_mutex);
+    SCOPE_DEPTH.set(SCOPE_DEPTH.get() + 1);
+
+    struct Restore(Option<Arc<Environment>>, Option<Arc<dyn FileSystem>>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            if let Some(previous) = self.0.take() {
+                write_environment(environment_lock(false), previous);
+            }
+            FILE_SYSTEM.set(self.1.take());
+            SCOPE_DEPTH.set(SCOPE_DEPTH.get() - 1);
+        }
+    }
+
+    let previous = write_environment(environment_lock(false), environment.clone());
+    let previous_fs = FILE_SYSTEM.replace(Some(fs.clone()));
+    let _restore = Restore(Some(previous), previous_fs);
+    f()
+}
+
 #[cfg(test)]
 pub(super) fn with_test_environment<R>(
     root: Utf8PathBuf,
     build_mode: BuildMode,
     f: impl FnOnce() -> R,
 ) -> R {
-    let _guard = lock_test_env_mutex();
+    let _guard = lock_scope_mutex();
 
     struct Reset;
     impl Drop for Reset {
@@ -132,26 +191,72 @@ pub(super) fn with_test_environment<R>(
     f()
 }
 
+static CONFIG_OVERRIDES: OnceLock<ConfigOverrides> = OnceLock::new();
+
+/// Install the overrides applied by every later [`init_environment`]. The
+/// CLI calls this once, before running a command.
+pub fn set_config_overrides(overrides: ConfigOverrides) {
+    let _ = CONFIG_OVERRIDES.set(overrides);
+}
+
+pub fn config_overrides() -> ConfigOverrides {
+    CONFIG_OVERRIDES.get().cloned().unwrap_or_default()
+}
+
 pub fn init_environment(toml_file: Utf8PathBuf, build_mode: BuildMode) -> eyre::Result<()> {
+    let environment = load_environment_with(toml_file, build_mode, &config_overrides())?;
+    update_environment(environment);
+    Ok(())
+}
+
+/// Read the configuration file, searching parent directories like the CLI,
+/// without installing it as the current environment.
+pub fn load_environment(
+    toml_file: Utf8PathBuf,
+    build_mode: BuildMode,
+) -> eyre::Result<Environment> {
+    load_environment_with(toml_file, build_mode, &ConfigOverrides::default())
+}
+
+/// Like [`load_environment`], with `overrides` merged over the file.
+pub fn load_environment_with(
+    toml_file: Utf8PathBuf,
+    build_mode: BuildMode,
+    overrides: &ConfigOverrides,
+) -> eyre::Result<Environment> {
     let toml_file = config::find_config(toml_file)?;
 
     let (root, _file_name) = path_utils::split_file_name(&toml_file)
         .ok_or_else(|| eyre!("invalid config path `{}`: path cannot be empty", toml_file))?;
     let toml = std::fs::read_to_string(&toml_file)?;
+    let config = config::parse_config_with(&toml, overrides)
+        .wrap_err_with(|| eyre!("invalid config file `{}`", toml_file))?;
+    // `kodama check` reports these as diagnostics instead.
+    if !matches!(build_mode, BuildMode::Check) {
+        let warnings = config::validate::validate_config(&toml)
+            .into_iter()
+            .chain(config::override_diagnostics(&toml, overrides)?)
+            .filter(|d| d.severity == config::validate::ConfigSeverity::Warning);
+        for warning in warnings {
+            color_print::ceprintln!(
+                "<y>Warning: {}: {}</>",
+                warning.location(&toml_file),
+                warning.message
+            );
+        }
+    }
 
-    update_environment(Environment {
+    Ok(Environment {
         root: root.to_owned(),
         config_file: toml_file,
-        config: config::parse_config(&toml)?,
+        config,
         build_mode,
-    });
-    Ok(())
+    })
 }
 
-#[cfg(test)]
-fn test_env_mutex() -> &'static std::sync::Mutex<()> {
-    static TEST_ENV_MUTEX: OnceLock<std::sync::Mutex<()>> = OnceLock::new();
-    TEST_ENV_MUTEX.get_or_init(|| std::sync::Mutex::new(()))
+fn scope_mutex() -> &'static Mutex<()> {
+    static SCOPE_MUTEX: OnceLock<Mutex<()>> = OnceLock::new();
+    SCOPE_MUTEX.get_or_init(|| Mutex::new(()))
 }
 
 #[derive(Clone, Copy)]
@@ -169,6 +274,7 @@ pub enum BuildMode {
 pub const CACHE_DIR_NAME: &str = ".cache";
 pub const HASH_DIR_NAME: &str = "hash";
 pub const ENTRY_DIR_NAME: &str = "entry";
+pub const MATH_DIR_NAME: &str = "math";
 
 pub fn to_page_suffix(pretty_urls: bool) -> String {
     let page_suffix = match pretty_urls {
@@ -199,13 +305,12 @@ pub fn is_check() -> bool {
     with_environment(|env| matches!(env.build_mode, BuildMode::Check))
 }
 
-#[cfg(test)]
-fn lock_test_env_mutex() -> std::sync::MutexGuard<'static, ()> {
-    match test_env_mutex().lock() {
+fn lock_scope_mutex() -> MutexGuard<'static, ()> {
+    match scope_mutex().lock() {
         Ok(guard) => guard,
         Err(poisoned) => {
             color_print::ceprintln!(
-                "<y>Warning: test environment mutex is poisoned; continuing with recovered state.</>"
+                "<y>Warning: environment scope mutex is poisoned; continuing with recovered state.</>"
             );
             poisoned.into_inner()
         }
@@ -216,7 +321,7 @@ fn lock_test_env_mutex() -> std::sync::MutexGuard<'static, ()> {
 #[allow(dead_code)]
 pub fn mock_environment() -> eyre::Result<()> {
     #[cfg(test)]
-    let _guard = lock_test_env_mutex();
+    let _guard = lock_scope_mutex();
 
     update_environment(default_environment());
     Ok(())
@@ -224,11 +329,9 @@ pub fn mock_environment() -> eyre::Result<()> {
 
 #[cfg(test)]
 mod tests {
-    use std::sync::{Arc, RwLock};
-
     use super::*;
 
-    fn poison_read_lock(lock: Arc<RwLock<Environment>>) {
+    fn poison_read_lock(lock: Arc<RwLock<Arc<Environment>>>) {
         let _ = std::thread::spawn(move || {
             let _guard = lock.write().unwrap();
             panic!("poison lock");
@@ -238,7 +341,7 @@ mod tests {
 
     #[test]
     fn test_read_environment_recovers_from_poisoned_lock() {
-        let lock = Arc::new(RwLock::new(default_environment()));
+        let lock = Arc::new(RwLock::new(Arc::new(default_environment())));
         poison_read_lock(lock.clone());
 
         let root = read_environment(&lock, |env| env.root.clone());
@@ -247,17 +350,17 @@ mod tests {
 
     #[test]
     fn test_write_environment_recovers_from_poisoned_lock() {
-        let lock = Arc::new(RwLock::new(default_environment()));
+        let lock = Arc::new(RwLock::new(Arc::new(default_environment())));
         poison_read_lock(lock.clone());
 
         write_environment(
             &lock,
-            Environment {
+            Arc::new(Environment {
                 root: Utf8PathBuf::from("site"),
                 config_file: Utf8PathBuf::from("Kodama.toml"),
                 config: Config::default(),
                 build_mode: BuildMode::Serve,
-            },
+            }),
         );
 
         let (root, mode) = read_environment(&lock, |env| (env.root.clone(), env.build_mode));
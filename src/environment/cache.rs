@@ -8,13 +8,24 @@ use camino::{Utf8Path, Utf8PathBuf};
 use eyre::{eyre, WrapErr};
 
 const CACHE_VERSION_FILE: &str = "version";
-const CACHE_SCHEMA_VERSION: &str = "schema-v3";
+const CACHE_SCHEMA_VERSION: &str = "schema-v10";
 
 fn cache_version_value() -> String {
     format!(
-        "kodama:{}:{}",
+        "kodama:{}:{}:{}",
         env!("CARGO_PKG_VERSION"),
-        CACHE_SCHEMA_VERSION
+        CACHE_SCHEMA_VERSION,
+        parse_settings()
+    )
+}
+
+/// Config settings baked into parsed entries, so toggling one clears the cache.
+fn parse_settings() -> String {
+    format!(
+        "highlight={},line-numbers={},markdown-math={:?}",
+        super::highlight(),
+        super::line_numbers(),
+        super::markdown_math()
     )
 }
 
@@ -23,14 +34,15 @@ fn cache_version_path() -> Utf8PathBuf {
 }
 
 fn read_cache_version(path: &Utf8Path) -> Option<String> {
-    std::fs::read_to_string(path)
+    super::fs()
+        .read_to_string(path)
         .ok()
         .map(|content| content.trim().to_string())
         .filter(|value| !value.is_empty())
 }
 
 fn remove_dir_if_exists(path: &Utf8Path) -> eyre::Result<()> {
-    match std::fs::remove_dir_all(path) {
+    match super::fs().remove_dir_all(path) {
         Ok(()) => Ok(()),
         Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
         Err(err) => Err(err).wrap_err_with(|| eyre!("failed to remove directory `{}`", path)),
@@ -39,7 +51,8 @@ fn remove_dir_if_exists(path: &Utf8Path) -> eyre::Result<()> {
 
 pub fn ensure_cache_version() -> eyre::Result<()> {
     let cache_dir = super::get_cache_dir();
-    std::fs::create_dir_all(cache_dir.as_std_path())
+    let fs = super::fs();
+    fs.create_dir_all(&cache_dir)
         .wrap_err_with(|| eyre!("failed to create cache directory `{}`", cache_dir))?;
 
     let version_path = cache_version_path();
@@ -52,14 +65,14 @@ pub fn ensure_cache_version() -> eyre::Result<()> {
 
     remove_dir_if_exists(super::hash_dir().as_path())?;
     remove_dir_if_exists(super::entry_dir().as_path())?;
+    remove_dir_if_exists(super::math_dir().as_path())?;
 
-    super::create_parent_dirs(version_path.as_path());
-    std::fs::write(version_path.as_std_path(), expected.as_bytes())
+    fs.write(&version_path, expected.as_bytes())
         .wrap_err_with(|| eyre!("failed to write cache version file `{}`", version_path))?;
 
     if current.is_some() {
         color_print::ceprintln!(
-            "<dim>[cache] Cache layout changed. Cleared \"{}\" and \"{}\".</>",
+            "<dim>[cache] Cache layout or parse settings changed. Cleared \"{}\" and \"{}\".</>",
             super::HASH_DIR_NAME,
             super::ENTRY_DIR_NAME
         );
@@ -120,4 +133,30 @@ mod tests {
 
         let _ = fs::remove_dir_all(root.as_std_path());
     }
+
+    #[test]
+    fn test_ensure_cache_version_clears_entry_when_parse_settings_change() {
+        let root = crate::test_io::case_dir("env-cache-settings");
+        fs::create_dir_all(root.as_std_path()).unwrap();
+
+        super::super::with_test_environment(root.clone(), super::super::BuildMode::Publish, || {
+            ensure_cache_version().unwrap();
+            let entry_file = super::super::entry_file_path("a.md");
+            fs::write(entry_file.as_std_path(), "{}").unwrap();
+
+            let mut config = crate::config::Config::default();
+            config.build.highlight = !config.build.highlight;
+            super::super::update_environment(super::super::Environment {
+                root: root.clone(),
+                config_file: root.join(crate::config::DEFAULT_CONFIG_PATH),
+                config,
+                build_mode: super::super::BuildMode::Publish,
+            });
+            ensure_cache_version().unwrap();
+
+            assert!(!entry_file.exists());
+        });
+
+        let _ = fs::remove_dir_all(root.as_std_path());
+    }
 }
@@ -4,7 +4,17 @@
 
 use camino::{Utf8Path, Utf8PathBuf};
 
-use crate::config::{build::FooterMode, kodama, toc};
+use crate::{
+    config::{
+        action::Action,
+        build::{FooterMode, MarkdownMath, Timestamps},
+        kodama,
+        plugin::Plugin,
+        schema::Schema,
+        toc,
+    },
+    recorder,
+};
 
 use super::{with_config, with_environment, BuildMode, CACHE_DIR_NAME};
 
@@ -39,6 +49,59 @@ pub fn theme_paths() -> Vec<Utf8PathBuf> {
     })
 }
 
+pub fn exclude_patterns() -> Vec<String> {
+    with_config(|cfg| cfg.kodama.exclude.clone())
+}
+
+/// The `[actions.<name>]` entry for a link action, unless `name` is built in.
+pub fn link_action(name: &str) -> Option<Action> {
+    if name.is_empty() || recorder::is_builtin_action(name) {
+        return None;
+    }
+    with_config(|cfg| cfg.actions.get(name).cloned())
+}
+
+pub fn plugins() -> Vec<Plugin> {
+    with_config(|cfg| cfg.plugin.clone())
+}
+
+pub fn schema() -> Schema {
+    with_config(|cfg| cfg.schema.clone())
+}
+
+/// Name of the current build mode, as reported to plugins.
+pub fn build_mode_name() -> &'static str {
+    with_environment(|env| match env.build_mode {
+        BuildMode::Publish => "build",
+        BuildMode::Check => "check",
+        BuildMode::Serve => "serve",
+    })
+}
+
+pub fn highlight() -> bool {
+    with_config(|cfg| cfg.build.highlight)
+}
+
+pub fn line_numbers() -> bool {
+    with_config(|cfg| cfg.build.line_numbers)
+}
+
+pub fn markdown_math() -> MarkdownMath {
+    with_config(|cfg| cfg.build.markdown_math)
+}
+
+pub fn timestamps() -> Timestamps {
+    with_config(|cfg| cfg.build.timestamps)
+}
+
+pub fn local_graph() -> bool {
+    with_config(|cfg| cfg.build.local_graph)
+}
+
+pub fn local_graph_depth() -> usize {
+    with_config(|cfg| cfg.build.local_graph_depth)
+}
+
 pub fn theme_lock() -> bool {
     with_config(|cfg| cfg.kodama.theme_lock)
 }
@@ -53,6 +116,18 @@ pub fn output_dir() -> Utf8PathBuf {
     })
 }
 
+/// Return the build and serve output directories, without duplicates.
+pub fn output_dirs() -> Vec<Utf8PathBuf> {
+    with_environment(|env| {
+        let mut dirs = vec![env.root.join(&env.config.build.output)];
+        let serve = env.root.join(&env.config.serve.output);
+        if !dirs.contains(&serve) {
+            dirs.push(serve);
+        }
+        dirs
+    })
+}
+
 pub fn indexes_path(output_dir: &Utf8Path) -> Utf8PathBuf {
     output_dir.join("kodama.json")
 }
@@ -115,6 +190,10 @@ pub fn get_footer_backlinks_text() -> String {
     with_config(|cfg| cfg.text.backlinks.clone())
 }
 
+pub fn get_footer_local_graph_text() -> String {
+    with_config(|cfg| cfg.text.local_graph.clone())
+}
+
 pub fn footer_mode() -> FooterMode {
     with_config(|cfg| cfg.build.footer_mode)
 }
@@ -5,13 +5,20 @@
 use camino::Utf8Path;
 use eyre::{eyre, Context};
 
+/// 64-bit FNV-1a hash of `content`. Unlike `DefaultHasher` it does not change
+/// between Rust releases, so hashes kept on disk stay valid.
+pub fn content_hash(content: &str) -> u64 {
+    content.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
 /// Return is file modified i.e. is hash updated.
 pub fn is_hash_updated<P: AsRef<Utf8Path>>(content: &str, hash_path: P) -> (bool, u64) {
-    let mut hasher = std::hash::DefaultHasher::new();
-    std::hash::Hash::hash(&content, &mut hasher);
-    let current_hash = std::hash::Hasher::finish(&hasher);
+    let current_hash = content_hash(content);
 
-    let history_hash = std::fs::read_to_string(hash_path.as_ref())
+    let history_hash = super::fs()
+        .read_to_string(hash_path.as_ref())
         .ok()
         .and_then(|s| s.parse::<u64>().ok())
         .unwrap_or(0); // no file / invalid hash: 0
@@ -22,19 +29,21 @@ pub fn is_hash_updated<P: AsRef<Utf8Path>>(content: &str, hash_path: P) -> (bool
 /// Checks whether the file has been modified by comparing its current hash with the stored hash.
 /// If the file is modified, updates the stored hash to reflect the latest state.
 pub fn verify_and_file_hash<P: AsRef<Utf8Path>>(relative_path: P) -> eyre::Result<bool> {
-    if *crate::cli::build::no_cache_enabled() {
+    if crate::cli::build::no_cache_enabled() {
         return Ok(true);
     }
 
-    let root_dir = super::trees_dir();
-    let full_path = root_dir.join(&relative_path);
+    let full_path = super::input_path(&relative_path);
     let hash_path = super::hash_file_path(&relative_path);
 
-    let content = std::fs::read_to_string(&full_path)
+    let fs = super::fs();
+    let content = fs
+        .read_to_string(&full_path)
         .wrap_err_with(|| eyre!("failed to read file `{}`", full_path))?;
     let (is_modified, current_hash) = is_hash_updated(&content, &hash_path);
-    if is_modified {
-        std::fs::write(&hash_path, current_hash.to_string())
+    // Check mode only reads the caches.
+    if is_modified && !super::is_check() {
+        fs.write(&hash_path, current_hash.to_string().as_bytes())
             .wrap_err_with(|| eyre!("failed to write file `{}`", hash_path))?;
     }
     Ok(is_modified)
@@ -46,14 +55,14 @@ pub fn verify_update_hash<P: AsRef<Utf8Path>>(
     path: P,
     content: &str,
 ) -> Result<bool, std::io::Error> {
-    if *crate::cli::build::no_cache_enabled() {
+    if crate::cli::build::no_cache_enabled() {
         return Ok(true);
     }
 
     let hash_path = super::hash_file_path(path.as_ref());
     let (is_modified, current_hash) = is_hash_updated(content, &hash_path);
     if is_modified {
-        std::fs::write(&hash_path, current_hash.to_string())?;
+        super::fs().write(&hash_path, current_hash.to_string().as_bytes())?;
     }
 
     Ok(is_modified)
@@ -86,6 +95,12 @@ mod tests {
         let _ = fs::remove_dir_all(root);
     }
 
+    #[test]
+    fn test_content_hash_is_fnv1a() {
+        assert_eq!(content_hash(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(content_hash("a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
     #[test]
     fn test_is_hash_updated_returns_unmodified_for_matching_hash() {
         let root = crate::test_io::case_dir("env-hash-matching");
@@ -2,25 +2,36 @@
 // Released under the GPL-3.0 license as described in the file LICENSE.
 // Authors: Kokic (@kokic), Spore (@s-cerevisiae)
 
-use std::fs;
+use crate::config::build::MarkdownMath;
 
 const DEFAULT_IMPORT_FONT_HTML: &str = include_str!("../include/import-font.html");
 const DEFAULT_IMPORT_MATH_HTML: &str = include_str!("../include/import-math.html");
 
 pub fn import_meta_html() -> String {
-    fs::read_to_string(super::root_dir().join("import-meta.html")).unwrap_or_default()
+    super::fs()
+        .read_to_string(&super::root_dir().join("import-meta.html"))
+        .unwrap_or_default()
 }
 
 pub fn import_style_html() -> String {
-    fs::read_to_string(super::root_dir().join("import-style.html")).unwrap_or_default()
+    super::fs()
+        .read_to_string(&super::root_dir().join("import-style.html"))
+        .unwrap_or_default()
 }
 
 pub fn import_fonts_html() -> String {
-    fs::read_to_string(super::root_dir().join("import-font.html"))
+    super::fs()
+        .read_to_string(&super::root_dir().join("import-font.html"))
         .unwrap_or_else(|_| DEFAULT_IMPORT_FONT_HTML.to_string())
 }
 
+/// Pages rendering Markdown math with Typst need no KaTeX, unless the user
+/// imports math scripts of their own.
 pub fn import_math_html() -> String {
-    fs::read_to_string(super::root_dir().join("import-math.html"))
-        .unwrap_or_else(|_| DEFAULT_IMPORT_MATH_HTML.to_string())
+    super::fs()
+        .read_to_string(&super::root_dir().join("import-math.html"))
+        .unwrap_or_else(|_| match super::markdown_math() {
+            MarkdownMath::Katex => DEFAULT_IMPORT_MATH_HTML.to_string(),
+            MarkdownMath::Typst => String::new(),
+        })
 }
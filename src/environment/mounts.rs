@@ -0,0 +1,144 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+use camino::{Utf8Path, Utf8PathBuf};
+
+use crate::path_utils;
+
+use super::with_environment;
+
+/// A physical source directory and the slug prefix its sections live under.
+/// The main `trees` directory is the mount with an empty prefix.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TreeMount {
+    pub dir: Utf8PathBuf,
+    pub prefix: String,
+    pub edit: Option<String>,
+}
+
+impl TreeMount {
+    pub fn main(dir: Utf8PathBuf) -> Self {
+        Self {
+            dir,
+            prefix: String::new(),
+            edit: None,
+        }
+    }
+
+    pub fn is_main(&self) -> bool {
+        self.prefix.is_empty()
+    }
+
+    /// Map a path inside this mount to the trees-relative path used for slugs and caches.
+    pub fn virtual_path(&self, inner: &Utf8Path) -> Utf8PathBuf {
+        if self.is_main() {
+            inner.to_owned()
+        } else {
+            Utf8Path::new(&self.prefix).join(inner)
+        }
+    }
+
+    /// Map a trees-relative path back into this mount, if it lies under the prefix.
+    pub fn inner_path<'a>(&self, relative: &'a Utf8Path) -> Option<&'a Utf8Path> {
+        if self.is_main() {
+            return Some(relative);
+        }
+        relative.strip_prefix(&self.prefix).ok()
+    }
+}
+
+/// The main trees directory followed by the configured `[kodama].mounts`.
+pub fn tree_mounts() -> Vec<TreeMount> {
+    with_environment(|env| {
+        let mut mounts = vec![TreeMount::main(env.root.join(&env.config.kodama.trees))];
+        mounts.extend(env.config.kodama.mounts.iter().map(|mount| TreeMount {
+            dir: env.root.join(&mount.path),
+            prefix: path_utils::pretty_path(Utf8Path::new(&mount.prefix)),
+            edit: mount.edit.clone(),
+        }));
+        mounts
+    })
+}
+
+/// Find the mount owning a trees-relative path: the one with the longest matching
+/// prefix, or the main trees directory. Returns the mount and the path inside it.
+pub fn locate_in_mounts<'a>(
+    mounts: &'a [TreeMount],
+    relative: &Utf8Path,
+) -> Option<(&'a TreeMount, Utf8PathBuf)> {
+    mounts
+        .iter()
+        .filter_map(|mount| Some((mount, mount.inner_path(relative)?.to_owned())))
+        .max_by_key(|(mount, _)| mount.prefix.len())
+}
+
+/// Physical location of a trees-relative path, as `(mount, path inside mount)`.
+pub fn locate_tree_path(relative: &Utf8Path) -> (TreeMount, Utf8PathBuf) {
+    let mounts = tree_mounts();
+    match locate_in_mounts(&mounts, relative) {
+        Some((mount, inner)) => (mount.clone(), inner),
+        None => (TreeMount::main(super::trees_dir()), relative.to_owned()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mounts() -> Vec<TreeMount> {
+        vec![
+            TreeMount::main("site/trees".into()),
+            TreeMount {
+                dir: "site/vendor/foundations".into(),
+                prefix: "found".to_string(),
+                edit: None,
+            },
+            TreeMount {
+                dir: "site/vendor/algebra".into(),
+                prefix: "found/algebra".to_string(),
+                edit: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_locate_in_mounts_prefers_longest_prefix() {
+        let mounts = mounts();
+        let locate = |path: &str| {
+            let (mount, inner) = locate_in_mounts(&mounts, Utf8Path::new(path)).unwrap();
+            (mount.dir.clone(), inner)
+        };
+
+        assert_eq!(
+            locate("notes/a.md"),
+            ("site/trees".into(), "notes/a.md".into())
+        );
+        assert_eq!(
+            locate("found/sets.md"),
+            ("site/vendor/foundations".into(), "sets.md".into())
+        );
+        assert_eq!(
+            locate("found/algebra/group.typst"),
+            ("site/vendor/algebra".into(), "group.typst".into())
+        );
+        // Prefixes match whole path components only.
+        assert_eq!(
+            locate("foundry/a.md"),
+            ("site/trees".into(), "foundry/a.md".into())
+        );
+    }
+
+    #[test]
+    fn test_tree_mount_virtual_path_adds_prefix() {
+        let mounts = mounts();
+        assert_eq!(
+            mounts[0].virtual_path(Utf8Path::new("a.md")),
+            Utf8PathBuf::from("a.md")
+        );
+        assert_eq!(
+            mounts[1].virtual_path(Utf8Path::new("sets/empty.md")),
+            Utf8PathBuf::from("found/sets/empty.md")
+        );
+    }
+}
@@ -2,13 +2,12 @@
 // Released under the GPL-3.0 license as described in the file LICENSE.
 // Authors: Kokic (@kokic), Spore (@s-cerevisiae)
 
-use std::fs::create_dir_all;
-
 use camino::{Utf8Path, Utf8PathBuf};
+use eyre::eyre;
 
 use crate::{path_utils, slug::Slug};
 
-use super::{ENTRY_DIR_NAME, HASH_DIR_NAME};
+use super::{ENTRY_DIR_NAME, HASH_DIR_NAME, MATH_DIR_NAME};
 
 /// URL keep posix style, so the type of return value is [`String`].
 pub fn full_url<P: AsRef<Utf8Path>>(path: P) -> String {
@@ -28,18 +27,19 @@ pub fn full_html_url(slug: Slug) -> String {
     full_url(format!("{}{}", slug, page_suffix))
 }
 
+/// Physical path of a trees-relative path, resolved through the tree mounts.
 pub fn input_path<P: AsRef<Utf8Path>>(path: P) -> Utf8PathBuf {
-    let mut filepath: Utf8PathBuf = super::trees_dir();
-    filepath.push(path);
-    filepath
+    let (mount, inner) = super::locate_tree_path(path.as_ref());
+    mount.dir.join(inner)
 }
 
 pub fn create_parent_dirs<P: AsRef<Utf8Path>>(path: P) {
     let Some(parent_dir) = path.as_ref().parent() else {
         return;
     };
-    if !parent_dir.exists() {
-        if let Err(err) = create_dir_all(parent_dir) {
+    let fs = super::fs();
+    if !fs.exists(parent_dir) {
+        if let Err(err) = fs.create_dir_all(parent_dir) {
             color_print::ceprintln!(
                 "<y>Warning: failed to create parent directory `{}`: {}</>",
                 parent_dir,
@@ -105,6 +105,31 @@ pub fn entry_file_path<P: AsRef<Utf8Path>>(path: P) -> Utf8PathBuf {
     entry_path
 }
 
+/// Directory of Markdown math rendered by Typst, one HTML fragment per formula.
+pub fn math_dir() -> Utf8PathBuf {
+    super::get_cache_dir().join(MATH_DIR_NAME)
+}
+
+/// Refuse to delete output directories that would take project sources with them.
+pub fn ensure_removable_output(output_dir: &Utf8Path) -> eyre::Result<()> {
+    let protected = [super::root_dir(), super::trees_dir(), super::assets_dir()];
+    let output = canonicalize_or_self(output_dir);
+    for dir in protected {
+        if canonicalize_or_self(dir.as_path()).starts_with(&output) {
+            return Err(eyre!(
+                "refusing to remove output directory \"{}\": it contains \"{}\"",
+                output_dir,
+                dir
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn canonicalize_or_self(path: &Utf8Path) -> Utf8PathBuf {
+    path.canonicalize_utf8().unwrap_or_else(|_| path.to_owned())
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs;
@@ -0,0 +1,94 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+use std::{
+    io::{Read, Write},
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use camino::Utf8Path;
+use eyre::{bail, eyre, WrapErr};
+
+/// Run `command` from `cwd` with `input` on stdin, returning its stdout.
+///
+/// The command is killed once `timeout` seconds pass; `0` waits forever. A
+/// non-zero exit status is an error carrying the command's stderr. On success,
+/// anything written to stderr is forwarded to ours.
+pub fn run_piped(
+    command: &[String],
+    cwd: &Utf8Path,
+    input: Vec<u8>,
+    timeout: u64,
+) -> eyre::Result<Vec<u8>> {
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| eyre!("`command` is empty"))?;
+    let mut child = Command::new(program)
+        .args(args)
+        .current_dir(cwd)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .wrap_err_with(|| eyre!("failed to run `{}`", program))?;
+
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| eyre!("failed to open stdin"))?;
+    let mut stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| eyre!("failed to open stdout"))?;
+    let mut stderr = child
+        .stderr
+        .take()
+        .ok_or_else(|| eyre!("failed to open stderr"))?;
+    // A command may exit without reading all of stdin, so write errors are ignored
+    // and the exit status decides the outcome.
+    thread::spawn(move || {
+        let _ = stdin.write_all(&input);
+    });
+    let stdout = thread::spawn(move || {
+        let mut buf = Vec::new();
+        stdout.read_to_end(&mut buf).map(|_| buf)
+    });
+    let stderr = thread::spawn(move || {
+        let mut buf = Vec::new();
+        stderr.read_to_end(&mut buf).map(|_| buf)
+    });
+
+    let deadline = (timeout > 0).then(|| Instant::now() + Duration::from_secs(timeout));
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("timed out after {}s", timeout);
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+
+    let stdout = stdout
+        .join()
+        .map_err(|_| eyre!("stdout reader panicked"))?
+        .wrap_err("failed to read stdout")?;
+    let stderr = stderr
+        .join()
+        .map_err(|_| eyre!("stderr reader panicked"))?
+        .wrap_err("failed to read stderr")?;
+    let stderr = String::from_utf8_lossy(&stderr);
+
+    if !status.success() {
+        bail!("exited with {}:\n{}", status, stderr.trim_end());
+    }
+    if !stderr.trim().is_empty() {
+        eprintln!("{}", stderr.trim_end());
+    }
+    Ok(stdout)
+}
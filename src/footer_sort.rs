@@ -4,11 +4,29 @@
 
 use std::cmp::Ordering;
 
+use crate::entry::{KEY_CREATED, KEY_MODIFIED, KEY_READING_TIME, KEY_WORD_COUNT};
+
 pub fn compare_values(sort_key: &str, left: &str, right: &str) -> Ordering {
-    if sort_key == "date" {
-        if let (Some(left_date), Some(right_date)) = (parse_date(left), parse_date(right)) {
-            return left_date.cmp(&right_date);
+    match sort_key {
+        "date" => {
+            if let (Some(left_date), Some(right_date)) = (parse_date(left), parse_date(right)) {
+                return left_date.cmp(&right_date);
+            }
+        }
+        KEY_CREATED | KEY_MODIFIED => {
+            if let (Some(left_date), Some(right_date)) = (parse_date(left), parse_date(right)) {
+                // Computed timestamps of the same day still order by time.
+                return left_date.cmp(&right_date).then_with(|| left.cmp(right));
+            }
         }
+        KEY_WORD_COUNT | KEY_READING_TIME => {
+            if let (Ok(left_num), Ok(right_num)) =
+                (left.trim().parse::<u64>(), right.trim().parse::<u64>())
+            {
+                return left_num.cmp(&right_num);
+            }
+        }
+        _ => {}
     }
 
     left.cmp(right)
@@ -109,7 +127,7 @@ fn validate_ymd(year: u32, month: u8, day: u8) -> Option<(u32, u8, u8)> {
         1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
         4 | 6 | 9 | 11 => 30,
         2 => {
-            if year % 400 == 0 || (year % 4 == 0 && year % 100 != 0) {
+            if year.is_multiple_of(400) || (year.is_multiple_of(4) && !year.is_multiple_of(100)) {
                 29
             } else {
                 28
@@ -141,4 +159,18 @@ mod tests {
         assert_eq!(compare_values("date", "unknown", "zzz"), Ordering::Less);
         assert_eq!(compare_values("title", "b", "a"), Ordering::Greater);
     }
+
+    #[test]
+    fn test_compare_values_orders_computed_metadata() {
+        assert_eq!(compare_values("word-count", "9", "10"), Ordering::Less);
+        assert_eq!(compare_values("reading-time", "12", "3"), Ordering::Greater);
+        assert_eq!(
+            compare_values("modified", "2025-03-01T08:00:00Z", "2025-03-01T17:30:00Z"),
+            Ordering::Less
+        );
+        assert_eq!(
+            compare_values("created", "March 2, 2025", "2025-03-01T17:30:00Z"),
+            Ordering::Greater
+        );
+    }
 }
@@ -5,11 +5,12 @@
 mod core;
 mod document;
 mod header;
+mod highlight;
 
 pub use core::{
     catalog_item, footnote_reference, html_article_inner, html_catalog_block, html_code_block,
-    html_typst_figure, html_figure_code, html_footer, html_footer_section, html_header_nav,
-    html_inline_typst_span, html_link,
+    html_code_figure, html_display_typst_span, html_figure_code, html_footer, html_footer_section,
+    html_header_nav, html_inline_typst_span, html_link, html_local_graph, html_typst_figure,
 };
 pub use document::{html_doc, html_main_script, html_main_style};
 pub use header::{html_header, HtmlHeaderArgs};
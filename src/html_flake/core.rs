@@ -10,6 +10,8 @@ use crate::{
     slug::Slug,
 };
 
+use super::highlight::{highlight_lines, plain_lines};
+
 const CATALOG_BULLET_SYMBOL: &str = "\u{25A0}"; // ■
 const HEADER_LOGO_PREFIX: &str = "\u{00AB} "; // «
 
@@ -104,8 +106,21 @@ pub fn html_inline_typst_span(svg: &str) -> String {
     html!(span class="inline-typst" { (svg) })
 }
 
-pub fn html_footer(references_html: &str, backlinks_html: &str) -> String {
-    html!(footer { (references_html) (backlinks_html) })
+pub fn html_display_typst_span(svg: &str) -> String {
+    html!(span class="typst-display" { (svg) })
+}
+
+pub fn html_footer(references_html: &str, backlinks_html: &str, local_graph_html: &str) -> String {
+    html!(footer { (references_html) (backlinks_html) (local_graph_html) })
+}
+
+/// The graph is drawn by `main.js` from the inline JSON.
+pub fn html_local_graph(summary: &str, graph_json: &str) -> String {
+    let content = format!(
+        r#"<div class="local-graph-view"></div><script type="application/json" class="local-graph-data">{}</script>"#,
+        graph_json
+    );
+    html_footer_section("local-graph", summary, &content)
 }
 
 pub fn footnote_reference(s: &str, back_id: &str, number: usize) -> String {
@@ -154,9 +169,54 @@ pub fn html_link(href: &str, title: &str, text: &str, class_name: &str) -> Strin
     )
 }
 
+/// Render raw `code` as a `<pre><code>` block, highlighted at build time when
+/// `[build].highlight` is on and numbered when `[build].line-numbers` is on.
+///
 /// Also see [`crate::compiler::parser::tests::test_code_block`]
-pub fn html_code_block(code: &str, language: &str) -> String {
-    html!(pre { code class={format!("language-{}", language)} { (code) } })
+/// Render a code block whose first line is line `first_line` of its source.
+pub fn html_code_block(code: &str, language: &str, first_line: usize) -> String {
+    let lines = match environment::highlight() {
+        true => highlight_lines(code, language),
+        false => plain_lines(code),
+    };
+    let code_class = match language.is_empty() {
+        true => String::new(),
+        false => format!(
+            r#" class="language-{}""#,
+            htmlize::escape_attribute(language)
+        ),
+    };
+
+    let mut html = String::new();
+    if environment::line_numbers() {
+        // The counter is incremented before each line is numbered.
+        let counter = match first_line > 1 {
+            true => format!(r#" style="counter-reset: line {}""#, first_line - 1),
+            false => String::new(),
+        };
+        html.push_str(&format!(
+            r#"<pre class="line-numbers"><code{code_class}{counter}>"#
+        ));
+        for line in lines {
+            html.push_str(&format!(r#"<span class="line">{line}</span>"#));
+            html.push('\n');
+        }
+    } else {
+        html.push_str(&format!("<pre><code{code_class}>"));
+        html.push_str(&lines.join("\n"));
+        if code.ends_with('\n') {
+            html.push('\n');
+        }
+    }
+    html.push_str("</code></pre>");
+    html
+}
+
+pub fn html_code_figure(code_block: &str, caption: &str, source: &str) -> String {
+    html!(figure class="code-excerpt" {
+        (code_block)
+        figcaption { (caption) " " span class="code-source" { (source) } }
+    })
 }
 
 pub fn html_header_nav(title: &str, page_title: &str, href: &str) -> String {
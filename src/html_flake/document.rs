@@ -151,18 +151,20 @@ pub fn html_main_script() -> &'static str {
 fn html_import_theme() -> String {
     environment::theme_paths()
         .iter()
-        .map(|theme_path| match std::fs::read_to_string(theme_path) {
-            Ok(content) => content,
-            Err(err) => {
-                color_print::ceprintln!(
-                    "<y>Warning: Failed to read theme file at '{}': {}</>",
-                    theme_path,
-                    err
-                );
-
-                String::new()
-            }
-        })
+        .map(
+            |theme_path| match environment::fs().read_to_string(theme_path) {
+                Ok(content) => content,
+                Err(err) => {
+                    color_print::ceprintln!(
+                        "<y>Warning: Failed to read theme file at '{}': {}</>",
+                        theme_path,
+                        err
+                    );
+
+                    String::new()
+                }
+            },
+        )
         .collect()
 }
 
@@ -2,6 +2,8 @@
 // Released under the GPL-3.0 license as described in the file LICENSE.
 // Authors: Kokic (@kokic), Spore (@s-cerevisiae)
 
+use camino::Utf8PathBuf;
+
 use crate::{
     entry::EntryMetaData,
     environment::{self, input_path},
@@ -84,11 +86,23 @@ pub fn html_header(args: HtmlHeaderArgs<'_>) -> String {
                 }
             }
         }
-        (false, _, Some(prefix)) => {
-            let source_path = format!("{}.{}", source_slug, ext);
-            let editor_url =
-                append_editor_position(format!("{}{}", prefix, source_path), &prefix, source_pos);
-            html!(a class="edit" href={editor_url.to_string()} { (edit_text) })
+        (false, _, deploy_edit) => {
+            // Mounted sources link into their own repository, relative to the mount.
+            let source_path = Utf8PathBuf::from(format!("{}.{}", source_slug, ext));
+            let (mount, inner) = environment::locate_tree_path(&source_path);
+            let prefix = if mount.is_main() {
+                deploy_edit
+            } else {
+                mount.edit
+            };
+            match prefix {
+                Some(prefix) => {
+                    let editor_url =
+                        append_editor_position(format!("{}{}", prefix, inner), &prefix, source_pos);
+                    html!(a class="edit" href={editor_url.to_string()} { (edit_text) })
+                }
+                None => String::default(),
+            }
         }
         _ => String::default(),
     };
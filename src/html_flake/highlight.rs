@@ -0,0 +1,122 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+use std::sync::OnceLock;
+
+use syntect::{
+    html::{line_tokens_to_classed_spans, ClassStyle},
+    parsing::{ParseState, Scope, ScopeStack, SyntaxReference, SyntaxSet},
+    util::LinesWithEndings,
+};
+
+/// Prefix of the token classes, e.g. `hl-comment`, styled by `main.css`.
+pub const HIGHLIGHT_CLASS_PREFIX: &str = "hl-";
+
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed {
+    prefix: HIGHLIGHT_CLASS_PREFIX,
+};
+
+fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAX_SET: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAX_SET.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn find_syntax(language: &str) -> Option<&'static SyntaxReference> {
+    let language = language.trim();
+    if language.is_empty() {
+        return None;
+    }
+    syntax_set().find_syntax_by_token(language)
+}
+
+/// Split `code` into escaped HTML lines, without line terminators.
+///
+/// When `language` names a known syntax, tokens are wrapped in `hl-*` class spans.
+/// Every line closes the spans it opens, so lines can be wrapped individually.
+pub fn highlight_lines(code: &str, language: &str) -> Vec<String> {
+    let Some(syntax) = find_syntax(language) else {
+        return plain_lines(code);
+    };
+
+    let mut parse_state = ParseState::new(syntax);
+    let mut scopes = ScopeStack::new();
+    let mut result = Vec::new();
+    for line in LinesWithEndings::from(code) {
+        let highlighted = parse_state
+            .parse_line(line, syntax_set())
+            .ok()
+            .and_then(|ops| {
+                let mut html: String = scopes.as_slice().iter().map(|s| open_span(*s)).collect();
+                let (tokens, _) =
+                    line_tokens_to_classed_spans(line, &ops, CLASS_STYLE, &mut scopes).ok()?;
+                html.push_str(&tokens);
+                Some(html)
+            });
+        let Some(mut html) = highlighted else {
+            // A syntax that fails to parse leaves the whole block unhighlighted.
+            return plain_lines(code);
+        };
+        if let Some(pos) = html.rfind('\n') {
+            html.remove(pos);
+        }
+        html.push_str(&"</span>".repeat(scopes.len()));
+        result.push(html);
+    }
+    result
+}
+
+/// Split `code` into escaped HTML lines without highlighting.
+pub fn plain_lines(code: &str) -> Vec<String> {
+    LinesWithEndings::from(code).map(escape_line).collect()
+}
+
+fn escape_line(line: &str) -> String {
+    htmlize::escape_text(line.trim_end_matches(['\n', '\r'])).into_owned()
+}
+
+fn open_span(scope: Scope) -> String {
+    let classes: Vec<String> = scope
+        .build_string()
+        .split('.')
+        .map(|atom| format!("{HIGHLIGHT_CLASS_PREFIX}{atom}"))
+        .collect();
+    format!(r#"<span class="{}">"#, classes.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_lines_wraps_tokens_in_classes() {
+        let lines = highlight_lines("// note\nlet x = \"<a>\";\n", "rs");
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains(r#"class="hl-comment"#), "{}", lines[0]);
+        assert!(lines[1].contains("hl-storage"), "{}", lines[1]);
+        assert!(lines[1].contains("&lt;a&gt;"), "{}", lines[1]);
+        assert!(lines.iter().all(|line| !line.contains('\n')));
+    }
+
+    #[test]
+    fn test_highlight_lines_balances_spans_across_lines() {
+        let lines = highlight_lines("/* a\nb */ x\n", "rust");
+        for line in &lines {
+            assert_eq!(
+                line.matches("<span").count(),
+                line.matches("</span>").count(),
+                "{line}"
+            );
+        }
+        assert!(lines[1].starts_with(r#"<span class="hl-source hl-rust"><span class="hl-comment"#));
+    }
+
+    #[test]
+    fn test_highlight_lines_escapes_unknown_languages() {
+        assert_eq!(
+            highlight_lines("a < b\n", "no-such-language"),
+            vec!["a &lt; b".to_string()]
+        );
+        assert_eq!(highlight_lines("x\ny", ""), vec!["x", "y"]);
+    }
+}
@@ -3,7 +3,7 @@
 // Authors: Spore (@s-cerevisiae)
 
 /// Example:
-/// ```rust
+/// ```ignore
 /// let mut s = "a";
 /// let html = html_write!(&mut s; br);
 /// assert_eq!(html, r#"a<br />"#)
@@ -51,7 +51,7 @@ macro_rules! html_write {
 }
 
 /// Example:
-/// ```rust
+/// ```ignore
 /// let value = 1;
 /// let id = "some_id";
 /// let html = html!(
@@ -13,6 +13,21 @@
   repr(r)
 }
 
+// Dates use their ISO form so that they sort as text.
+#let meta-text(value) = if type(value) == datetime { value.display() } else { repri(value) }
+
+// Arrays and dictionaries are passed to Kodama as JSON.
+#let meta-json(value) = if type(value) == array {
+  if value.len() == 0 { "[]" } else { "[" + value.map(meta-json).join(",") + "]" }
+} else if type(value) == dictionary {
+  if value.len() == 0 { "{}" } else {
+    "{" + value.pairs().map(((k, v)) => meta-json(k) + ":" + meta-json(v)).join(",") + "}"
+  }
+} else {
+  let s = meta-text(value)
+  "\"" + s.replace("\\", "\\\\").replace("\"", "\\\"").replace("\n", "\\n") + "\""
+}
+
 #let with-target-check(callback) = context {
   let target-value = if "target" in dictionary(std) { std.target() } else { "paged" }
   callback(target-value)
@@ -80,9 +95,12 @@
           let v = value
           let attrs = (key: e.at(0))
 
-          if type(value) != content {
+          if type(value) == array or type(value) == dictionary {
+            v = none
+            attrs.insert("json", meta-json(value))
+          } else if type(value) != content {
             v = none
-            attrs.insert("value", repri(value))
+            attrs.insert("value", meta-text(value))
           }
           html.elem("kodama-meta", v, attrs: attrs)
         }).join()
@@ -360,3 +360,137 @@ function applyDynamicColorInvert() {
     }
   }
 }
+
+// Local graph: a force-directed SVG of the page's neighborhood, drawn from inline JSON.
+const SVG_NS = "http://www.w3.org/2000/svg";
+const LOCAL_GRAPH_HEIGHT = 320;
+const LOCAL_GRAPH_LABEL_LENGTH = 24;
+
+function svgElement(name, attributes = {}) {
+  const element = document.createElementNS(SVG_NS, name);
+  for (const [key, value] of Object.entries(attributes)) {
+    element.setAttribute(key, value);
+  }
+  return element;
+}
+
+// Fruchterman-Reingold layout; the current page stays in the center.
+function layoutLocalGraph(nodes, edges, width, height) {
+  const k = Math.sqrt((width * height) / nodes.length) * 0.6;
+  nodes.forEach((node, i) => {
+    const angle = (2 * Math.PI * i) / nodes.length;
+    const radius = node.depth * k * 0.8;
+    node.x = width / 2 + radius * Math.cos(angle);
+    node.y = height / 2 + radius * Math.sin(angle);
+  });
+
+  let temperature = width / 10;
+  for (let step = 0; step < 300; step++) {
+    nodes.forEach((node) => (node.dx = node.dy = 0));
+    for (let i = 0; i < nodes.length; i++) {
+      for (let j = i + 1; j < nodes.length; j++) {
+        const a = nodes[i], b = nodes[j];
+        const x = a.x - b.x, y = a.y - b.y;
+        const distance = Math.max(Math.hypot(x, y), 0.01);
+        const force = (k * k) / distance;
+        a.dx += (x / distance) * force; a.dy += (y / distance) * force;
+        b.dx -= (x / distance) * force; b.dy -= (y / distance) * force;
+      }
+    }
+    edges.forEach(({ from, to }) => {
+      const x = from.x - to.x, y = from.y - to.y;
+      const distance = Math.max(Math.hypot(x, y), 0.01);
+      const force = (distance * distance) / k;
+      from.dx -= (x / distance) * force; from.dy -= (y / distance) * force;
+      to.dx += (x / distance) * force; to.dy += (y / distance) * force;
+    });
+    nodes.forEach((node) => {
+      if (node.depth === 0) {
+        node.x = width / 2;
+        node.y = height / 2;
+        return;
+      }
+      const length = Math.max(Math.hypot(node.dx, node.dy), 0.01);
+      node.x += (node.dx / length) * Math.min(length, temperature);
+      node.y += (node.dy / length) * Math.min(length, temperature);
+      node.x = Math.max(16, Math.min(width - 16, node.x));
+      node.y = Math.max(16, Math.min(height - 16, node.y));
+    });
+    temperature *= 0.98;
+  }
+}
+
+function renderLocalGraph(view, data) {
+  const width = Math.max(view.clientWidth, 300);
+  const height = LOCAL_GRAPH_HEIGHT;
+  const nodes = data.nodes.map((node) => ({ ...node }));
+  const bySlug = new Map(nodes.map((node) => [node.slug, node]));
+  const edges = data.edges
+    .map((edge) => ({ kind: edge.kind, from: bySlug.get(edge.source), to: bySlug.get(edge.target) }))
+    .filter((edge) => edge.from && edge.to);
+  layoutLocalGraph(nodes, edges, width, height);
+
+  const svg = svgElement("svg", { viewBox: `0 0 ${width} ${height}`, role: "img" });
+  edges.forEach((edge) => {
+    edge.line = svgElement("line", { class: `local-graph-edge ${edge.kind}` });
+    svg.appendChild(edge.line);
+  });
+
+  const place = () => {
+    edges.forEach(({ line, from, to }) => {
+      line.setAttribute("x1", from.x); line.setAttribute("y1", from.y);
+      line.setAttribute("x2", to.x); line.setAttribute("y2", to.y);
+    });
+    nodes.forEach((node) => node.group.setAttribute("transform", `translate(${node.x},${node.y})`));
+  };
+
+  const toSvgPoint = (event) => {
+    const point = new DOMPoint(event.clientX, event.clientY);
+    return point.matrixTransform(svg.getScreenCTM().inverse());
+  };
+
+  nodes.forEach((node) => {
+    const link = svgElement("a", { href: node.url });
+    node.group = svgElement("g", { class: node.depth === 0 ? "local-graph-node current" : "local-graph-node" });
+    const label = node.title.length > LOCAL_GRAPH_LABEL_LENGTH
+      ? `${node.title.slice(0, LOCAL_GRAPH_LABEL_LENGTH - 1)}…`
+      : node.title;
+    const tooltip = svgElement("title");
+    tooltip.textContent = node.taxon ? `${node.taxon}: ${node.title}` : node.title;
+    const text = svgElement("text", { y: -10 });
+    text.textContent = label;
+    node.group.append(tooltip, svgElement("circle", { r: node.depth === 0 ? 7 : 5 }), text);
+    link.appendChild(node.group);
+    svg.appendChild(link);
+
+    // Dragging moves a node; only a click without movement follows the link.
+    let dragging = false, moved = false;
+    node.group.addEventListener("pointerdown", (event) => {
+      dragging = true;
+      moved = false;
+      node.group.setPointerCapture(event.pointerId);
+    });
+    node.group.addEventListener("pointermove", (event) => {
+      if (!dragging) return;
+      const point = toSvgPoint(event);
+      node.x = point.x;
+      node.y = point.y;
+      moved = true;
+      place();
+    });
+    node.group.addEventListener("pointerup", () => (dragging = false));
+    link.addEventListener("click", (event) => {
+      if (moved) event.preventDefault();
+    });
+  });
+
+  place();
+  view.replaceChildren(svg);
+}
+
+document.addEventListener("DOMContentLoaded", function () {
+  document.querySelectorAll(".local-graph-data").forEach((script) => {
+    const view = script.parentElement.querySelector(".local-graph-view");
+    if (view) renderLocalGraph(view, JSON.parse(script.textContent));
+  });
+});
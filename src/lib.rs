@@ -0,0 +1,39 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+//! Kodama compiles a forest of Markdown and Typst sections into a static site.
+//! See [`api`] for embedding the compiler; the `kodama` binary only calls
+//! [`run_cli`].
+
+pub mod api;
+mod assets_sync;
+mod atomic_text;
+mod cli;
+mod compiler;
+mod config;
+mod entry;
+mod environment;
+mod external_command;
+mod footer_sort;
+mod html_flake;
+mod html_macro;
+mod ordered_map;
+mod path_utils;
+mod process;
+mod recorder;
+mod slug;
+#[cfg(test)]
+mod test_io;
+mod typst_cli;
+pub mod vfs;
+
+pub use api::{
+    BuildMode, CompileOutputs, CompileState, Config, Context, Section, Slug, UnresolvedSection,
+    UnresolvedSections, Workspace,
+};
+
+/// Parse the command-line arguments and run the `kodama` command they select.
+pub fn run_cli() -> eyre::Result<()> {
+    cli::run()
+}
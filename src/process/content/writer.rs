@@ -10,6 +10,7 @@ use pulldown_cmark::{
 use pulldown_cmark_escape::{escape_href, escape_html, escape_html_body_text};
 
 use crate::compiler::section::{LazyContent, LazyContents};
+use crate::html_flake::html_code_block;
 use crate::process::url::{is_allowed_scheme, is_unsafe_scheme, scheme_name};
 
 use super::EventExtended;
@@ -50,6 +51,8 @@ pub(super) struct HtmlWriter<'e, I> {
     numbers: HashMap<CowStr<'e>, usize>,
     in_paragraph: bool,
     paragraph_started: bool,
+    /// Language and text of the code block being collected, if any.
+    code_block: Option<(String, String)>,
 }
 
 impl<'e, I> HtmlWriter<'e, I>
@@ -69,6 +72,7 @@ where
             numbers: HashMap::new(),
             in_paragraph: false,
             paragraph_started: false,
+            code_block: None,
         }
     }
 
@@ -157,6 +161,11 @@ where
                 End(tag) => {
                     self.end_tag(tag);
                 }
+                Text(text) if self.code_block.is_some() => {
+                    if let Some((_, code)) = self.code_block.as_mut() {
+                        code.push_str(&text);
+                    }
+                }
                 Text(text) => {
                     if !self.in_non_writing_block {
                         self.ensure_paragraph_started();
@@ -325,19 +334,11 @@ where
                 if !self.end_newline {
                     self.write_newline();
                 }
-                match info {
-                    CodeBlockKind::Fenced(info) => {
-                        let lang = info.split(' ').next().unwrap();
-                        if lang.is_empty() {
-                            self.write("<pre><code>")
-                        } else {
-                            self.write("<pre><code class=\"language-");
-                            escape_html(self.writer(), lang).unwrap();
-                            self.write("\">")
-                        }
-                    }
-                    CodeBlockKind::Indented => self.write("<pre><code>"),
-                }
+                let lang = match info {
+                    CodeBlockKind::Fenced(info) => info.split(' ').next().unwrap().to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                self.code_block = Some((lang, String::new()));
             }
             Tag::List(Some(1)) => {
                 if self.end_newline {
@@ -503,7 +504,9 @@ where
                 self.write("</blockquote>\n");
             }
             TagEnd::CodeBlock => {
-                self.write("</code></pre>\n");
+                let (lang, code) = self.code_block.take().unwrap_or_default();
+                self.write(&html_code_block(&code, &lang, 1));
+                self.write_newline();
             }
             TagEnd::List(true) => {
                 self.write("</ol>\n");
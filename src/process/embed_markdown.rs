@@ -4,19 +4,23 @@
 
 use super::{
     content::EventExtended,
+    include_excerpt::{select_excerpt, split_include_url, whole_file, IncludeRange},
     path_resolution::{relocate_trees_path_with_trees_root, resolve_section_url},
     processor::url_action,
     url::{is_allowed_scheme, is_unsafe_scheme, scheme_name},
 };
 use std::{
-    fs, mem,
+    mem,
     sync::atomic::{AtomicBool, Ordering},
 };
 
 use crate::{
-    compiler::section::{EmbedContent, HTMLContent, LocalLink, SectionOption},
-    environment::{assets_dir_without_root, root_dir, trees_dir_without_root},
-    html_flake::{html_code_block, html_link},
+    compiler::{
+        self,
+        section::{EmbedContent, HTMLContent, LocalLink, SectionOption},
+    },
+    environment::{self, assets_dir_without_root, root_dir, trees_dir_without_root},
+    html_flake::{html_code_block, html_code_figure, html_link},
     path_utils,
     process::typst_image::is_inline_typst,
     recorder::State,
@@ -35,10 +39,24 @@ pub fn include_error_detected() -> bool {
     INCLUDE_READ_ERROR_FLAG.load(Ordering::Relaxed)
 }
 
-fn record_include_error() {
+pub(crate) fn record_include_error() {
     INCLUDE_READ_ERROR_FLAG.store(true, Ordering::Relaxed);
 }
 
+static INCLUDE_RANGE_WARNING_FLAG: AtomicBool = AtomicBool::new(false);
+
+pub fn reset_include_warning_flag() {
+    INCLUDE_RANGE_WARNING_FLAG.store(false, Ordering::Relaxed);
+}
+
+pub fn include_warning_detected() -> bool {
+    INCLUDE_RANGE_WARNING_FLAG.load(Ordering::Relaxed)
+}
+
+fn record_include_warning() {
+    INCLUDE_RANGE_WARNING_FLAG.store(true, Ordering::Relaxed);
+}
+
 pub struct Embed<'e, E> {
     events: E,
     current_slug: Slug,
@@ -47,6 +65,9 @@ pub struct Embed<'e, E> {
     state: State,
     url: Option<String>,
     content: Vec<Event<'e>>,
+    /// The `#L10-L42` or `#region` fragment and the link title of an include.
+    include_range: Option<IncludeRange>,
+    include_title: String,
 }
 
 impl<'e, E> Embed<'e, E> {
@@ -73,6 +94,8 @@ impl<'e, E> Embed<'e, E> {
             state: State::None,
             url: None,
             content: Vec::new(),
+            include_range: None,
+            include_title: String::new(),
         }
     }
 
@@ -91,7 +114,11 @@ impl<'e, E: Iterator<Item = Event<'e>>> Iterator for Embed<'e, E> {
     fn next(&mut self) -> Option<Self::Item> {
         for e in self.events.by_ref() {
             match e {
-                Event::Start(Tag::Link { ref dest_url, .. }) => {
+                Event::Start(Tag::Link {
+                    ref dest_url,
+                    ref title,
+                    ..
+                }) => {
                     let (url, action) = url_action(dest_url);
                     if !is_safe_link_target(&url) {
                         self.state = State::UnsafeLink;
@@ -104,8 +131,11 @@ impl<'e, E: Iterator<Item = Event<'e>>> Iterator for Embed<'e, E> {
                         ));
                     // [0]
                     } else if action == State::Include.strify() {
+                        let (path, range) = split_include_url(&url);
                         self.state = State::Include;
-                        self.url = Some(resolve_include_url(&url, self.current_slug));
+                        self.url = Some(resolve_include_url(path, self.current_slug.as_str()));
+                        self.include_range = range;
+                        self.include_title = title.to_string();
                     } else if is_external_link(&url) {
                         self.state = State::ExternalLink;
                         self.url = Some(url);
@@ -154,19 +184,15 @@ impl<'e, E: Iterator<Item = Event<'e>>> Iterator for Embed<'e, E> {
                             Some(text)
                         };
 
-                        let include_path = root_dir().join(&url);
-                        let content = fs::read_to_string(&include_path).unwrap_or_else(|err| {
-                            record_include_error();
-                            color_print::ceprintln!(
-                                "<y>Warning: failed to include file `{}` resolved to `{}`: {}</>",
-                                url,
-                                include_path,
-                                err
-                            );
-                            format!("failed to include file: {url}")
-                        });
-                        let escaped = htmlize::escape_text(content);
-                        let html = html_code_block(&escaped, &language_tag.unwrap_or_default());
+                        let range = self.include_range.take();
+                        let title = mem::take(&mut self.include_title);
+                        let html = render_include(
+                            &url,
+                            range.as_ref(),
+                            &title,
+                            &language_tag.unwrap_or_default(),
+                            self.current_slug,
+                        );
                         return Some(Event::Html(html.into()).into());
                     }
                     State::LocalLink => {
@@ -262,11 +288,66 @@ fn resolve_local_link_url_with_trees_root(
     relocate_trees_path_with_trees_root(&resolved, trees_dir_without_root)
 }
 
-fn resolve_include_url(raw_url: &str, current_slug: Slug) -> String {
+/// Read an include as a code block, captioned with its source when the link has a title.
+fn render_include(
+    url: &str,
+    range: Option<&IncludeRange>,
+    title: &str,
+    language: &str,
+    current_slug: Slug,
+) -> String {
+    compiler::record_root_dependency(url);
+    let include_path = root_dir().join(url);
+    let content = match environment::fs().read_to_string(&include_path) {
+        Ok(content) => content,
+        Err(err) => {
+            record_include_error();
+            color_print::ceprintln!(
+                "<y>Warning: failed to include file `{}` resolved to `{}`: {}</>",
+                url,
+                include_path,
+                err
+            );
+            return html_code_block(&format!("failed to include file: {url}"), language, 1);
+        }
+    };
+
+    let excerpt = select_excerpt(&content, range).unwrap_or_else(|reason| {
+        record_include_warning();
+        color_print::ceprintln!(
+            "<y>Warning: {} in `{}` at {}, including the whole file</>",
+            reason,
+            url,
+            current_slug
+        );
+        whole_file(&content)
+    });
+    let html = html_code_block(&excerpt.code, language, excerpt.first_line);
+    if title.is_empty() {
+        return html;
+    }
+
+    let source = match range {
+        Some(_) if excerpt.first_line == excerpt.last_line => {
+            format!("{}:{}", url, excerpt.first_line)
+        }
+        Some(_) => format!("{}:{}-{}", url, excerpt.first_line, excerpt.last_line),
+        None => url.to_string(),
+    };
+    html_code_figure(
+        &html,
+        &htmlize::escape_text(title),
+        &htmlize::escape_text(source),
+    )
+}
+
+/// Resolve an include URL to a project-root-relative path. Relative URLs are
+/// resolved against `base`, a slug or the root-relative path of the including file.
+pub(crate) fn resolve_include_url(raw_url: &str, base: &str) -> String {
     let path = if raw_url.starts_with('/') {
         Utf8PathBuf::from(raw_url.trim_start_matches('/'))
     } else {
-        path_utils::relative_to_current(current_slug.as_str(), raw_url)
+        path_utils::relative_to_current(base, raw_url)
     };
     path_utils::pretty_path(path.as_path())
 }
@@ -422,15 +503,15 @@ mod tests {
     #[test]
     fn test_resolve_include_url_supports_root_and_relative_paths() {
         assert_eq!(
-            resolve_include_url("/import-meta.html", Slug::new("a/b")),
+            resolve_include_url("/import-meta.html", "a/b"),
             "import-meta.html"
         );
         assert_eq!(
-            resolve_include_url("./shared/snippet.txt", Slug::new("docs/chapter")),
+            resolve_include_url("./shared/snippet.txt", "docs/chapter"),
             "docs/shared/snippet.txt"
         );
         assert_eq!(
-            resolve_include_url("../snippet.txt", Slug::new("docs/chapter")),
+            resolve_include_url("../snippet.txt", "docs/chapter"),
             "snippet.txt"
         );
     }
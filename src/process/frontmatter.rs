@@ -0,0 +1,471 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+//! Frontmatter parsing: a YAML subset for `---` blocks and TOML for `+++` blocks.
+//!
+//! The YAML subset covers `key: value` lines, flow lists `[a, b]` and tables
+//! `{a: 1}`, block lists and nested tables by indentation, `|` and `>` block
+//! scalars, quoted strings and `#` comment lines. Anything else is kept as text:
+//! a top-level value is only structured when the whole value is a flow list or
+//! table that parses, and otherwise stays verbatim, quotes included.
+
+use eyre::{eyre, WrapErr};
+
+use crate::{entry::MetaValue, ordered_map::OrderedMap, slug::Slug};
+
+/// A top-level frontmatter value. Scalars are later rendered like before, as
+/// plain text or inline Markdown depending on the key.
+#[derive(Debug, PartialEq)]
+pub enum Field {
+    Scalar(String),
+    Structured(MetaValue),
+}
+
+impl Field {
+    fn into_meta_value(self) -> MetaValue {
+        match self {
+            Field::Scalar(text) => MetaValue::Text(text),
+            Field::Structured(value) => value,
+        }
+    }
+}
+
+pub fn parse_toml(source: &str, slug: Slug) -> eyre::Result<Vec<(String, Field)>> {
+    let table: toml::Table =
+        toml::from_str(source).wrap_err_with(|| eyre!("invalid TOML metadata in `{}`", slug))?;
+    Ok(table
+        .into_iter()
+        .map(|(key, value)| {
+            let field = match value {
+                toml::Value::String(text) => Field::Scalar(text),
+                value @ (toml::Value::Array(_) | toml::Value::Table(_)) => {
+                    Field::Structured(MetaValue::from(value))
+                }
+                // Numbers, booleans and dates keep their TOML spelling.
+                value => Field::Scalar(value.to_string()),
+            };
+            (key, field)
+        })
+        .collect())
+}
+
+pub fn parse_yaml(source: &str, slug: Slug) -> eyre::Result<Vec<(String, Field)>> {
+    let mut parser = Yaml {
+        lines: source.lines().collect(),
+        pos: 0,
+        slug,
+    };
+    parser.mapping(0, true)
+}
+
+struct Yaml<'a> {
+    lines: Vec<&'a str>,
+    pos: usize,
+    slug: Slug,
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+fn is_ignored(line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty() || line.starts_with('#')
+}
+
+impl Yaml<'_> {
+    fn error(&self, index: usize, reason: String) -> eyre::Report {
+        eyre!(
+            "invalid metadata in `{}` at line {}: {}",
+            self.slug,
+            index + 1,
+            reason
+        )
+    }
+
+    fn skip_ignored(&mut self) {
+        while self
+            .lines
+            .get(self.pos)
+            .is_some_and(|line| is_ignored(line))
+        {
+            self.pos += 1;
+        }
+    }
+
+    /// Indentation of the next meaningful line, if any.
+    fn peek_indent(&mut self) -> Option<usize> {
+        self.skip_ignored();
+        self.lines.get(self.pos).map(|line| indent_of(line))
+    }
+
+    /// `key: value` lines at exactly `indent`. The top level splits at the first
+    /// `:` like earlier versions and, like them, also reads stray indented lines
+    /// as `key: value`; nested tables need `: ` so URLs stay text.
+    fn mapping(&mut self, indent: usize, top_level: bool) -> eyre::Result<Vec<(String, Field)>> {
+        let mut entries = Vec::new();
+        while let Some(current) = self.peek_indent() {
+            if current != indent && !top_level {
+                break;
+            }
+            let index = self.pos;
+            let line = self.lines[index].trim();
+            let split = match top_level {
+                true => line.split_once(':'),
+                false => split_key(line),
+            };
+            let Some((key, rest)) = split else {
+                return Err(self.error(index, format!("expected `name: value`, found `{}`", line)));
+            };
+            self.pos += 1;
+            let rest = rest.trim();
+            let value = match top_level && !rest.is_empty() && !is_block_indicator(rest) {
+                true => parse_flow(rest)
+                    .map_or_else(|| Field::Scalar(rest.to_string()), Field::Structured),
+                false => self.value(rest, current)?,
+            };
+            entries.push((unquote(key.trim()), value));
+        }
+        Ok(entries)
+    }
+
+    /// The value after `key:` or `-`, possibly continued on deeper lines.
+    fn value(&mut self, rest: &str, indent: usize) -> eyre::Result<Field> {
+        match rest {
+            "" => {
+                let Some(child) = self.peek_indent().filter(|child| *child > indent) else {
+                    return Ok(Field::Scalar(String::new()));
+                };
+                let line = self.lines[self.pos].trim_start();
+                if line == "-" || line.starts_with("- ") {
+                    Ok(Field::Structured(MetaValue::List(self.sequence(child)?)))
+                } else {
+                    let entries = self.mapping(child, false)?;
+                    Ok(Field::Structured(into_map(entries)))
+                }
+            }
+            _ if is_block_indicator(rest) => Ok(Field::Scalar(self.block_scalar(rest, indent))),
+            _ => Ok(parse_flow_or_text(rest)),
+        }
+    }
+
+    /// `- item` lines at exactly `indent`.
+    fn sequence(&mut self, indent: usize) -> eyre::Result<Vec<MetaValue>> {
+        let mut items = Vec::new();
+        while self.peek_indent() == Some(indent) {
+            let line = self.lines[self.pos].trim();
+            let Some(item) = line.strip_prefix('-') else {
+                break;
+            };
+            if !(item.is_empty() || item.starts_with(' ')) {
+                break;
+            }
+            let item = item.trim();
+            self.pos += 1;
+            // Content of an item is aligned after `- `.
+            let item_indent = indent + 2;
+            let value = match split_key(item) {
+                Some((key, rest)) if !item.starts_with(['"', '\'', '[', '{']) => {
+                    let mut entries =
+                        vec![(unquote(key.trim()), self.value(rest.trim(), item_indent)?)];
+                    if self.peek_indent() == Some(item_indent) {
+                        entries.extend(self.mapping(item_indent, false)?);
+                    }
+                    into_map(entries)
+                }
+                _ => self.value(item, indent)?.into_meta_value(),
+            };
+            items.push(value);
+        }
+        Ok(items)
+    }
+
+    /// Lines of a `|` (literal) or `>` (folded) block deeper than `indent`.
+    fn block_scalar(&mut self, style: &str, indent: usize) -> String {
+        let mut lines = Vec::new();
+        while let Some(line) = self.lines.get(self.pos) {
+            if !line.trim().is_empty() && indent_of(line) <= indent {
+                break;
+            }
+            lines.push(*line);
+            self.pos += 1;
+        }
+        while lines.last().is_some_and(|line| line.trim().is_empty()) {
+            lines.pop();
+        }
+        let block_indent = lines
+            .iter()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| indent_of(line))
+            .min()
+            .unwrap_or(0);
+        let lines = lines
+            .iter()
+            .map(|line| line.get(block_indent..).unwrap_or("").trim_end());
+
+        if style.starts_with('|') {
+            return lines.collect::<Vec<_>>().join("\n");
+        }
+        // Folded: single line breaks become spaces, blank lines become breaks.
+        let mut folded = String::new();
+        for line in lines {
+            if line.is_empty() {
+                folded.push('\n');
+            } else {
+                if !folded.is_empty() && !folded.ends_with('\n') {
+                    folded.push(' ');
+                }
+                folded.push_str(line);
+            }
+        }
+        folded
+    }
+}
+
+fn into_map(entries: Vec<(String, Field)>) -> MetaValue {
+    MetaValue::Map(
+        entries
+            .into_iter()
+            .map(|(key, field)| (key, field.into_meta_value()))
+            .collect::<OrderedMap<_, _>>(),
+    )
+}
+
+/// Split `key: value` or `key:`, but not `https://...`.
+fn split_key(text: &str) -> Option<(&str, &str)> {
+    if let Some(key) = text.strip_suffix(':') {
+        return (!key.is_empty()).then_some((key, ""));
+    }
+    text.split_once(": ").filter(|(key, _)| !key.is_empty())
+}
+
+fn is_block_indicator(text: &str) -> bool {
+    matches!(text, "|" | "|-" | "|+" | ">" | ">-" | ">+")
+}
+
+/// A nested single-line value: a flow list, a flow table, or unquoted text.
+fn parse_flow_or_text(text: &str) -> Field {
+    parse_flow(text).map_or_else(|| Field::Scalar(unquote(text)), Field::Structured)
+}
+
+/// A flow list `[a, b]` or table `{a: 1}` spanning the whole of `text`, so
+/// `[WIP] Foo` or `[a] and [b]` are not mistaken for lists.
+fn parse_flow(text: &str) -> Option<MetaValue> {
+    if let Some(inner) = text.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+        let items = split_flow(inner)?
+            .into_iter()
+            .map(|item| parse_flow_or_text(item).into_meta_value())
+            .collect();
+        return Some(MetaValue::List(items));
+    }
+    if let Some(inner) = text.strip_prefix('{').and_then(|t| t.strip_suffix('}')) {
+        let entries = split_flow(inner)?
+            .into_iter()
+            .map(|entry| {
+                let (key, value) = split_key(entry)?;
+                Some((unquote(key.trim()), parse_flow_or_text(value.trim())))
+            })
+            .collect::<Option<_>>()?;
+        return Some(into_map(entries));
+    }
+    None
+}
+
+/// Split flow items at top-level commas, respecting quotes and nesting.
+/// Returns `None` when brackets or quotes are unbalanced.
+fn split_flow(inner: &str) -> Option<Vec<&str>> {
+    let mut items = Vec::new();
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut start = 0;
+    let mut prev = None;
+    for (i, c) in inner.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            // Only a quote opening a value starts a string, not `Bob's`.
+            (None, '"' | '\'') if matches!(prev, None | Some('[' | '{' | ',' | ':')) => {
+                quote = Some(c)
+            }
+            (None, '[' | '{') => depth += 1,
+            (None, ']' | '}') => depth = depth.checked_sub(1)?,
+            (None, ',') if depth == 0 => {
+                items.push(inner[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+        if !c.is_whitespace() {
+            prev = Some(c);
+        }
+    }
+    if depth != 0 || quote.is_some() {
+        return None;
+    }
+    items.push(inner[start..].trim());
+    items.retain(|item| !item.is_empty());
+    Some(items)
+}
+
+fn unquote(text: &str) -> String {
+    let quoted = |q: char| text.len() >= 2 && text.starts_with(q) && text.ends_with(q);
+    if quoted('"') {
+        let inner = &text[1..text.len() - 1];
+        let mut unescaped = String::with_capacity(inner.len());
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                unescaped.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('n') => unescaped.push('\n'),
+                Some('t') => unescaped.push('\t'),
+                Some(other) => unescaped.push(other),
+                None => unescaped.push('\\'),
+            }
+        }
+        unescaped
+    } else if quoted('\'') {
+        text[1..text.len() - 1].replace("''", "'")
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> MetaValue {
+        MetaValue::Text(s.to_string())
+    }
+
+    fn yaml(source: &str) -> Vec<(String, Field)> {
+        parse_yaml(source, Slug::new("index")).unwrap()
+    }
+
+    #[test]
+    fn test_parse_yaml_keeps_scalars_and_first_colon_split() {
+        assert_eq!(
+            yaml("title: Hello: world\nlink: https://example.com\n# note\nquoted: \"a \\\"b\\\"\""),
+            vec![
+                (
+                    "title".to_string(),
+                    Field::Scalar("Hello: world".to_string())
+                ),
+                (
+                    "link".to_string(),
+                    Field::Scalar("https://example.com".to_string())
+                ),
+                (
+                    "quoted".to_string(),
+                    Field::Scalar("\"a \\\"b\\\"\"".to_string())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_yaml_lists_and_nested_tables() {
+        let fields = yaml(
+            "tags: [a, 'b, c']\nauthors:\n  - Alice\n  - name: Bob\n    url: https://bob.dev\nlinks:\n  home: https://example.com\n  more: {x: 1, y: [2, 3]}\n",
+        );
+        let mut bob = OrderedMap::new();
+        bob.insert("name".to_string(), text("Bob"));
+        bob.insert("url".to_string(), text("https://bob.dev"));
+        let mut more = OrderedMap::new();
+        more.insert("x".to_string(), text("1"));
+        more.insert("y".to_string(), MetaValue::List(vec![text("2"), text("3")]));
+        let mut links = OrderedMap::new();
+        links.insert("home".to_string(), text("https://example.com"));
+        links.insert("more".to_string(), MetaValue::Map(more));
+        assert_eq!(
+            fields,
+            vec![
+                (
+                    "tags".to_string(),
+                    Field::Structured(MetaValue::List(vec![text("a"), text("b, c")]))
+                ),
+                (
+                    "authors".to_string(),
+                    Field::Structured(MetaValue::List(vec![text("Alice"), MetaValue::Map(bob)]))
+                ),
+                (
+                    "links".to_string(),
+                    Field::Structured(MetaValue::Map(links))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_yaml_block_scalars() {
+        let fields = yaml("literal: |\n  a\n   b\n\nfolded: >\n  one\n  two\n\n  three\nnext: x");
+        assert_eq!(fields[0].1, Field::Scalar("a\n b".to_string()));
+        assert_eq!(fields[1].1, Field::Scalar("one two\nthree".to_string()));
+        assert_eq!(fields[2].1, Field::Scalar("x".to_string()));
+    }
+
+    #[test]
+    fn test_parse_yaml_reports_line_numbers() {
+        let err = parse_yaml("title: a\n\nnot metadata", Slug::new("index")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid metadata in `index` at line 3: expected `name: value`, found `not metadata`"
+        );
+        assert!(parse_yaml("title: a\n  stray text", Slug::new("index")).is_err());
+    }
+
+    #[test]
+    fn test_parse_yaml_keeps_legacy_scalars() {
+        let fields = yaml(
+            "title: [WIP] Foo\nsubtitle: [a] and [b]\nnote: [Bob's, notes\nquote: \"Hello\"\ntaxon: 'def'\n  author: Alice\n",
+        );
+        assert_eq!(
+            fields,
+            vec![
+                ("title".to_string(), Field::Scalar("[WIP] Foo".to_string())),
+                (
+                    "subtitle".to_string(),
+                    Field::Scalar("[a] and [b]".to_string())
+                ),
+                (
+                    "note".to_string(),
+                    Field::Scalar("[Bob's, notes".to_string())
+                ),
+                ("quote".to_string(), Field::Scalar("\"Hello\"".to_string())),
+                ("taxon".to_string(), Field::Scalar("'def'".to_string())),
+                ("author".to_string(), Field::Scalar("Alice".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_yaml_flow_values_must_parse_whole() {
+        let fields = yaml("tags: [Bob's, 'x, y']\nbroken: {a: 1, b}\n");
+        assert_eq!(
+            fields[0].1,
+            Field::Structured(MetaValue::List(vec![text("Bob's"), text("x, y")]))
+        );
+        assert_eq!(fields[1].1, Field::Scalar("{a: 1, b}".to_string()));
+    }
+
+    #[test]
+    fn test_parse_toml_keeps_order_and_types() {
+        let fields = parse_toml(
+            "title = \"T\"\ndate = 2025-01-31\ndraft = false\ntags = [\"a\", \"b\"]\n[extra]\nz = 1\na = 2\n",
+            Slug::new("index"),
+        )
+        .unwrap();
+        let keys: Vec<_> = fields.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, ["title", "date", "draft", "tags", "extra"]);
+        assert_eq!(fields[1].1, Field::Scalar("2025-01-31".to_string()));
+        assert_eq!(fields[2].1, Field::Scalar("false".to_string()));
+        let Field::Structured(MetaValue::Map(extra)) = &fields[4].1 else {
+            panic!("expected a table");
+        };
+        assert_eq!(extra.keys().collect::<Vec<_>>(), ["z", "a"]);
+    }
+}
@@ -0,0 +1,269 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+use std::fmt;
+
+/// The part of a file selected by an include fragment, e.g. `demo.rs#L10-L42`
+/// or `demo.rs#setup`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IncludeRange {
+    /// 1-based inclusive lines; `None` runs to the end of the file.
+    Lines { start: usize, end: Option<usize> },
+    /// Lines between `region: <name>` and `endregion` marker comments.
+    Region(String),
+}
+
+impl fmt::Display for IncludeRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IncludeRange::Lines { start, end: None } => write!(f, "L{}-", start),
+            IncludeRange::Lines {
+                start,
+                end: Some(end),
+            } if start == end => write!(f, "L{}", start),
+            IncludeRange::Lines {
+                start,
+                end: Some(end),
+            } => write!(f, "L{}-L{}", start, end),
+            IncludeRange::Region(name) => write!(f, "region `{}`", name),
+        }
+    }
+}
+
+/// Split `path#fragment` into the path and the selected range.
+pub fn split_include_url(url: &str) -> (&str, Option<IncludeRange>) {
+    let Some((path, fragment)) = url.rsplit_once('#') else {
+        return (url, None);
+    };
+    if fragment.is_empty() {
+        return (path, None);
+    }
+    let range =
+        parse_line_range(fragment).unwrap_or_else(|| IncludeRange::Region(fragment.to_string()));
+    (path, Some(range))
+}
+
+/// `L10`, `L10-L42`, `L10-42` and `L10-`.
+fn parse_line_range(fragment: &str) -> Option<IncludeRange> {
+    let rest = fragment.strip_prefix('L')?;
+    let (start, end) = match rest.split_once('-') {
+        None => {
+            let line = rest.parse().ok()?;
+            (line, Some(line))
+        }
+        Some((start, "")) => (start.parse().ok()?, None),
+        Some((start, end)) => {
+            let end = end.strip_prefix('L').unwrap_or(end);
+            (start.parse().ok()?, Some(end.parse().ok()?))
+        }
+    };
+    Some(IncludeRange::Lines { start, end })
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Excerpt {
+    pub code: String,
+    /// 1-based number of the first and last included lines.
+    pub first_line: usize,
+    pub last_line: usize,
+}
+
+/// Select the lines of `content` given by `range`, dropping region marker lines.
+///
+/// A range outside the file or a missing region is an error, so the caller can
+/// warn and fall back to the whole file.
+pub fn select_excerpt(content: &str, range: Option<&IncludeRange>) -> Result<Excerpt, String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let (first, last) = match range {
+        None => return Ok(whole_file(content)),
+        Some(IncludeRange::Lines { start, end }) => {
+            let end = end.unwrap_or(lines.len());
+            if *start == 0 || *start > end || end > lines.len() {
+                return Err(format!(
+                    "line range {} is out of bounds for a file of {} lines",
+                    range.unwrap(),
+                    lines.len()
+                ));
+            }
+            (*start, end)
+        }
+        Some(IncludeRange::Region(name)) => {
+            find_region(&lines, name).ok_or_else(|| format!("region `{}` is not found", name))?
+        }
+    };
+
+    Ok(collect_lines(&lines, first, last))
+}
+
+/// The whole of `content` without region marker lines.
+pub fn whole_file(content: &str) -> Excerpt {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut excerpt = collect_lines(&lines, 1, lines.len());
+    if !content.ends_with('\n') {
+        excerpt.code.pop();
+    }
+    excerpt
+}
+
+fn collect_lines(lines: &[&str], first: usize, last: usize) -> Excerpt {
+    let mut code = String::new();
+    for line in lines.iter().take(last).skip(first - 1) {
+        if region_marker(line).is_none() {
+            code.push_str(line);
+            code.push('\n');
+        }
+    }
+    Excerpt {
+        code,
+        first_line: first,
+        last_line: last,
+    }
+}
+
+/// The 1-based lines inside the first region called `name`.
+fn find_region(lines: &[&str], name: &str) -> Option<(usize, usize)> {
+    let open = lines
+        .iter()
+        .position(|line| region_marker(line) == Some(Marker::Start(name)))?;
+    let close = lines[open + 1..]
+        .iter()
+        .position(|line| matches!(region_marker(line), Some(Marker::End(end)) if end.is_empty() || end == name))?
+        + open
+        + 1;
+    // Lines are 1-based: the region body spans `open + 1 ..= close - 1` in 0-based terms.
+    Some((open + 2, close))
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Marker<'a> {
+    Start(&'a str),
+    End(&'a str),
+}
+
+/// Recognize `region: name` and `endregion` in a comment of any common syntax,
+/// such as `// region: setup`, `# endregion` or `<!-- region: setup -->`.
+fn region_marker(line: &str) -> Option<Marker<'_>> {
+    let line = line.trim();
+    let text = line.trim_start_matches(['/', '#', ';', '%', '-', '*', '<', '!', '(']);
+    if text.len() == line.len() {
+        // Not a comment.
+        return None;
+    }
+    let text = text.trim();
+    let text = ["*/", "-->", "*)"]
+        .iter()
+        .find_map(|suffix| text.strip_suffix(suffix))
+        .unwrap_or(text)
+        .trim();
+    if let Some(name) = text.strip_prefix("region:") {
+        return Some(Marker::Start(name.trim()));
+    }
+    if let Some(rest) = text.strip_prefix("endregion") {
+        if rest.is_empty() {
+            return Some(Marker::End(""));
+        }
+        return rest.strip_prefix(':').map(|name| Marker::End(name.trim()));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEMO: &str = "\
+use std::io;
+// region: setup
+let a = 1;
+let b = 2;
+// endregion: setup
+# region: other
+done();
+# endregion
+";
+
+    #[test]
+    fn test_split_include_url_parses_ranges_and_regions() {
+        assert_eq!(split_include_url("demo.rs"), ("demo.rs", None));
+        assert_eq!(
+            split_include_url("demo.rs#L10-L42"),
+            (
+                "demo.rs",
+                Some(IncludeRange::Lines {
+                    start: 10,
+                    end: Some(42)
+                })
+            )
+        );
+        assert_eq!(
+            split_include_url("demo.rs#L3"),
+            (
+                "demo.rs",
+                Some(IncludeRange::Lines {
+                    start: 3,
+                    end: Some(3)
+                })
+            )
+        );
+        assert_eq!(
+            split_include_url("demo.rs#L3-"),
+            (
+                "demo.rs",
+                Some(IncludeRange::Lines {
+                    start: 3,
+                    end: None
+                })
+            )
+        );
+        assert_eq!(
+            split_include_url("demo.rs#setup"),
+            ("demo.rs", Some(IncludeRange::Region("setup".to_string())))
+        );
+    }
+
+    #[test]
+    fn test_select_excerpt_by_lines_and_region() {
+        let lines = IncludeRange::Lines {
+            start: 1,
+            end: Some(3),
+        };
+        let excerpt = select_excerpt(DEMO, Some(&lines)).unwrap();
+        assert_eq!(excerpt.code, "use std::io;\nlet a = 1;\n");
+        assert_eq!((excerpt.first_line, excerpt.last_line), (1, 3));
+
+        let region = IncludeRange::Region("setup".to_string());
+        let excerpt = select_excerpt(DEMO, Some(&region)).unwrap();
+        assert_eq!(excerpt.code, "let a = 1;\nlet b = 2;\n");
+        assert_eq!((excerpt.first_line, excerpt.last_line), (3, 4));
+
+        let region = IncludeRange::Region("other".to_string());
+        assert_eq!(
+            select_excerpt(DEMO, Some(&region)).unwrap().code,
+            "done();\n"
+        );
+    }
+
+    #[test]
+    fn test_select_excerpt_strips_markers_from_whole_file() {
+        let excerpt = whole_file("a\n<!-- region: x -->\nb\n<!-- endregion -->");
+        assert_eq!(excerpt.code, "a\nb");
+        assert_eq!(excerpt.first_line, 1);
+    }
+
+    #[test]
+    fn test_select_excerpt_rejects_bad_ranges() {
+        let out_of_bounds = IncludeRange::Lines {
+            start: 5,
+            end: Some(20),
+        };
+        assert!(select_excerpt(DEMO, Some(&out_of_bounds))
+            .unwrap_err()
+            .contains("out of bounds"));
+        let missing = IncludeRange::Region("nope".to_string());
+        assert!(select_excerpt(DEMO, Some(&missing))
+            .unwrap_err()
+            .contains("`nope`"));
+        assert_eq!(region_marker("let region: u8 = 0;"), None);
+    }
+}
@@ -0,0 +1,531 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+//! A best-effort translation of common LaTeX math into Typst math, so Markdown
+//! written for KaTeX keeps working with `[build].markdown-math = "typst"`.
+
+/// LaTeX commands whose Typst name differs. Commands not listed here and not
+/// handled structurally are emitted without the backslash, which covers Greek
+/// letters and operators such as `\sin` or `\sum` that share names.
+const SYMBOLS: &[(&str, &str)] = &[
+    ("infty", "infinity"),
+    ("cdot", "dot.op"),
+    ("cdots", "dots.c"),
+    ("ldots", "dots"),
+    ("vdots", "dots.v"),
+    ("ddots", "dots.down"),
+    ("pm", "plus.minus"),
+    ("mp", "minus.plus"),
+    ("leq", "<="),
+    ("le", "<="),
+    ("geq", ">="),
+    ("ge", ">="),
+    ("neq", "!="),
+    ("ne", "!="),
+    ("ll", "<<"),
+    ("gg", ">>"),
+    ("sim", "tilde.op"),
+    ("simeq", "tilde.eq"),
+    ("cong", "tilde.equiv"),
+    ("propto", "prop"),
+    ("to", "->"),
+    ("rightarrow", "->"),
+    ("longrightarrow", "-->"),
+    ("leftarrow", "<-"),
+    ("gets", "<-"),
+    ("Rightarrow", "=>"),
+    ("implies", "==>"),
+    ("Leftarrow", "arrow.l.double"),
+    ("Leftrightarrow", "<=>"),
+    ("iff", "<==>"),
+    ("leftrightarrow", "<->"),
+    ("mapsto", "|->"),
+    ("notin", "in.not"),
+    ("subseteq", "subset.eq"),
+    ("supseteq", "supset.eq"),
+    ("cup", "union"),
+    ("bigcup", "union.big"),
+    ("cap", "sect"),
+    ("bigcap", "sect.big"),
+    ("setminus", "without"),
+    ("emptyset", "emptyset"),
+    ("varnothing", "nothing"),
+    ("neg", "not"),
+    ("lnot", "not"),
+    ("land", "and"),
+    ("wedge", "and"),
+    ("lor", "or"),
+    ("vee", "or"),
+    ("partial", "diff"),
+    ("langle", "angle.l"),
+    ("rangle", "angle.r"),
+    ("lfloor", "floor.l"),
+    ("rfloor", "floor.r"),
+    ("lceil", "ceil.l"),
+    ("rceil", "ceil.r"),
+    ("mid", "|"),
+    ("vert", "|"),
+    ("lvert", "|"),
+    ("rvert", "|"),
+    ("Vert", "||"),
+    ("lVert", "||"),
+    ("rVert", "||"),
+    ("hbar", "planck.reduce"),
+    ("circ", "compose"),
+    ("ast", "ast"),
+    ("star", "star"),
+    ("oplus", "plus.circle"),
+    ("otimes", "times.circle"),
+    ("prod", "product"),
+    ("coprod", "product.co"),
+    ("int", "integral"),
+    ("iint", "integral.double"),
+    ("iiint", "integral.triple"),
+    ("oint", "integral.cont"),
+    ("qquad", "wide"),
+    ("phi", "phi.alt"),
+    ("varphi", "phi"),
+    ("epsilon", "epsilon.alt"),
+    ("varepsilon", "epsilon"),
+    ("vartheta", "theta.alt"),
+    ("varrho", "rho.alt"),
+    ("varsigma", "sigma.alt"),
+    ("varpi", "pi.alt"),
+    ("aleph", "aleph"),
+    ("Re", "Re"),
+    ("Im", "Im"),
+];
+
+/// Commands taking one argument that map to a Typst function of one argument.
+const UNARY: &[(&str, &str)] = &[
+    ("mathbb", "bb"),
+    ("mathbf", "bold"),
+    ("boldsymbol", "bold"),
+    ("bm", "bold"),
+    ("mathrm", "upright"),
+    ("mathit", "italic"),
+    ("mathcal", "cal"),
+    ("mathscr", "scr"),
+    ("mathfrak", "frak"),
+    ("mathsf", "sans"),
+    ("mathtt", "mono"),
+    ("hat", "hat"),
+    ("widehat", "hat"),
+    ("tilde", "tilde"),
+    ("widetilde", "tilde"),
+    ("bar", "overline"),
+    ("overline", "overline"),
+    ("underline", "underline"),
+    ("vec", "arrow"),
+    ("dot", "dot"),
+    ("ddot", "dot.double"),
+    ("abs", "abs"),
+    ("norm", "norm"),
+];
+
+/// Commands whose argument is text rather than math.
+const TEXT: &[&str] = &["text", "textrm", "textit", "textbf", "mbox", "operatorname"];
+
+/// Whether `math` looks like LaTeX, i.e. it uses a `\command`.
+pub fn is_latex(math: &str) -> bool {
+    math.as_bytes()
+        .windows(2)
+        .any(|pair| pair[0] == b'\\' && pair[1].is_ascii_alphabetic())
+}
+
+/// Translate LaTeX math into Typst math.
+pub fn latex_to_typst(latex: &str) -> String {
+    let mut shim = Shim {
+        chars: latex.chars().collect(),
+        pos: 0,
+    };
+    tidy(&shim.sequence(Scope::Top))
+}
+
+/// Tidy the spacing of translated math, leaving the string literals of text
+/// commands as written.
+fn tidy(typst: &str) -> String {
+    let mut out = String::new();
+    let mut math = String::new();
+    let mut chars = typst.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                math.push(c);
+                math.extend(chars.next());
+            }
+            '"' => {
+                out.push_str(&tidy_math(&math));
+                math.clear();
+                out.push(c);
+                while let Some(c) = chars.next() {
+                    out.push(c);
+                    match c {
+                        '\\' => out.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            c => math.push(c),
+        }
+    }
+    out.push_str(&tidy_math(&math));
+    out.trim().to_string()
+}
+
+fn tidy_math(math: &str) -> String {
+    let words = math.split_whitespace().collect::<Vec<_>>().join(" ");
+    let lead = match math.starts_with(char::is_whitespace) {
+        true => " ",
+        false => "",
+    };
+    let trail = match !words.is_empty() && math.ends_with(char::is_whitespace) {
+        true => " ",
+        false => "",
+    };
+    // Spacing never changes meaning here, except before `(` where it separates
+    // a function call from an implicit product; attachments hug their base.
+    format!("{lead}{words}{trail}")
+        .replace("( ", "(")
+        .replace(" )", ")")
+        .replace(" ,", ",")
+        .replace(" ;", ";")
+        .replace(" ^(", "^(")
+        .replace(" _(", "_(")
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    Top,
+    /// Inside `{...}`; stops at the closing brace.
+    Group,
+    /// Inside a `\begin{...}` environment; stops at `\end`.
+    Env(Env),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Env {
+    /// `&` separates cells and `\\` separates rows.
+    Matrix,
+    /// `&` aligns and `\\` separates cases.
+    Cases,
+    /// `&` aligns and `\\` breaks lines.
+    Lines,
+}
+
+struct Shim {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Shim {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn sequence(&mut self, scope: Scope) -> String {
+        let mut out = String::new();
+        // Parentheses opened in this sequence; commas inside them are not separators.
+        let mut parens = 0usize;
+        while let Some(c) = self.peek() {
+            match c {
+                '}' if scope == Scope::Group => {
+                    self.pos += 1;
+                    break;
+                }
+                '}' => self.pos += 1,
+                '{' => {
+                    self.pos += 1;
+                    out.push(' ');
+                    out.push_str(&self.sequence(Scope::Group));
+                    out.push(' ');
+                }
+                '\\' => {
+                    if let Scope::Env(_) = scope {
+                        if self.at_command("end") {
+                            self.pos += "\\end".len();
+                            self.raw_group();
+                            break;
+                        }
+                    }
+                    out.push_str(&self.command(scope));
+                }
+                '^' | '_' => {
+                    self.pos += 1;
+                    out.push(c);
+                    out.push('(');
+                    out.push_str(&self.argument());
+                    out.push(')');
+                }
+                '&' if scope == Scope::Env(Env::Matrix) => {
+                    self.pos += 1;
+                    out.push_str(", ");
+                }
+                ',' if parens == 0 && scope != Scope::Top => {
+                    self.pos += 1;
+                    out.push_str("\\, ");
+                }
+                '(' => {
+                    self.pos += 1;
+                    parens += 1;
+                    out.push('(');
+                }
+                ')' => {
+                    self.pos += 1;
+                    parens = parens.saturating_sub(1);
+                    out.push(')');
+                }
+                '"' | '#' | '$' => {
+                    self.pos += 1;
+                    out.push('\\');
+                    out.push(c);
+                }
+                c if c.is_ascii_alphabetic() => {
+                    // LaTeX multiplies adjacent letters; Typst reads them as one name.
+                    self.pos += 1;
+                    out.push(' ');
+                    out.push(c);
+                    out.push(' ');
+                }
+                c => {
+                    self.pos += 1;
+                    out.push(c);
+                }
+            }
+        }
+        out
+    }
+
+    fn at_command(&self, name: &str) -> bool {
+        let end = self.pos + 1 + name.len();
+        self.chars
+            .get(self.pos + 1..end)
+            .is_some_and(|s| s.iter().copied().eq(name.chars()))
+            && !self.chars.get(end).is_some_and(char::is_ascii_alphabetic)
+    }
+
+    /// One argument: a `{...}` group, a command, or a single character.
+    fn argument(&mut self) -> String {
+        self.skip_spaces();
+        match self.peek() {
+            Some('{') => {
+                self.pos += 1;
+                self.sequence(Scope::Group)
+            }
+            Some('\\') => self.command(Scope::Group),
+            Some(c) => {
+                self.pos += 1;
+                match c {
+                    '"' | '#' | '$' | ',' => format!("\\{c}"),
+                    c => c.to_string(),
+                }
+            }
+            None => String::new(),
+        }
+    }
+
+    /// The raw text of a `{...}` group, without translation.
+    fn raw_group(&mut self) -> String {
+        self.skip_spaces();
+        if self.peek() != Some('{') {
+            return self
+                .peek()
+                .map(|c| {
+                    self.pos += 1;
+                    c.to_string()
+                })
+                .unwrap_or_default();
+        }
+        self.pos += 1;
+        let mut depth = 1;
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+            text.push(c);
+        }
+        text
+    }
+
+    fn command(&mut self, scope: Scope) -> String {
+        // Skip the backslash.
+        self.pos += 1;
+        let Some(first) = self.peek() else {
+            return String::new();
+        };
+        if !first.is_ascii_alphabetic() {
+            self.pos += 1;
+            return match first {
+                '\\' => match scope {
+                    Scope::Env(Env::Matrix) => "; ".to_string(),
+                    Scope::Env(Env::Cases) => ", ".to_string(),
+                    _ => " \\ ".to_string(),
+                },
+                ',' | ':' | '>' => " thin ".to_string(),
+                ';' => " med ".to_string(),
+                '!' => String::new(),
+                ' ' => " space ".to_string(),
+                '|' => " || ".to_string(),
+                '{' | '}' | '_' | '&' | '#' | '$' | '%' => format!("\\{first}"),
+                c => c.to_string(),
+            };
+        }
+
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+            self.pos += 1;
+        }
+        let name: String = self.chars[start..self.pos].iter().collect();
+        let name = name.as_str();
+
+        match name {
+            "frac" | "dfrac" | "tfrac" => {
+                let numerator = self.argument();
+                let denominator = self.argument();
+                format!(" frac({numerator}, {denominator}) ")
+            }
+            "binom" => {
+                let n = self.argument();
+                let k = self.argument();
+                format!(" binom({n}, {k}) ")
+            }
+            "sqrt" => {
+                self.skip_spaces();
+                if self.peek() == Some('[') {
+                    self.pos += 1;
+                    let start = self.pos;
+                    while self.peek().is_some_and(|c| c != ']') {
+                        self.pos += 1;
+                    }
+                    let index: String = self.chars[start..self.pos].iter().collect();
+                    self.pos += 1;
+                    let radicand = self.argument();
+                    format!(" root({}, {radicand}) ", latex_to_typst(&index))
+                } else {
+                    format!(" sqrt({}) ", self.argument())
+                }
+            }
+            "left" | "right" | "big" | "Big" | "bigg" | "Bigg" | "displaystyle" | "textstyle"
+            | "limits" | "nolimits" => {
+                self.skip_spaces();
+                if self.peek() == Some('.') {
+                    self.pos += 1;
+                }
+                " ".to_string()
+            }
+            "begin" => {
+                let env = self.raw_group();
+                self.environment(env.trim_end_matches('*'))
+            }
+            _ if TEXT.contains(&name) => {
+                let text = self.raw_group().replace('\\', "\\\\").replace('"', "\\\"");
+                match name {
+                    "operatorname" => format!(" op(\"{text}\") "),
+                    _ => format!(" \"{text}\" "),
+                }
+            }
+            _ => {
+                if let Some((_, function)) = UNARY.iter().find(|(latex, _)| *latex == name) {
+                    return format!(" {function}({}) ", self.argument());
+                }
+                let symbol = SYMBOLS
+                    .iter()
+                    .find(|(latex, _)| *latex == name)
+                    .map_or(name, |(_, typst)| typst);
+                format!(" {symbol} ")
+            }
+        }
+    }
+
+    fn environment(&mut self, name: &str) -> String {
+        let (function, env) = match name {
+            "matrix" => ("mat(delim: #none, ", Env::Matrix),
+            "pmatrix" => ("mat(delim: \"(\", ", Env::Matrix),
+            "bmatrix" => ("mat(delim: \"[\", ", Env::Matrix),
+            "Bmatrix" => ("mat(delim: \"{\", ", Env::Matrix),
+            "vmatrix" => ("mat(delim: \"|\", ", Env::Matrix),
+            "Vmatrix" => ("mat(delim: \"||\", ", Env::Matrix),
+            "cases" => ("cases(", Env::Cases),
+            _ => ("", Env::Lines),
+        };
+        let body = self.sequence(Scope::Env(env));
+        match env {
+            Env::Lines => format!(" {body} "),
+            _ => format!(" {function}{body}) "),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_latex_detects_commands() {
+        assert!(is_latex(r"\alpha + 1"));
+        assert!(!is_latex("x^2 + y"));
+        assert!(!is_latex(r"a \ b"));
+    }
+
+    #[test]
+    fn test_latex_to_typst_common_commands() {
+        assert_eq!(latex_to_typst(r"\frac{a+b}{2}"), "frac(a + b, 2)");
+        assert_eq!(latex_to_typst(r"\sqrt[3]{x}"), "root(3, x)");
+        assert_eq!(latex_to_typst(r"x^{2} + \alpha_1"), "x^(2) + alpha_(1)");
+        assert_eq!(
+            latex_to_typst(r"\mathbb{R} \to \infty"),
+            "bb(R) -> infinity"
+        );
+        assert_eq!(latex_to_typst(r"\text{if } x \leq 0"), "\"if \" x <= 0");
+        assert_eq!(latex_to_typst(r"\left( xy \right)"), "(x y)");
+    }
+
+    #[test]
+    fn test_latex_to_typst_environments() {
+        assert_eq!(
+            latex_to_typst(r"\begin{pmatrix} a & b \\ c & d \end{pmatrix}"),
+            "mat(delim: \"(\", a, b; c, d)"
+        );
+        assert_eq!(
+            latex_to_typst(r"|x| = \begin{cases} x & x \geq 0 \\ -x & \text{else} \end{cases}"),
+            "| x | = cases(x & x >= 0, - x & \"else\")"
+        );
+    }
+
+    #[test]
+    fn test_latex_to_typst_keeps_text_spacing() {
+        assert_eq!(
+            latex_to_typst(r"\text{a ( b ) ,  c} (x)"),
+            "\"a ( b ) ,  c\" (x)"
+        );
+        assert_eq!(
+            latex_to_typst(r"\operatorname{arg ; max}_{x}"),
+            "op(\"arg ; max\")_(x)"
+        );
+    }
+
+    #[test]
+    fn test_latex_to_typst_escapes_commas_in_arguments() {
+        assert_eq!(
+            latex_to_typst(r"\frac{a,b}{f(x,y)}"),
+            "frac(a \\, b, f (x, y))"
+        );
+    }
+}
@@ -0,0 +1,225 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use eyre::{eyre, WrapErr};
+use pulldown_cmark::{Event, Tag, TagEnd};
+
+use crate::{
+    compiler, config::action::Action, environment, external_command, html_flake::html_typst_figure,
+    slug::Slug,
+};
+
+use super::{
+    path_resolution::{relocate_trees_path, resolve_section_url},
+    processor::url_action,
+};
+
+static LINK_ACTION_ERROR_FLAG: AtomicBool = AtomicBool::new(false);
+
+pub fn reset_link_action_error_flag() {
+    LINK_ACTION_ERROR_FLAG.store(false, Ordering::Relaxed);
+}
+
+pub fn link_action_error_detected() -> bool {
+    LINK_ACTION_ERROR_FLAG.load(Ordering::Relaxed)
+}
+
+/// Runs links whose action is declared in `[actions]`, e.g. `[caption](./graph.dot#:dot)`,
+/// and replaces them with a figure showing the command output.
+pub struct LinkAction<E> {
+    events: E,
+    pending: Option<(String, Action, String)>,
+    caption: String,
+    current_slug: Slug,
+}
+
+impl<E> LinkAction<E> {
+    pub fn process(events: E, current_slug: Slug) -> Self {
+        Self {
+            events,
+            pending: None,
+            caption: String::new(),
+            current_slug,
+        }
+    }
+}
+
+impl<'e, E: Iterator<Item = Event<'e>>> Iterator for LinkAction<E> {
+    type Item = Event<'e>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for e in self.events.by_ref() {
+            match e {
+                Event::Start(Tag::Link { ref dest_url, .. }) if self.pending.is_none() => {
+                    let (url, name) = url_action(dest_url);
+                    match environment::link_action(&name) {
+                        Some(action) => self.pending = Some((url, action, name)),
+                        None => return Some(e),
+                    }
+                }
+                Event::Text(ref text) | Event::Code(ref text) if self.pending.is_some() => {
+                    self.caption.push_str(text);
+                }
+                Event::End(TagEnd::Link) if self.pending.is_some() => {
+                    let (url, action, name) = self.pending.take().unwrap();
+                    let caption = std::mem::take(&mut self.caption);
+                    let relative = action_input_path(self.current_slug, &url);
+                    compiler::record_dependency(relative.clone());
+
+                    let output = action_output_path(&relative, &action);
+                    if let Err(err) = run_action(&relative, &output, &action) {
+                        LINK_ACTION_ERROR_FLAG.store(true, Ordering::Relaxed);
+                        color_print::ceprintln!(
+                            "<r>{:?} at {}</>",
+                            err.wrap_err(format!("link action `{}` failed", name)),
+                            self.current_slug
+                        );
+                        return Some(Event::Html("".into()));
+                    }
+                    let html =
+                        html_typst_figure(&environment::full_url(&output), !action.inline, caption);
+                    return Some(Event::Html(html.into()));
+                }
+                _ if self.pending.is_some() => {}
+                _ => return Some(e),
+            }
+        }
+        None
+    }
+}
+
+fn action_input_path(current_slug: Slug, url: &str) -> Utf8PathBuf {
+    let resolved = resolve_section_url(url, current_slug);
+    let relocated = relocate_trees_path(&resolved);
+    Utf8PathBuf::from(relocated.trim_start_matches('/'))
+}
+
+/// `graph.dot` becomes `graph.dot.svg`, so outputs never collide with Typst figures
+/// and stay traceable to their input.
+fn action_output_path(relative: &Utf8Path, action: &Action) -> Utf8PathBuf {
+    Utf8PathBuf::from(format!("{}.{}", relative, action.extension))
+}
+
+/// Pipe the input file through the action command, unless the input and command
+/// are unchanged since the output was last written. Check mode runs the command
+/// without writing anything.
+fn run_action(relative: &Utf8Path, output: &Utf8Path, action: &Action) -> eyre::Result<()> {
+    let input_path = environment::input_path(relative);
+    let fs = environment::fs();
+    let input = fs
+        .read(&input_path)
+        .wrap_err_with(|| eyre!("failed to read `{}`", input_path))?;
+    let root = environment::root_dir();
+
+    if environment::is_check() {
+        external_command::run_piped(&action.command, &root, input, action.timeout)?;
+        return Ok(());
+    }
+
+    let output_path = environment::output_path(output);
+    let cache_key = format!(
+        "{}\n{}",
+        action.command.join("\u{0}"),
+        String::from_utf8_lossy(&input)
+    );
+    let modified = environment::verify_update_hash(output, &cache_key)
+        .wrap_err_with(|| eyre!("failed to update hash for `{}`", output))?;
+    if !modified && fs.exists(&output_path) {
+        if crate::cli::build::verbose_skip() {
+            println!("Skip: {}", output);
+        }
+        return Ok(());
+    }
+
+    let result = external_command::run_piped(&action.command, &root, input, action.timeout)
+        .and_then(|bytes| {
+            fs.write(&output_path, &bytes)
+                .wrap_err_with(|| eyre!("failed to write `{}`", output_path))
+        });
+    if result.is_err() {
+        // Forget the hash so the next build retries the command.
+        let _ = fs.remove_file(&environment::hash_file_path(output));
+    } else if crate::cli::build::verbose() {
+        println!("Generated: {}", output);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_action_output_path_appends_extension() {
+        let action = Action {
+            command: vec!["dot".to_string()],
+            extension: "svg".to_string(),
+            inline: false,
+            timeout: 0,
+        };
+        assert_eq!(
+            action_output_path(Utf8Path::new("figs/graph.dot"), &action),
+            Utf8PathBuf::from("figs/graph.dot.svg")
+        );
+    }
+
+    #[test]
+    fn test_action_input_path_resolves_relative_to_section() {
+        crate::environment::mock_environment().unwrap();
+        let path = action_input_path(Slug::new("guide/index"), "./graph.dot");
+        assert_eq!(path, Utf8PathBuf::from("guide/graph.dot"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_link_action_pipes_file_and_caches_output() {
+        let root = crate::test_io::case_dir("link-action-pipe");
+        fs::create_dir_all(root.join("trees/guide")).unwrap();
+        fs::write(root.join("trees/guide/note.txt"), "hello").unwrap();
+        let config_path = root.join("Kodama.toml");
+        fs::write(
+            &config_path,
+            r#"
+[actions.upper]
+command = ["tr", "a-z", "A-Z"]
+extension = "txt"
+"#,
+        )
+        .unwrap();
+
+        environment::with_test_environment(root.clone(), environment::BuildMode::Publish, || {
+            environment::init_environment(config_path.clone(), environment::BuildMode::Publish)
+                .unwrap();
+            let parse = || {
+                crate::compiler::parser::parse_spanned_markdown(
+                    "[Shout](./note.txt#:upper)",
+                    Slug::new("guide/index"),
+                )
+            };
+
+            let html = parse();
+            let html = html.as_str().unwrap();
+            assert!(html.contains("guide/note.txt.txt"), "{html}");
+            assert!(html.contains("Shout"), "{html}");
+            let output = environment::output_path("guide/note.txt.txt");
+            assert_eq!(fs::read_to_string(&output).unwrap(), "HELLO");
+
+            // Unchanged input: the cached output is kept as is.
+            fs::write(&output, "cached").unwrap();
+            parse();
+            assert_eq!(fs::read_to_string(&output).unwrap(), "cached");
+
+            fs::write(root.join("trees/guide/note.txt"), "again").unwrap();
+            parse();
+            assert_eq!(fs::read_to_string(&output).unwrap(), "AGAIN");
+        });
+
+        let _ = fs::remove_dir_all(root);
+    }
+}
@@ -0,0 +1,190 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+use camino::Utf8PathBuf;
+use eyre::{eyre, WrapErr};
+use pulldown_cmark::{Event, Tag, TagEnd};
+
+use crate::{config::build::MarkdownMath, environment, slug::Slug, typst_cli};
+
+use super::{
+    latex_shim::{is_latex, latex_to_typst},
+    typst_image::record_typst_image_error,
+};
+
+/// Renders `$...$` and `$$...$$` with Typst when `[build].markdown-math = "typst"`.
+/// A formula that fails to compile is reported and left as its source text, which
+/// stays unrendered since this mode imports no KaTeX.
+pub struct MarkdownMathRenderer<E> {
+    events: E,
+    enabled: bool,
+    /// Math in image alt text must stay plain text.
+    image_depth: usize,
+    current_slug: Slug,
+}
+
+impl<E> MarkdownMathRenderer<E> {
+    pub fn process(events: E, current_slug: Slug) -> Self {
+        Self {
+            events,
+            enabled: environment::markdown_math() == MarkdownMath::Typst,
+            image_depth: 0,
+            current_slug,
+        }
+    }
+
+    fn render<'e>(&self, math: &str, display: bool, original: Event<'e>) -> Event<'e> {
+        match render_math(math, display) {
+            Ok(html) => Event::InlineHtml(html.into()),
+            Err(err) => {
+                record_typst_image_error();
+                color_print::ceprintln!(
+                    "<r>{:?} at {}</>",
+                    err.wrap_err(format!("failed to render math `{}`", math)),
+                    self.current_slug
+                );
+                original
+            }
+        }
+    }
+}
+
+impl<'e, E: Iterator<Item = Event<'e>>> Iterator for MarkdownMathRenderer<E> {
+    type Item = Event<'e>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let e = self.events.next()?;
+        if !self.enabled {
+            return Some(e);
+        }
+        match e {
+            Event::Start(Tag::Image { .. }) => self.image_depth += 1,
+            Event::End(TagEnd::Image) => self.image_depth = self.image_depth.saturating_sub(1),
+            Event::InlineMath(ref math) if self.image_depth == 0 => {
+                let math = math.to_string();
+                return Some(self.render(&math, false, e));
+            }
+            Event::DisplayMath(ref math) if self.image_depth == 0 => {
+                let math = math.to_string();
+                return Some(self.render(&math, true, e));
+            }
+            _ => {}
+        }
+        Some(e)
+    }
+}
+
+/// Typst source of a Markdown formula; LaTeX commands go through the shim.
+fn typst_source(math: &str, display: bool) -> String {
+    let math = math.trim();
+    let math = if is_latex(math) {
+        latex_to_typst(math)
+    } else {
+        math.to_string()
+    };
+    match display {
+        true => format!("$ {} $", math),
+        false => format!("${}$", math),
+    }
+}
+
+/// Formulas repeat across pages and builds, so each rendering is cached by its
+/// source and the Kodama and Typst versions that rendered it.
+fn math_cache_path(source: &str) -> Utf8PathBuf {
+    let key = format!(
+        "{}\n{}\n{}",
+        env!("CARGO_PKG_VERSION"),
+        typst_cli::version(),
+        source
+    );
+    let hash = environment::content_hash(&key);
+    environment::math_dir().join(format!("{:016x}.html", hash))
+}
+
+fn render_math(math: &str, display: bool) -> eyre::Result<String> {
+    let source = typst_source(math, display);
+    let cache_path = math_cache_path(&source);
+    if let Some(name) = cache_path.file_name() {
+        crate::compiler::record_math(name.to_string());
+    }
+    if !crate::cli::build::no_cache_enabled() {
+        if let Ok(html) = environment::fs().read_to_string(&cache_path) {
+            if crate::cli::build::verbose_skip() {
+                println!("Skip: {}", source);
+            }
+            return Ok(html);
+        }
+    }
+
+    let html = match display {
+        true => typst_cli::source_to_display_svg(&source)?,
+        false => typst_cli::source_to_inline_svg(&source)?.trim().to_string(),
+    };
+    if !environment::is_check() {
+        environment::fs()
+            .write(&cache_path, html.as_bytes())
+            .wrap_err_with(|| eyre!("failed to write `{}`", cache_path))?;
+    }
+    Ok(html)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_typst_source_translates_latex_only() {
+        assert_eq!(typst_source(" x^2 ", false), "$x^2$");
+        assert_eq!(typst_source(r"\alpha", true), "$ alpha $");
+    }
+
+    #[test]
+    fn test_markdown_math_uses_cached_rendering() {
+        let root = crate::test_io::case_dir("markdown-math-cache");
+        fs::create_dir_all(root.join("trees")).unwrap();
+        let config_path = root.join("Kodama.toml");
+        fs::write(&config_path, "[build]\nmarkdown-math = \"typst\"\n").unwrap();
+
+        environment::with_test_environment(root.clone(), environment::BuildMode::Publish, || {
+            environment::init_environment(config_path.clone(), environment::BuildMode::Publish)
+                .unwrap();
+            for (source, html) in [
+                ("$x$", "<span class=\"inline-typst\">X</span>"),
+                ("$ sum $", "<span class=\"typst-display\">S</span>"),
+            ] {
+                let path = math_cache_path(source);
+                environment::create_parent_dirs(&path);
+                fs::write(path, html).unwrap();
+            }
+
+            let html = crate::compiler::parser::parse_spanned_markdown(
+                r"$x$ and $$\sum$$ ![$x$](a.png)",
+                Slug::new("index"),
+            );
+            let html = html.as_str().unwrap();
+            assert!(
+                html.contains("<span class=\"inline-typst\">X</span> and"),
+                "{html}"
+            );
+            assert!(
+                html.contains("<span class=\"typst-display\">S</span>"),
+                "{html}"
+            );
+            assert!(html.contains("alt=\"$x$\""), "{html}");
+            assert!(environment::import_math_html().is_empty());
+        });
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn test_markdown_math_defaults_to_katex_text() {
+        crate::environment::mock_environment().unwrap();
+        let html = crate::compiler::parser::parse_spanned_markdown("$x$", Slug::new("index"));
+        assert!(html.as_str().unwrap().contains("$x$"));
+        assert!(!environment::import_math_html().is_empty());
+    }
+}
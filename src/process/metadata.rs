@@ -9,11 +9,14 @@ use crate::{
     slug::Slug,
 };
 use eyre::eyre;
-use pulldown_cmark::{Event, Tag, TagEnd};
+use pulldown_cmark::{Event, MetadataBlockKind, Tag, TagEnd};
+
+use super::frontmatter::{self, Field};
 
 pub struct Metadata<'m, E> {
     events: E,
-    state: bool,
+    /// The style and text of the metadata block being read.
+    block: Option<(MetadataBlockKind, String)>,
     metadata: &'m mut OrderedMap<String, HTMLContent>,
 }
 
@@ -21,7 +24,7 @@ impl<'m, E> Metadata<'m, E> {
     pub fn process(events: E, metadata: &'m mut OrderedMap<String, HTMLContent>) -> Self {
         Self {
             events,
-            state: false,
+            block: None,
             metadata,
         }
     }
@@ -33,19 +36,20 @@ impl<'e, E: Iterator<Item = Event<'e>>> Iterator for Metadata<'_, E> {
     fn next(&mut self) -> Option<Self::Item> {
         for e in self.events.by_ref() {
             match e {
-                Event::Start(Tag::MetadataBlock(_)) => {
-                    self.state = true;
+                Event::Start(Tag::MetadataBlock(kind)) => {
+                    self.block = Some((kind, String::new()));
                 }
                 Event::End(TagEnd::MetadataBlock(_)) => {
-                    self.state = false;
+                    let Some((kind, text)) = self.block.take() else {
+                        continue;
+                    };
+                    if let Err(e) = parse_metadata(kind, &text, self.metadata) {
+                        return Some(Err(e.wrap_err("failed to parse metadata")));
+                    }
                 }
-                Event::Text(ref text) => {
-                    if self.state && !text.trim().is_empty() {
-                        if let Err(e) = parse_metadata(text, self.metadata) {
-                            return Some(Err(e.wrap_err("failed to parse metadata")));
-                        }
-                    } else {
-                        return Some(Ok(e));
+                Event::Text(ref text) if self.block.is_some() => {
+                    if let Some((_, block)) = self.block.as_mut() {
+                        block.push_str(text);
                     }
                 }
                 _ => return Some(Ok(e)),
@@ -55,37 +59,54 @@ impl<'e, E: Iterator<Item = Event<'e>>> Iterator for Metadata<'_, E> {
     }
 }
 
-/// It is known that the behavior differs between the two architectures
+/// Text of a metadata block is collected before parsing, since it is known that
+/// the behavior differs between the two architectures
 /// `(I)` `x86_64-pc-windows-msvc` and `(II)` `aarch64-unknown-linux-musl`.
 /// `(I)` automatically splits the input by lines,
 /// while `(II)` receives the entire multi-line string as a whole.
-fn parse_metadata(s: &str, metadata: &mut OrderedMap<String, HTMLContent>) -> eyre::Result<()> {
+fn parse_metadata(
+    kind: MetadataBlockKind,
+    s: &str,
+    metadata: &mut OrderedMap<String, HTMLContent>,
+) -> eyre::Result<()> {
     let current_slug = metadata
         .get(KEY_SLUG)
         .and_then(HTMLContent::as_str)
         .map(Slug::new)
         .ok_or_else(|| eyre!("missing `slug` while parsing metadata block"))?;
 
-    for (line_no, s) in s.lines().enumerate() {
-        if !s.trim().is_empty() {
-            let pos = s.find(':').ok_or_else(|| {
-                eyre!(
-                    "invalid metadata in `{}` at line {}: expected `name: value`, found `{}`",
-                    current_slug,
-                    line_no + 1,
-                    s
-                )
-            })?;
-            let key = s[0..pos].trim();
-            let val = s[pos + 1..].trim();
-
-            let parsed = parse_metadata_value(key, val, current_slug);
-            metadata.insert(key.to_string(), parsed);
-        }
+    let fields = match kind {
+        MetadataBlockKind::YamlStyle => frontmatter::parse_yaml(s, current_slug)?,
+        MetadataBlockKind::PlusesStyle => frontmatter::parse_toml(s, current_slug)?,
+    };
+    for (key, field) in fields {
+        let parsed = field_content(&key, field, current_slug);
+        metadata.insert(key, parsed);
     }
     Ok(())
 }
 
+/// Parse TOML metadata outside a Markdown source, such as a `_defaults.toml` file.
+pub fn parse_toml_metadata(
+    s: &str,
+    current_slug: Slug,
+) -> eyre::Result<Vec<(String, HTMLContent)>> {
+    Ok(frontmatter::parse_toml(s, current_slug)?
+        .into_iter()
+        .map(|(key, field)| {
+            let parsed = field_content(&key, field, current_slug);
+            (key, parsed)
+        })
+        .collect())
+}
+
+fn field_content(key: &str, field: Field, current_slug: Slug) -> HTMLContent {
+    match field {
+        Field::Scalar(value) => parse_metadata_value(key, &value, current_slug),
+        Field::Structured(value) => HTMLContent::Structured(value),
+    }
+}
+
 fn parse_metadata_value(key: &str, value: &str, current_slug: Slug) -> HTMLContent {
     if is_plain_metadata(key) {
         return HTMLContent::Plain(value.to_string());
@@ -112,7 +133,7 @@ pub fn display_taxon(s: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::entry::{KEY_PAGE_TITLE, KEY_TITLE};
+    use crate::entry::{MetaValue, KEY_PAGE_TITLE, KEY_TITLE};
 
     fn metadata_with_slug(slug: &str) -> OrderedMap<String, HTMLContent> {
         let mut metadata = OrderedMap::new();
@@ -125,7 +146,12 @@ mod tests {
         crate::environment::mock_environment().unwrap();
 
         let mut metadata = metadata_with_slug("index");
-        parse_metadata("page-title: 中文", &mut metadata).unwrap();
+        parse_metadata(
+            MetadataBlockKind::YamlStyle,
+            "page-title: 中文",
+            &mut metadata,
+        )
+        .unwrap();
 
         let parsed = metadata
             .get(KEY_PAGE_TITLE)
@@ -141,7 +167,7 @@ mod tests {
         crate::environment::mock_environment().unwrap();
 
         let mut metadata = metadata_with_slug("index");
-        parse_metadata("title: 中文", &mut metadata).unwrap();
+        parse_metadata(MetadataBlockKind::YamlStyle, "title: 中文", &mut metadata).unwrap();
 
         let parsed = metadata
             .get(KEY_TITLE)
@@ -156,7 +182,7 @@ mod tests {
         crate::environment::mock_environment().unwrap();
 
         let mut metadata = metadata_with_slug("index");
-        parse_metadata("taxon: remark", &mut metadata).unwrap();
+        parse_metadata(MetadataBlockKind::YamlStyle, "taxon: remark", &mut metadata).unwrap();
 
         let parsed = metadata
             .get(KEY_TAXON)
@@ -165,4 +191,49 @@ mod tests {
             .to_string();
         assert_eq!(parsed, "Remark. ");
     }
+
+    fn parse_frontmatter(source: &str) -> OrderedMap<String, HTMLContent> {
+        let mut metadata = metadata_with_slug("index");
+        let events = pulldown_cmark::Parser::new_ext(source, crate::compiler::parser::OPTIONS);
+        for event in Metadata::process(events, &mut metadata) {
+            event.unwrap();
+        }
+        metadata
+    }
+
+    #[test]
+    fn test_yaml_lists_are_structured() {
+        crate::environment::mock_environment().unwrap();
+
+        let metadata = parse_frontmatter("---\ntitle: T\ntags:\n  - a\n  - b\n---\n\nBody\n");
+        let tags = metadata.get("tags").unwrap();
+        assert_eq!(
+            serde_json::to_string(tags).unwrap(),
+            r#"{"Structured":["a","b"]}"#
+        );
+        assert_eq!(tags.remove_all_tags(), "a, b");
+    }
+
+    #[test]
+    fn test_toml_frontmatter_keeps_dates_and_key_order() {
+        crate::environment::mock_environment().unwrap();
+
+        let metadata = parse_frontmatter(
+            "+++\ntaxon = \"remark\"\ndate = 2025-01-31\nauthors = [\"A\", \"B\"]\n+++\n\nBody\n",
+        );
+        let keys: Vec<_> = metadata.keys().map(String::as_str).collect();
+        assert_eq!(keys, ["slug", "taxon", "date", "authors"]);
+        assert_eq!(
+            metadata.get("date").and_then(HTMLContent::as_str),
+            Some("2025-01-31")
+        );
+        assert_eq!(
+            metadata.get(KEY_TAXON).and_then(HTMLContent::as_str),
+            Some("Remark. ")
+        );
+        assert!(matches!(
+            metadata.get("authors"),
+            Some(HTMLContent::Structured(MetaValue::List(_)))
+        ));
+    }
 }
@@ -8,6 +8,11 @@ pub mod content;
 pub mod embed_markdown;
 pub mod figure;
 pub mod footnote;
+mod frontmatter;
+mod include_excerpt;
+mod latex_shim;
+pub mod link_action;
+pub mod markdown_math;
 pub mod metadata;
 pub mod path_resolution;
 pub mod processor;
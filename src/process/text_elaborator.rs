@@ -167,6 +167,49 @@ impl<E> TextElaborator<'_, E> {
     }
 }
 
+/// Words in a text, counted separately for reading speed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct WordCount {
+    /// Space-separated words, including Korean words.
+    pub words: usize,
+    /// Han and Japanese characters, each counted as one word.
+    pub cjk_chars: usize,
+}
+
+impl WordCount {
+    pub fn total(&self) -> usize {
+        self.words + self.cjk_chars
+    }
+}
+
+/// Count words using the same character classes as language tagging.
+pub fn count_words(text: &str) -> WordCount {
+    let mut count = WordCount::default();
+    let mut in_word = false;
+    for ch in text.chars() {
+        match classify_char(ch) {
+            CharClass::Han | CharClass::Japanese => {
+                count.cjk_chars += 1;
+                in_word = false;
+            }
+            CharClass::Common => in_word = false,
+            CharClass::Korean | CharClass::Other => {
+                // Apostrophes and hyphens join the parts of a word.
+                let joins = in_word && matches!(ch, '\'' | '\u{2019}' | '-');
+                if ch.is_alphanumeric() || joins {
+                    if !in_word {
+                        count.words += 1;
+                        in_word = true;
+                    }
+                } else {
+                    in_word = false;
+                }
+            }
+        }
+    }
+    count
+}
+
 fn contains_cjk_related(text: &str) -> bool {
     text.chars().any(|ch| classify_char(ch) != CharClass::Other)
 }
@@ -311,4 +354,18 @@ mod tests {
         assert_text(&actual[1], "中文");
         assert!(matches!(actual[2], Event::End(TagEnd::CodeBlock)));
     }
+
+    #[test]
+    fn test_count_words_counts_cjk_characters_and_spaced_words() {
+        let count = count_words("Don't panic, well-known 中文かな 한글 단어.");
+        assert_eq!(
+            count,
+            WordCount {
+                words: 5,
+                cjk_chars: 4
+            }
+        );
+        assert_eq!(count.total(), 9);
+        assert_eq!(count_words("  \n").total(), 0);
+    }
 }
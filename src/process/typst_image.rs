@@ -8,12 +8,14 @@ use std::{
     sync::atomic::{AtomicBool, Ordering},
 };
 
-use camino::Utf8PathBuf;
+use camino::{Utf8Path, Utf8PathBuf};
 use pulldown_cmark::{Event, Tag, TagEnd};
 
 use crate::{
+    compiler,
     environment::{self, output_path},
     html_flake::{html_typst_figure, html_figure_code},
+    path_utils,
     recorder::State,
     slug::Slug,
     typst_cli::{self, write_to_inline_html},
@@ -34,7 +36,7 @@ pub fn typst_image_error_detected() -> bool {
     TYPEST_IMAGE_ERROR_FLAG.load(Ordering::Relaxed)
 }
 
-fn record_typst_image_error() {
+pub(crate) fn record_typst_image_error() {
     TYPEST_IMAGE_ERROR_FLAG.store(true, Ordering::Relaxed);
 }
 
@@ -111,9 +113,10 @@ impl<'e, E: Iterator<Item = Event<'e>>> Iterator for TypstImage<E> {
                     State::Html => {
                         let typst_url =
                             typst_path(self.current_slug, &self.url.take().unwrap_or_default());
+                        compiler::record_dependency(typst_url.clone());
                         let html = if environment::is_check() {
-                            let trees_dir = environment::trees_dir();
-                            match typst_cli::file_to_html(typst_url.as_str(), trees_dir.as_str()) {
+                            let (mount, inner) = environment::locate_tree_path(&typst_url);
+                            match typst_cli::file_to_html(inner.as_str(), mount.dir.as_str()) {
                                 Ok(inline_html) => inline_html,
                                 Err(err) => {
                                     record_typst_image_error();
@@ -187,6 +190,7 @@ impl<'e, E: Iterator<Item = Event<'e>>> Iterator for TypstImage<E> {
                     State::ImageSpan => {
                         let typst_url =
                             typst_path(self.current_slug, &self.url.take().unwrap_or_default());
+                        compiler::record_dependency(typst_url.clone());
                         let caption = self.content.take().unwrap_or_default();
                         let svg_url = typst_url.with_extension("svg");
                         self.exit();
@@ -197,6 +201,7 @@ impl<'e, E: Iterator<Item = Event<'e>>> Iterator for TypstImage<E> {
                     State::ImageBlock => {
                         let typst_url =
                             typst_path(self.current_slug, &self.url.take().unwrap_or_default());
+                        compiler::record_dependency(typst_url.clone());
                         let caption = self.content.take().unwrap_or_default();
                         let svg_url = typst_url.with_extension("svg");
                         self.exit();
@@ -207,12 +212,13 @@ impl<'e, E: Iterator<Item = Event<'e>>> Iterator for TypstImage<E> {
                     State::ImageCode => {
                         let typst_url =
                             typst_path(self.current_slug, &self.url.take().unwrap_or_default());
+                        compiler::record_dependency(typst_url.clone());
+                        compiler::record_dependency(Utf8PathBuf::from(format!("{typst_url}.code")));
                         let caption = self.content.take().unwrap_or_default();
                         let svg_url = typst_url.with_extension("svg");
                         self.exit();
 
-                        let root_dir = environment::trees_dir();
-                        let full_path = root_dir.join(typst_url);
+                        let full_path = environment::input_path(typst_url);
                         let code = fs::read_to_string(format!("{}.code", full_path))
                             .or_else(|_| fs::read_to_string(&full_path))
                             .unwrap_or_else(|err| {
@@ -243,6 +249,11 @@ impl<'e, E: Iterator<Item = Event<'e>>> Iterator for TypstImage<E> {
                          * but we recommend users to manually enter "*" to avoid ambiguity.
                          */
                         let imported = imported.as_ref().map_or("*", |s| s);
+                        if !typst_url.starts_with('@') {
+                            // Inline Typst is compiled with the trees directory as its root.
+                            let path = path_utils::pretty_path(Utf8Path::new(&typst_url));
+                            compiler::record_dependency(Utf8PathBuf::from(path));
+                        }
                         self.shareds
                             .push(format!(r#"#import "{typst_url}": {imported}"#));
 
@@ -13,6 +13,9 @@ pub enum State {
     /// Include contents
     Include,
 
+    /// Markdown spliced into the source before parsing
+    TranscludeRaw,
+
     /// Shared for inline typst
     Shared,
 
@@ -43,6 +46,7 @@ impl State {
             State::None => "none",
             State::Embed => "embed",
             State::Include => "include",
+            State::TranscludeRaw => "transclude-raw",
             State::Shared => "shared",
             State::Html => "html",
             State::InlineTypst => "inline",
@@ -56,3 +60,20 @@ impl State {
         }
     }
 }
+
+/// Link actions handled by Kodama itself. `[actions]` entries cannot override them.
+pub const BUILTIN_ACTIONS: [State; 9] = [
+    State::Embed,
+    State::Include,
+    State::TranscludeRaw,
+    State::Shared,
+    State::Html,
+    State::InlineTypst,
+    State::ImageSpan,
+    State::ImageBlock,
+    State::ImageCode,
+];
+
+pub fn is_builtin_action(action: &str) -> bool {
+    BUILTIN_ACTIONS.iter().any(|state| state.strify() == action)
+}
@@ -2,7 +2,7 @@
 // Released under the GPL-3.0 license as described in the file LICENSE.
 // Authors: Kokic (@kokic), Spore (@s-cerevisiae)
 
-use std::{fs, io::Write, process::Command};
+use std::{fs, io::Write, process::Command, sync::OnceLock};
 
 use camino::Utf8Path;
 use eyre::{eyre, WrapErr};
@@ -19,7 +19,7 @@ pub fn write_to_inline_html<P: AsRef<Utf8Path>>(
     if !verify_and_file_hash(typst_path.as_ref())? && html_path.as_ref().exists() {
         let existed_html = fs::read_to_string(html_path.as_ref())?;
         if let Ok(existed_html) = html_to_body_content(&existed_html) {
-            if *crate::cli::build::verbose_skip() {
+            if crate::cli::build::verbose_skip() {
                 println!("Skip: {}", path_utils::pretty_path(typst_path.as_ref()));
             }
             return Ok(existed_html);
@@ -30,12 +30,12 @@ pub fn write_to_inline_html<P: AsRef<Utf8Path>>(
         );
     }
 
-    let root_dir = environment::trees_dir();
-    let html = to_html_string(typst_path.as_ref(), &root_dir)?;
+    let (mount, inner) = environment::locate_tree_path(typst_path.as_ref());
+    let html = to_html_string(inner.as_path(), mount.dir.as_path())?;
     let html_body = html_to_body_content(&html)?;
 
     fs::write(html_path.as_ref(), html)?;
-    if *crate::cli::build::verbose() {
+    if crate::cli::build::verbose() {
         println!(
             "Compiled to HTML: {}",
             path_utils::pretty_path(html_path.as_ref())
@@ -44,6 +44,20 @@ pub fn write_to_inline_html<P: AsRef<Utf8Path>>(
     Ok(html_body)
 }
 
+/// The `typst --version` line, read once per run. Empty when Typst is missing.
+pub fn version() -> &'static str {
+    static VERSION: OnceLock<String> = OnceLock::new();
+    VERSION.get_or_init(|| {
+        Command::new("typst")
+            .arg("--version")
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .unwrap_or_default()
+    })
+}
+
 pub fn html_to_body_content(html: &str) -> eyre::Result<String> {
     let start_pos = html
         .find("<html>")
@@ -83,6 +97,27 @@ pub fn source_to_inline_svg(src: &str) -> eyre::Result<String> {
     ))
 }
 
+/// Compile a display equation such as `$ x^2 $`, keeping only its SVG frame.
+pub fn source_to_display_svg(src: &str) -> eyre::Result<String> {
+    let kodama_header = include_str!("include/kodama.typ");
+    let html = source_to_html(format!("{}\n#show: kodama\n{}", kodama_header, src).as_str())?;
+
+    let start_pos = html
+        .find("<svg")
+        .ok_or_else(|| eyre!("missing `<svg>` tag in typst display svg output"))?;
+    let end_pos = html
+        .rfind("</svg>")
+        .map(|pos| pos + 6)
+        .ok_or_else(|| eyre!("missing `</svg>` tag in typst display svg output"))?;
+    if end_pos < start_pos {
+        return Err(eyre!("malformed svg range in typst display svg output"));
+    }
+
+    Ok(html_flake::html_display_typst_span(
+        &html[start_pos..end_pos],
+    ))
+}
+
 pub fn file_to_html(rel_path: &str, root_dir: &str) -> eyre::Result<String> {
     to_html_string(rel_path, root_dir)
         .and_then(|s| html_to_body_content(&s))
@@ -169,24 +204,24 @@ pub fn write_svg<P: AsRef<Utf8Path>>(typst_path: P, svg_path: P) -> eyre::Result
     let svg_path = svg_path.as_ref();
 
     if !verify_and_file_hash(typst_path)? && svg_path.exists() {
-        if *crate::cli::build::verbose_skip() {
+        if crate::cli::build::verbose_skip() {
             println!("Skip: {}", path_utils::pretty_path(typst_path));
         }
         return Ok(());
     }
 
-    let trees_dir = environment::trees_dir();
-    let full_path = trees_dir.join(typst_path);
+    let (mount, inner) = environment::locate_tree_path(typst_path);
+    let full_path = mount.dir.join(inner);
     let output = Command::new("typst")
         .arg("c")
         .arg("-f=svg")
-        .arg(format!("--root={}", trees_dir))
+        .arg(format!("--root={}", mount.dir))
         .arg(&full_path)
         .arg(svg_path)
         .output()?;
 
     if output.status.success() {
-        if *crate::cli::build::verbose() {
+        if crate::cli::build::verbose() {
             println!(
                 "Compiled to SVG: {}",
                 path_utils::pretty_path(Utf8Path::new(svg_path))
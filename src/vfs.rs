@@ -0,0 +1,411 @@
+// Copyright (c) 2025 Kodama Project. All rights reserved.
+// Released under the GPL-3.0 license as described in the file LICENSE.
+// Authors: Kokic (@kokic)
+
+//! The file system seen by scanning, parsing, caching and writing.
+//!
+//! [`DiskFs`] is the real disk. [`MemoryFs`] keeps every file in memory, so a
+//! forest can be compiled from strings. Typst sources and Typst math still go
+//! through the `typst` binary, which only reads the disk.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    io::{self, ErrorKind, Write},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        PoisonError, RwLock,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Metadata {
+    pub is_dir: bool,
+    pub len: u64,
+    pub modified: SystemTime,
+    /// Not every file system records creation times.
+    pub created: Option<SystemTime>,
+}
+
+pub trait FileSystem: Send + Sync {
+    fn read(&self, path: &Utf8Path) -> io::Result<Vec<u8>>;
+
+    /// Replace the file, creating its parent directories.
+    fn write(&self, path: &Utf8Path, contents: &[u8]) -> io::Result<()>;
+
+    /// Like [`FileSystem::write`], but readers never see a partly written file.
+    fn write_atomic(&self, path: &Utf8Path, contents: &[u8]) -> io::Result<()> {
+        self.write(path, contents)
+    }
+
+    fn remove_file(&self, path: &Utf8Path) -> io::Result<()>;
+
+    fn remove_dir_all(&self, path: &Utf8Path) -> io::Result<()>;
+
+    fn create_dir_all(&self, path: &Utf8Path) -> io::Result<()>;
+
+    /// The entries of a directory, sorted.
+    fn read_dir(&self, path: &Utf8Path) -> io::Result<Vec<Utf8PathBuf>>;
+
+    /// Follows symbolic links.
+    fn metadata(&self, path: &Utf8Path) -> io::Result<Metadata>;
+
+    /// The path with symbolic links resolved. File systems without links
+    /// return it unchanged.
+    fn canonicalize(&self, path: &Utf8Path) -> io::Result<Utf8PathBuf> {
+        Ok(path.to_owned())
+    }
+
+    fn read_to_string(&self, path: &Utf8Path) -> io::Result<String> {
+        String::from_utf8(self.read(path)?)
+            .map_err(|err| io::Error::new(ErrorKind::InvalidData, err))
+    }
+
+    fn exists(&self, path: &Utf8Path) -> bool {
+        self.metadata(path).is_ok()
+    }
+
+    fn is_file(&self, path: &Utf8Path) -> bool {
+        self.metadata(path).is_ok_and(|meta| !meta.is_dir)
+    }
+
+    fn is_dir(&self, path: &Utf8Path) -> bool {
+        self.metadata(path).is_ok_and(|meta| meta.is_dir)
+    }
+}
+
+/// Every file below `dir`, sorted, or nothing if `dir` does not exist.
+pub fn walk_files(fs: &dyn FileSystem, dir: &Utf8Path) -> io::Result<Vec<Utf8PathBuf>> {
+    let mut files = Vec::new();
+    if !fs.is_dir(dir) {
+        return Ok(files);
+    }
+    let mut pending = vec![dir.to_owned()];
+    while let Some(dir) = pending.pop() {
+        for path in fs.read_dir(&dir)? {
+            match fs.is_dir(&path) {
+                true => pending.push(path),
+                false => files.push(path),
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DiskFs;
+
+static ATOMIC_WRITE_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+impl FileSystem for DiskFs {
+    fn read(&self, path: &Utf8Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn write(&self, path: &Utf8Path, contents: &[u8]) -> io::Result<()> {
+        create_parent_dir(path)?;
+        fs::write(path, contents)
+    }
+
+    fn write_atomic(&self, path: &Utf8Path, contents: &[u8]) -> io::Result<()> {
+        create_parent_dir(path)?;
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "path has no file name"))?;
+        let temp_path = path.with_file_name(format!(
+            "{file_name}.tmp.{}.{}",
+            std::process::id(),
+            next_atomic_write_stamp()
+        ));
+
+        let write_temp = || -> io::Result<()> {
+            let mut file = fs::File::create(&temp_path)?;
+            file.write_all(contents)?;
+            file.sync_all()
+        };
+        if let Err(err) = write_temp().and_then(|()| fs::rename(&temp_path, path)) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Utf8Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn remove_dir_all(&self, path: &Utf8Path) -> io::Result<()> {
+        fs::remove_dir_all(path)
+    }
+
+    fn create_dir_all(&self, path: &Utf8Path) -> io::Result<()> {
+        fs::create_dir_all(path)
+    }
+
+    fn read_dir(&self, path: &Utf8Path) -> io::Result<Vec<Utf8PathBuf>> {
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(path)? {
+            let std_path = entry?.path();
+            match Utf8PathBuf::from_path_buf(std_path) {
+                Ok(path) => entries.push(path),
+                Err(non_utf8) => {
+                    color_print::ceprintln!(
+                        "<y>Warning: skipping non-UTF-8 path `{}`.</>",
+                        non_utf8.display()
+                    );
+                }
+            }
+        }
+        entries.sort();
+        Ok(entries)
+    }
+
+    fn metadata(&self, path: &Utf8Path) -> io::Result<Metadata> {
+        let meta = fs::metadata(path)?;
+        Ok(Metadata {
+            is_dir: meta.is_dir(),
+            len: meta.len(),
+            modified: meta.modified()?,
+            created: meta.created().ok(),
+        })
+    }
+
+    fn canonicalize(&self, path: &Utf8Path) -> io::Result<Utf8PathBuf> {
+        path.canonicalize_utf8()
+    }
+}
+
+fn create_parent_dir(path: &Utf8Path) -> io::Result<()> {
+    match path.parent() {
+        Some(parent) if !parent.as_str().is_empty() => fs::create_dir_all(parent),
+        _ => Ok(()),
+    }
+}
+
+fn next_atomic_write_stamp() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let sequence = ATOMIC_WRITE_SEQUENCE.fetch_add(1, Ordering::Relaxed);
+    format!("{nanos}-{sequence}")
+}
+
+/// Files held in memory. Paths are compared after removing `.` and resolving
+/// `..`, and directories exist as long as they contain a file or were created.
+#[derive(Debug, Default)]
+pub struct MemoryFs {
+    inner: RwLock<MemoryTree>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryTree {
+    files: BTreeMap<Utf8PathBuf, MemoryFile>,
+    dirs: BTreeSet<Utf8PathBuf>,
+}
+
+#[derive(Debug)]
+struct MemoryFile {
+    contents: Vec<u8>,
+    modified: SystemTime,
+}
+
+impl MemoryFs {
+    pub fn new() -> MemoryFs {
+        MemoryFs::default()
+    }
+
+    /// Add or replace a file.
+    pub fn insert(&self, path: impl AsRef<Utf8Path>, contents: impl Into<Vec<u8>>) {
+        let _ = self.write(path.as_ref(), &contents.into());
+    }
+
+    /// All files, sorted.
+    pub fn paths(&self) -> Vec<Utf8PathBuf> {
+        self.read_tree().files.keys().cloned().collect()
+    }
+
+    fn read_tree(&self) -> std::sync::RwLockReadGuard<'_, MemoryTree> {
+        self.inner.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write_tree(&self) -> std::sync::RwLockWriteGuard<'_, MemoryTree> {
+        self.inner.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl MemoryTree {
+    fn is_dir(&self, path: &Utf8Path) -> bool {
+        path.as_str().is_empty()
+            || self.dirs.contains(path)
+            || self
+                .files
+                .keys()
+                .any(|file| file.starts_with(path) && file != path)
+            || self.dirs.iter().any(|dir| dir.starts_with(path))
+    }
+}
+
+fn normalize(path: &Utf8Path) -> Utf8PathBuf {
+    let mut normalized = Utf8PathBuf::new();
+    for component in path.components() {
+        match component {
+            Utf8Component::CurDir => {}
+            Utf8Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+fn not_found(path: &Utf8Path) -> io::Error {
+    io::Error::new(ErrorKind::NotFound, format!("`{}` does not exist", path))
+}
+
+impl FileSystem for MemoryFs {
+    fn read(&self, path: &Utf8Path) -> io::Result<Vec<u8>> {
+        self.read_tree()
+            .files
+            .get(&normalize(path))
+            .map(|file| file.contents.clone())
+            .ok_or_else(|| not_found(path))
+    }
+
+    fn write(&self, path: &Utf8Path, contents: &[u8]) -> io::Result<()> {
+        let path = normalize(path);
+        let mut tree = self.write_tree();
+        if tree.is_dir(&path) {
+            return Err(io::Error::new(
+                ErrorKind::IsADirectory,
+                format!("`{}` is a directory", path),
+            ));
+        }
+        let file = MemoryFile {
+            contents: contents.to_vec(),
+            modified: SystemTime::now(),
+        };
+        tree.files.insert(path, file);
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Utf8Path) -> io::Result<()> {
+        let mut tree = self.write_tree();
+        tree.files
+            .remove(&normalize(path))
+            .map(|_| ())
+            .ok_or_else(|| not_found(path))
+    }
+
+    fn remove_dir_all(&self, path: &Utf8Path) -> io::Result<()> {
+        let dir = normalize(path);
+        let mut tree = self.write_tree();
+        if !tree.is_dir(&dir) {
+            return Err(not_found(path));
+        }
+        tree.files.retain(|file, _| !file.starts_with(&dir));
+        tree.dirs.retain(|other| !other.starts_with(&dir));
+        Ok(())
+    }
+
+    fn create_dir_all(&self, path: &Utf8Path) -> io::Result<()> {
+        let dir = normalize(path);
+        let mut tree = self.write_tree();
+        if tree.files.contains_key(&dir) {
+            return Err(io::Error::new(
+                ErrorKind::AlreadyExists,
+                format!("`{}` is a file", path),
+            ));
+        }
+        tree.dirs.insert(dir);
+        Ok(())
+    }
+
+    fn read_dir(&self, path: &Utf8Path) -> io::Result<Vec<Utf8PathBuf>> {
+        let dir = normalize(path);
+        let tree = self.read_tree();
+        if !tree.is_dir(&dir) {
+            return Err(not_found(path));
+        }
+        let children: BTreeSet<Utf8PathBuf> = tree
+            .files
+            .keys()
+            .chain(tree.dirs.iter())
+            .filter_map(|entry| {
+                let relative = entry.strip_prefix(&dir).ok()?;
+                let first = relative.components().next()?;
+                Some(path.join(first))
+            })
+            .collect();
+        Ok(children.into_iter().collect())
+    }
+
+    fn metadata(&self, path: &Utf8Path) -> io::Result<Metadata> {
+        let normalized = normalize(path);
+        let tree = self.read_tree();
+        if let Some(file) = tree.files.get(&normalized) {
+            return Ok(Metadata {
+                is_dir: false,
+                len: file.contents.len() as u64,
+                modified: file.modified,
+                created: None,
+            });
+        }
+        match tree.is_dir(&normalized) {
+            true => Ok(Metadata {
+                is_dir: true,
+                len: 0,
+                modified: UNIX_EPOCH,
+                created: None,
+            }),
+            false => Err(not_found(path)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_fs_has_implicit_directories() {
+        let fs = MemoryFs::new();
+        fs.insert("site/trees/index.md", "# Home");
+        fs.insert("site/./trees/notes/a.md", "A");
+
+        assert_eq!(
+            fs.read_to_string(Utf8Path::new("site/trees/../trees/index.md"))
+                .unwrap(),
+            "# Home"
+        );
+        assert!(fs.is_dir(Utf8Path::new("site/trees")));
+        assert!(fs.is_file(Utf8Path::new("site/trees/notes/a.md")));
+        assert!(!fs.exists(Utf8Path::new("site/tree")));
+        assert_eq!(
+            fs.read_dir(Utf8Path::new("site/trees")).unwrap(),
+            ["site/trees/index.md", "site/trees/notes"].map(Utf8PathBuf::from)
+        );
+        assert_eq!(
+            walk_files(&fs, Utf8Path::new("site")).unwrap(),
+            ["site/trees/index.md", "site/trees/notes/a.md"].map(Utf8PathBuf::from)
+        );
+        assert!(fs.write(Utf8Path::new("site/trees"), b"").is_err());
+
+        fs.create_dir_all(Utf8Path::new("site/.cache")).unwrap();
+        assert!(fs.is_dir(Utf8Path::new("site/.cache")));
+        fs.remove_dir_all(Utf8Path::new("site/trees/notes"))
+            .unwrap();
+        assert_eq!(fs.paths(), [Utf8PathBuf::from("site/trees/index.md")]);
+        let err = fs
+            .remove_file(Utf8Path::new("site/missing.md"))
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+    }
+}